@@ -0,0 +1,92 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use surreal_error_parser::{dialect::Dialect, QueryError};
+
+const CORPUS: &[(&str, &str)] = &[
+    ("ignore", "Conditional clause is not truthy"),
+    (
+        "thrown",
+        "An error occurred: CART_EMPTY: the cart has no items",
+    ),
+    (
+        "tx_failure",
+        "There was an error when starting a new datastore transaction",
+    ),
+    (
+        "invalid_query",
+        "Parse error on line 1 at character 14 when parsing 'SELECT * FORM user'",
+    ),
+    ("tb_not_found", "The table 'product' does not exist"),
+    (
+        "index_exists",
+        "Database index `unique_sku` already contains 'ABC-123', with record `product:ulid`",
+    ),
+    (
+        "field_check",
+        "Found 'abc' for field `price`, with record `product:1`, but expected a decimal",
+    ),
+    (
+        "convert_to",
+        "Expected a int but cannot convert 'abc' into a int",
+    ),
+    (
+        "net_target_not_allowed",
+        "Access to network target 'example.com' is not allowed",
+    ),
+    (
+        "access_db_not_found",
+        "The access method 'account' does not exist in the database 'shop'",
+    ),
+    (
+        "invalid_query_detail",
+        "Parse error: Unexpected token `FORM`, expected FROM\n --> [1:10]\n  |\n1 | SELECT * FORM user",
+    ),
+    ("unmatched", "Something the parser has never seen before"),
+];
+
+fn from_string(c: &mut Criterion) {
+    let mut group = c.benchmark_group("from_string");
+
+    for (name, message) in CORPUS {
+        group.bench_function(*name, |b| {
+            b.iter(|| QueryError::from_string(black_box(message)))
+        });
+    }
+
+    group.finish();
+}
+
+fn from_string_corpus(c: &mut Criterion) {
+    let mut group = c.benchmark_group("from_string_corpus");
+
+    group.throughput(Throughput::Elements(CORPUS.len() as u64));
+    group.bench_function("all", |b| {
+        b.iter(|| {
+            for (_, message) in CORPUS {
+                black_box(QueryError::from_string(black_box(message)));
+            }
+        })
+    });
+
+    group.finish();
+}
+
+fn from_string_with(c: &mut Criterion) {
+    let mut group = c.benchmark_group("from_string_with");
+
+    group.throughput(Throughput::Elements(CORPUS.len() as u64));
+    group.bench_function("v2", |b| {
+        b.iter(|| {
+            for (_, message) in CORPUS {
+                black_box(QueryError::from_string_with(
+                    black_box(message),
+                    Dialect::V2,
+                ));
+            }
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, from_string, from_string_corpus, from_string_with);
+criterion_main!(benches);
@@ -0,0 +1,74 @@
+//! Reads log lines from the files given, or stdin, and prints how many of them were SurrealDB
+//! errors by category and by code. With `--matches`, every matched line is printed too
+//!
+//! ```sh
+//! journalctl -u surreal | classify-log
+//! ```
+
+use std::{fs, io::Read, process::ExitCode};
+
+use surreal_error_parser::QueryError;
+
+fn main() -> ExitCode {
+    let mut matches = false;
+    let mut paths = vec![];
+
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--matches" => matches = true,
+            _ => paths.push(arg),
+        }
+    }
+
+    let mut log = String::new();
+
+    if paths.is_empty() {
+        if let Err(error) = std::io::stdin().read_to_string(&mut log) {
+            eprintln!("error: couldn't read stdin: {}", error);
+            return ExitCode::FAILURE;
+        }
+    }
+
+    for path in &paths {
+        match fs::read_to_string(path) {
+            Ok(contents) => {
+                log.push_str(&contents);
+                log.push('\n');
+            }
+            Err(error) => {
+                eprintln!("error: couldn't read {}: {}", path, error);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    let mut lines = QueryError::parse_lines(log.lines());
+
+    for (line, error) in lines.by_ref() {
+        if let (true, Some(error)) = (matches, error) {
+            println!("{}: {} ({})", line, error.code(), error.category().as_str());
+        }
+    }
+
+    let summary = lines.summary();
+
+    println!("{} lines, {} errors", summary.lines, summary.matched);
+
+    for (title, counts) in [
+        ("by category", &summary.by_category),
+        ("by code", &summary.by_code),
+    ] {
+        let mut counts = counts.iter().collect::<Vec<_>>();
+
+        // Most frequent first, ties by name
+        counts.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+
+        println!("\n{}:", title);
+
+        for (name, count) in counts {
+            println!("  {:>6}  {}", count, name);
+        }
+    }
+
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,23 @@
+use alloc::{string::String, vec::Vec};
+use core::str::FromStr;
+
+/// Converts the text captured by a template var into a field of a `StrPattern` type. Every
+/// `FromStr` type gets it for free, so it only has to be implemented for field types that don't
+/// parse from any string, like a table name that can't be empty
+pub trait FromCapture: Sized {
+    /// Returns `None` when the capture isn't a valid value, which rejects the pattern
+    fn from_capture(capture: &str) -> Option<Self>;
+}
+
+impl<T: FromStr> FromCapture for T {
+    fn from_capture(capture: &str) -> Option<Self> {
+        capture.parse().ok()
+    }
+}
+
+/// Turns every run of whitespace into a single space and drops the whitespace around the text,
+/// for fields with `#[str_pattern(collapse_whitespace)]`. Messages relayed through some log
+/// pipelines gain padding and line breaks that aren't part of the value
+pub fn collapse_whitespace(capture: &str) -> String {
+    capture.split_whitespace().collect::<Vec<_>>().join(" ")
+}
@@ -0,0 +1,299 @@
+use std::time::Duration;
+
+use crate::QueryError;
+
+/// Broad classification of a [`QueryError`], so that retry and alerting logic
+/// can be written against a handful of categories instead of every variant
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Category {
+    /// Authentication failed or the credentials are unknown
+    Auth,
+    /// The session is authenticated but not allowed to do this
+    Permission,
+    /// A referenced resource (table, namespace, function, ...) does not exist
+    NotFound,
+    /// The write collided with existing data or a failed condition
+    Conflict,
+    /// The query or the data sent with it is invalid
+    Validation,
+    /// The failure is temporary and the query may succeed when retried
+    Transient,
+    /// The database itself failed
+    Internal,
+    /// Used internally by the database to control query execution
+    ControlFlow,
+}
+
+impl Category {
+    /// A snake case name, for labels and log fields
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Category::Auth => "auth",
+            Category::Permission => "permission",
+            Category::NotFound => "not_found",
+            Category::Conflict => "conflict",
+            Category::Validation => "validation",
+            Category::Transient => "transient",
+            Category::Internal => "internal",
+            Category::ControlFlow => "control_flow",
+        }
+    }
+}
+
+/// How loudly an error should be logged
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Severity {
+    /// Expected during normal query execution, e.g. a conditional clause that was not truthy
+    Info,
+    /// Caused by the request, or temporary and expected to succeed on retry
+    Warn,
+    /// The database itself failed
+    Error,
+}
+
+impl QueryError {
+    pub fn category(&self) -> Category {
+        match self {
+            QueryError::Ignore | QueryError::Break | QueryError::Continue | QueryError::Return => {
+                Category::ControlFlow
+            }
+
+            QueryError::InvalidAuth
+            | QueryError::UnknownAuth
+            | QueryError::NtNotFound { .. }
+            | QueryError::NlNotFound { .. }
+            | QueryError::DtNotFound { .. }
+            | QueryError::DlNotFound { .. }
+            | QueryError::StNotFound { .. }
+            | QueryError::IamError(_)
+            | QueryError::ExpiredSession
+            | QueryError::ExpiredToken
+            | QueryError::SignupQueryFailed
+            | QueryError::SigninQueryFailed
+            | QueryError::InvalidSignup
+            | QueryError::InvalidPass
+            | QueryError::MissingUserOrPass
+            | QueryError::AccessRootNotFound { .. }
+            | QueryError::AccessNsNotFound { .. }
+            | QueryError::AccessDbNotFound { .. }
+            | QueryError::AccessGrantRootNotFound { .. }
+            | QueryError::AccessGrantNsNotFound { .. }
+            | QueryError::AccessGrantDbNotFound { .. }
+            | QueryError::AccessNotFound
+            | QueryError::AccessMethodMismatch
+            | QueryError::AccessGrantRevoked
+            | QueryError::AccessRecordSignupQueryFailed
+            | QueryError::AccessRecordSigninQueryFailed
+            | QueryError::AccessRecordNoSignup
+            | QueryError::AccessRecordNoSignin => Category::Auth,
+
+            QueryError::NsNotAllowed { .. }
+            | QueryError::DbNotAllowed { .. }
+            | QueryError::TablePermissions { .. }
+            | QueryError::HttpDisabled
+            | QueryError::RealtimeDisabled
+            | QueryError::ScriptingNotAllowed
+            | QueryError::FunctionNotAllowed(_)
+            | QueryError::NetTargetNotAllowed(_)
+            | QueryError::ParamPermissions { .. }
+            | QueryError::FunctionPermissions { .. } => Category::Permission,
+
+            QueryError::NsNotFound { .. }
+            | QueryError::DbNotFound { .. }
+            | QueryError::FcNotFound { .. }
+            | QueryError::ScNotFound { .. }
+            | QueryError::NoScopeFound
+            | QueryError::NdNotFound { .. }
+            | QueryError::PaNotFound { .. }
+            | QueryError::TbNotFound { .. }
+            | QueryError::LvNotFound { .. }
+            | QueryError::LqNotFound { .. }
+            | QueryError::AzNotFound { .. }
+            | QueryError::IxNotFound { .. }
+            | QueryError::UserRootNotFound { .. }
+            | QueryError::UserNsNotFound { .. }
+            | QueryError::UserDbNotFound { .. }
+            | QueryError::NoRecordFound
+            | QueryError::EvNotFound { .. }
+            | QueryError::FdNotFound { .. }
+            | QueryError::MlNotFound { .. }
+            | QueryError::IdNotFound { .. } => Category::NotFound,
+
+            QueryError::TxConditionNotMet
+            | QueryError::TxKeyAlreadyExists
+            | QueryError::ClAlreadyExists { .. }
+            | QueryError::RecordExists { .. }
+            | QueryError::IndexExists { .. }
+            | QueryError::PatchTest { .. }
+            | QueryError::NsAlreadyExists { .. }
+            | QueryError::DbAlreadyExists { .. }
+            | QueryError::TbAlreadyExists { .. }
+            | QueryError::FdAlreadyExists { .. }
+            | QueryError::IxAlreadyExists { .. }
+            | QueryError::EvAlreadyExists { .. }
+            | QueryError::FcAlreadyExists { .. }
+            | QueryError::PaAlreadyExists { .. }
+            | QueryError::AzAlreadyExists { .. }
+            | QueryError::MlAlreadyExists { .. }
+            | QueryError::AccessRootAlreadyExists { .. }
+            | QueryError::AccessNsAlreadyExists { .. }
+            | QueryError::AccessDbAlreadyExists { .. }
+            | QueryError::UserRootAlreadyExists { .. }
+            | QueryError::UserNsAlreadyExists { .. }
+            | QueryError::UserDbAlreadyExists { .. } => Category::Conflict,
+
+            QueryError::Thrown(_)
+            | QueryError::TxKeyTooLarge
+            | QueryError::TxValueTooLarge
+            | QueryError::TxTooLarge
+            | QueryError::NsEmpty
+            | QueryError::DbEmpty
+            | QueryError::QueryEmpty
+            | QueryError::QueryRemaining
+            | QueryError::InvalidQuery { .. }
+            | QueryError::InvalidQueryDetail { .. }
+            | QueryError::InvalidPatch { .. }
+            | QueryError::InvalidParam { .. }
+            | QueryError::InvalidField { .. }
+            | QueryError::InvalidSplit { .. }
+            | QueryError::InvalidOrder { .. }
+            | QueryError::InvalidGroup { .. }
+            | QueryError::InvalidLimit { .. }
+            | QueryError::InvalidStart { .. }
+            | QueryError::InvalidScript { .. }
+            | QueryError::InvalidFunction { .. }
+            | QueryError::InvalidArguments { .. }
+            | QueryError::InvalidUrl(_)
+            | QueryError::ComputationDepthExceeded
+            | QueryError::InvalidStatementTarget { .. }
+            | QueryError::CreateStatement { .. }
+            | QueryError::UpdateStatement { .. }
+            | QueryError::RelateStatement { .. }
+            | QueryError::DeleteStatement { .. }
+            | QueryError::InsertStatement { .. }
+            | QueryError::LiveStatement { .. }
+            | QueryError::KillStatement { .. }
+            | QueryError::TableIsView { .. }
+            | QueryError::FieldCheck { .. }
+            | QueryError::FieldValue { .. }
+            | QueryError::IdMismatch { .. }
+            | QueryError::IdInvalid { .. }
+            | QueryError::CoerceTo { .. }
+            | QueryError::ConvertTo { .. }
+            | QueryError::LengthInvalid { .. }
+            | QueryError::TryAdd(_, _)
+            | QueryError::TrySub(_, _)
+            | QueryError::TryMul(_, _)
+            | QueryError::TryDiv(_, _)
+            | QueryError::TryPow(_, _)
+            | QueryError::TryNeg(_)
+            | QueryError::TryFrom(_, _)
+            | QueryError::NoIndexFoundForMatch { .. }
+            | QueryError::AnalyzerError(_)
+            | QueryError::HighlightError(_)
+            | QueryError::FeatureNotYetImplemented { .. }
+            | QueryError::DuplicatedMatchRef { .. }
+            | QueryError::InvalidLevel(_)
+            | QueryError::UpsertStatement { .. }
+            | QueryError::RelateStatementIn { .. }
+            | QueryError::RelateStatementId { .. }
+            | QueryError::RelateStatementOut { .. }
+            | QueryError::InsertStatementIn { .. }
+            | QueryError::InsertStatementId { .. }
+            | QueryError::InsertStatementOut { .. }
+            | QueryError::InvalidContent { .. }
+            | QueryError::InvalidMerge { .. }
+            | QueryError::SingleOnlyOutput
+            | QueryError::FieldReadonly { .. }
+            | QueryError::FieldUndefined { .. }
+            | QueryError::SetCheck { .. }
+            | QueryError::TbInvalid { .. }
+            | QueryError::TryRem(_, _)
+            | QueryError::ArithmeticOverflow(_)
+            | QueryError::QueryTooLarge => Category::Validation,
+
+            // Statements that did not run because their transaction failed or was cancelled are
+            // transient too, since running the whole transaction again may succeed
+            QueryError::Ds(_)
+            | QueryError::Tx(_)
+            | QueryError::TxFailure
+            | QueryError::QueryTimedout
+            | QueryError::QueryCancelled
+            | QueryError::QueryNotExecutedDetail { .. }
+            | QueryError::QueryNotExecuted
+            | QueryError::Http(_)
+            | QueryError::Channel(_)
+            | QueryError::IndexAlreadyBuilding { .. } => Category::Transient,
+
+            QueryError::UnreachableDetail { .. }
+            | QueryError::Unreachable
+            | QueryError::TxFinished
+            | QueryError::TxReadonly
+            | QueryError::Io(_)
+            | QueryError::Encode(_)
+            | QueryError::Decode(_)
+            | QueryError::Revision(_)
+            | QueryError::CorruptedIndexDetail { .. }
+            | QueryError::CorruptedIndex
+            | QueryError::Bincode(_)
+            | QueryError::FstError(_)
+            | QueryError::Utf8Error(_)
+            | QueryError::TimestampOverflow(_)
+            | QueryError::Internal(_)
+            | QueryError::Unimplemented(_)
+            | QueryError::CorruptedVersionstampInKey(_)
+            | QueryError::Deprecated(_)
+            | QueryError::Unknown(_) => Category::Internal,
+        }
+    }
+
+    /// Whether running the same query again may succeed
+    pub fn is_retryable(&self) -> bool {
+        self.category() == Category::Transient
+    }
+
+    /// A suggested delay before retrying, or `None` when the error is not retryable. The delay
+    /// is meant as the base of a backoff, not as a fixed wait
+    pub fn retry_hint(&self) -> Option<Duration> {
+        if !self.is_retryable() {
+            return None;
+        }
+
+        let millis = match self {
+            // Transaction conflicts usually clear as soon as the other transaction commits
+            QueryError::Tx(_)
+            | QueryError::TxFailure
+            | QueryError::QueryCancelled
+            | QueryError::QueryNotExecutedDetail { .. }
+            | QueryError::QueryNotExecuted => 50,
+            QueryError::Channel(_) => 100,
+            QueryError::Ds(_) | QueryError::Http(_) => 500,
+            QueryError::QueryTimedout => 1_000,
+            QueryError::IndexAlreadyBuilding { .. } => 5_000,
+            _ => 100,
+        };
+
+        Some(Duration::from_millis(millis))
+    }
+
+    pub fn severity(&self) -> Severity {
+        match self.category() {
+            Category::ControlFlow => Severity::Info,
+            Category::Internal => Severity::Error,
+            // Transient errors are retried, so they only need attention when they keep happening
+            _ => Severity::Warn,
+        }
+    }
+
+    /// Whether the error was caused by the request rather than the database
+    pub fn is_client_error(&self) -> bool {
+        matches!(
+            self.category(),
+            Category::Auth
+                | Category::Permission
+                | Category::NotFound
+                | Category::Conflict
+                | Category::Validation
+        )
+    }
+}
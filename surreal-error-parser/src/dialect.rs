@@ -0,0 +1,10 @@
+/// The SurrealDB server generation that produced an error message. Patterns are tagged with the
+/// dialect they belong to, so [`QueryError::from_string_with`](crate::QueryError::from_string_with)
+/// can skip the wording used by the other generation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Dialect {
+    /// SurrealDB 1.x
+    V1,
+    /// SurrealDB 2.x
+    V2,
+}
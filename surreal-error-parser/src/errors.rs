@@ -0,0 +1,70 @@
+use std::fmt::Display;
+
+use crate::{category::Category, QueryError};
+
+/// The errors of all failed statements of a query, with the index of the statement that raised
+/// each of them
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct QueryErrors(pub Vec<(usize, QueryError)>);
+
+impl QueryErrors {
+    /// The first error that was not raised to control the query execution, which is usually the
+    /// one that caused the others
+    pub fn first_non_control_flow(&self) -> Option<&(usize, QueryError)> {
+        self.0
+            .iter()
+            .find(|(_, error)| error.category() != Category::ControlFlow)
+    }
+
+    /// Whether any of the errors may succeed when the query is run again
+    pub fn any_retryable(&self) -> bool {
+        self.0.iter().any(|(_, error)| error.is_retryable())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, (usize, QueryError)> {
+        self.0.iter()
+    }
+}
+
+impl From<Vec<(usize, QueryError)>> for QueryErrors {
+    fn from(errors: Vec<(usize, QueryError)>) -> Self {
+        Self(errors)
+    }
+}
+
+impl IntoIterator for QueryErrors {
+    type Item = (usize, QueryError);
+    type IntoIter = std::vec::IntoIter<(usize, QueryError)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl Display for QueryErrors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0.as_slice() {
+            [] => f.write_str("no statements failed"),
+            [(index, error)] => write!(f, "statement {index} failed: {error}"),
+            errors => {
+                write!(f, "{} statements failed", errors.len())?;
+
+                for (index, error) in errors {
+                    write!(f, "; statement {index}: {error}")?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for QueryErrors {}
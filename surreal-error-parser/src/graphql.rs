@@ -0,0 +1,20 @@
+use async_graphql::{Error, ErrorExtensions, Name, Value};
+
+use crate::QueryError;
+
+/// Adds the `code` and `fields` of the error as extensions. async-graphql already converts any
+/// `Display` type into an [`Error`], so resolvers opt into this with `.map_err(|e| e.extend())`
+impl ErrorExtensions for QueryError {
+    fn extend(&self) -> Error {
+        Error::new(self.to_string()).extend_with(|_, extensions| {
+            let fields = self
+                .fields()
+                .into_iter()
+                .map(|(name, value)| (Name::new(name), Value::from(value)))
+                .collect();
+
+            extensions.set("code", self.code());
+            extensions.set("fields", Value::Object(fields));
+        })
+    }
+}
@@ -0,0 +1,166 @@
+use crate::QueryError;
+
+/// The transaction errors of [`QueryError`]
+#[derive(Debug, PartialEq, Clone)]
+pub enum TxError {
+    /// A problem with the datastore transaction, with its message
+    Other(String),
+    Failure,
+    Finished,
+    Readonly,
+    ConditionNotMet,
+    KeyAlreadyExists,
+    KeyTooLarge,
+    ValueTooLarge,
+    TooLarge,
+}
+
+/// The authentication errors of [`QueryError`]
+#[derive(Debug, PartialEq, Clone)]
+pub enum AuthError {
+    InvalidAuth,
+    UnknownAuth,
+    ExpiredSession,
+    ExpiredToken,
+    /// The signup query of a scope or record access method failed
+    SignupFailed,
+    /// The signin query of a scope or record access method failed
+    SigninFailed,
+    /// The record access method does not allow signup
+    NoSignup,
+    /// The record access method does not allow signin
+    NoSignin,
+    InvalidSignup,
+    InvalidPass,
+    MissingUserOrPass,
+    AccessNotFound,
+    AccessMethodMismatch,
+    AccessGrantRevoked,
+    Iam(String),
+}
+
+/// The errors of [`QueryError`] about a schema resource that does not exist, with its name
+#[derive(Debug, PartialEq, Clone)]
+pub enum SchemaNotFound {
+    Namespace(String),
+    Database(String),
+    Table(String),
+    Field(String),
+    Index(String),
+    Event(String),
+    Function(String),
+    Param(String),
+    Analyzer(String),
+    Model(String),
+    Scope(String),
+}
+
+/// The errors of [`QueryError`] raised when a value has the wrong type
+#[derive(Debug, PartialEq, Clone)]
+pub enum TypeError {
+    FieldCheck {
+        thing: String,
+        value: String,
+        field: String,
+        check: String,
+    },
+    SetCheck {
+        value: String,
+        name: String,
+        check: String,
+    },
+    CoerceTo {
+        from: String,
+        into: String,
+    },
+    ConvertTo {
+        from: String,
+        into: String,
+    },
+    LengthInvalid {
+        kind: String,
+        size: usize,
+    },
+}
+
+impl QueryError {
+    pub fn as_tx_error(&self) -> Option<TxError> {
+        Some(match self {
+            QueryError::Tx(message) => TxError::Other(message.clone()),
+            QueryError::TxFailure => TxError::Failure,
+            QueryError::TxFinished => TxError::Finished,
+            QueryError::TxReadonly => TxError::Readonly,
+            QueryError::TxConditionNotMet => TxError::ConditionNotMet,
+            QueryError::TxKeyAlreadyExists => TxError::KeyAlreadyExists,
+            QueryError::TxKeyTooLarge => TxError::KeyTooLarge,
+            QueryError::TxValueTooLarge => TxError::ValueTooLarge,
+            QueryError::TxTooLarge => TxError::TooLarge,
+            _ => return None,
+        })
+    }
+
+    pub fn as_auth_error(&self) -> Option<AuthError> {
+        Some(match self {
+            QueryError::InvalidAuth => AuthError::InvalidAuth,
+            QueryError::UnknownAuth => AuthError::UnknownAuth,
+            QueryError::ExpiredSession => AuthError::ExpiredSession,
+            QueryError::ExpiredToken => AuthError::ExpiredToken,
+            QueryError::SignupQueryFailed | QueryError::AccessRecordSignupQueryFailed => {
+                AuthError::SignupFailed
+            }
+            QueryError::SigninQueryFailed | QueryError::AccessRecordSigninQueryFailed => {
+                AuthError::SigninFailed
+            }
+            QueryError::AccessRecordNoSignup => AuthError::NoSignup,
+            QueryError::AccessRecordNoSignin => AuthError::NoSignin,
+            QueryError::InvalidSignup => AuthError::InvalidSignup,
+            QueryError::InvalidPass => AuthError::InvalidPass,
+            QueryError::MissingUserOrPass => AuthError::MissingUserOrPass,
+            QueryError::AccessNotFound => AuthError::AccessNotFound,
+            QueryError::AccessMethodMismatch => AuthError::AccessMethodMismatch,
+            QueryError::AccessGrantRevoked => AuthError::AccessGrantRevoked,
+            QueryError::IamError(message) => AuthError::Iam(message.clone()),
+            _ => return None,
+        })
+    }
+
+    pub fn as_schema_not_found(&self) -> Option<SchemaNotFound> {
+        Some(match self {
+            QueryError::NsNotFound { value } => SchemaNotFound::Namespace(value.clone()),
+            QueryError::DbNotFound { value } => SchemaNotFound::Database(value.clone()),
+            QueryError::TbNotFound { value } => SchemaNotFound::Table(value.clone()),
+            QueryError::FdNotFound { value } => SchemaNotFound::Field(value.clone()),
+            QueryError::IxNotFound { value } => SchemaNotFound::Index(value.clone()),
+            QueryError::EvNotFound { value } => SchemaNotFound::Event(value.clone()),
+            QueryError::FcNotFound { value } => SchemaNotFound::Function(value.clone()),
+            QueryError::PaNotFound { value } => SchemaNotFound::Param(value.clone()),
+            QueryError::AzNotFound { value } => SchemaNotFound::Analyzer(value.clone()),
+            QueryError::MlNotFound { value } => SchemaNotFound::Model(value.clone()),
+            QueryError::ScNotFound { value } => SchemaNotFound::Scope(value.clone()),
+            _ => return None,
+        })
+    }
+
+    pub fn as_type_error(&self) -> Option<TypeError> {
+        Some(match self.clone() {
+            QueryError::FieldCheck {
+                thing,
+                value,
+                field,
+                check,
+            } => TypeError::FieldCheck {
+                thing,
+                value,
+                field,
+                check,
+            },
+            QueryError::SetCheck { value, name, check } => {
+                TypeError::SetCheck { value, name, check }
+            }
+            QueryError::CoerceTo { from, into } => TypeError::CoerceTo { from, into },
+            QueryError::ConvertTo { from, into } => TypeError::ConvertTo { from, into },
+            QueryError::LengthInvalid { kind, size } => TypeError::LengthInvalid { kind, size },
+            _ => return None,
+        })
+    }
+}
@@ -0,0 +1,38 @@
+use tonic::{metadata::MetadataValue, Code, Status};
+
+use crate::{category::Category, QueryError};
+
+/// Metadata key that carries [`QueryError::code`], so clients can match on the error without
+/// parsing the message
+pub const CODE_METADATA_KEY: &str = "x-error-code";
+
+impl QueryError {
+    /// The gRPC status code to respond with when this error is returned from a service
+    pub fn grpc_code(&self) -> Code {
+        if *self == QueryError::QueryTimedout {
+            return Code::DeadlineExceeded;
+        }
+
+        match self.category() {
+            Category::Auth => Code::Unauthenticated,
+            Category::Permission => Code::PermissionDenied,
+            Category::NotFound => Code::NotFound,
+            Category::Conflict => Code::AlreadyExists,
+            Category::Validation => Code::InvalidArgument,
+            Category::Transient => Code::Unavailable,
+            Category::Internal | Category::ControlFlow => Code::Internal,
+        }
+    }
+}
+
+impl From<QueryError> for Status {
+    fn from(error: QueryError) -> Self {
+        let mut status = Status::new(error.grpc_code(), error.to_string());
+
+        status
+            .metadata_mut()
+            .insert(CODE_METADATA_KEY, MetadataValue::from_static(error.code()));
+
+        status
+    }
+}
@@ -0,0 +1,63 @@
+use crate::{category::Category, QueryError};
+
+/// Content type of the body returned by [`QueryError::to_problem_details`]
+pub const PROBLEM_JSON_CONTENT_TYPE: &str = "application/problem+json";
+
+#[cfg(feature = "actix")]
+mod actix;
+#[cfg(feature = "axum")]
+mod axum;
+
+impl QueryError {
+    /// The HTTP status code to respond with when this error is returned from an API
+    pub fn http_status(&self) -> u16 {
+        match self.category() {
+            Category::Auth => 401,
+            Category::Permission => 403,
+            Category::NotFound => 404,
+            Category::Conflict => 409,
+            Category::Validation => 400,
+            Category::Transient => 503,
+            Category::Internal | Category::ControlFlow => 500,
+        }
+    }
+
+    /// The JSON body to respond with when this error is returned from an API, in the form
+    /// `{"code": ..., "message": ..., "fields": {...}}`
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> serde_json::Value {
+        let fields = self
+            .fields()
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), serde_json::Value::String(value)))
+            .collect::<serde_json::Map<_, _>>();
+
+        serde_json::json!({
+            "code": self.code(),
+            "message": self.to_string(),
+            "fields": fields,
+        })
+    }
+
+    /// An RFC 7807 problem details body for this error. The `type` is the code appended to
+    /// `base_uri`, and the parsed fields are added as extension members
+    #[cfg(feature = "serde")]
+    pub fn to_problem_details(&self, base_uri: &str) -> serde_json::Value {
+        let mut problem = serde_json::Map::new();
+
+        problem.insert(
+            "type".to_string(),
+            format!("{}/{}", base_uri.trim_end_matches('/'), self.code()).into(),
+        );
+        problem.insert("title".to_string(), self.code().into());
+        problem.insert("status".to_string(), self.http_status().into());
+        problem.insert("detail".to_string(), self.to_string().into());
+
+        // Fields never replace the members defined by the RFC
+        for (name, value) in self.fields() {
+            problem.entry(name).or_insert(value.into());
+        }
+
+        serde_json::Value::Object(problem)
+    }
+}
@@ -0,0 +1,13 @@
+use actix_web::{http::StatusCode, HttpResponse, ResponseError};
+
+use crate::QueryError;
+
+impl ResponseError for QueryError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::from_u16(self.http_status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code()).json(self.to_json())
+    }
+}
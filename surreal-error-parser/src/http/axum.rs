@@ -0,0 +1,16 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+
+use crate::QueryError;
+
+impl IntoResponse for QueryError {
+    fn into_response(self) -> Response {
+        let status =
+            StatusCode::from_u16(self.http_status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+
+        (status, Json(self.to_json())).into_response()
+    }
+}
@@ -0,0 +1,130 @@
+use crate::QueryError;
+
+/// A SurrealQL type, parsed from the type named by a type check error
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Kind {
+    Any,
+    Null,
+    Bool,
+    Bytes,
+    Datetime,
+    Decimal,
+    Duration,
+    Float,
+    Int,
+    Number,
+    Object,
+    Point,
+    String,
+    Uuid,
+    /// `record<a | b>`, with the allowed tables. Empty when any table is allowed
+    Record(Vec<String>),
+    /// `geometry<a | b>`, with the allowed geometry types. Empty when any type is allowed
+    Geometry(Vec<String>),
+    Option(Box<Kind>),
+    /// `a | b`
+    Either(Vec<Kind>),
+    /// `set<kind, max>`
+    Set(Box<Kind>, Option<u64>),
+    /// `array<kind, max>`
+    Array(Box<Kind>, Option<u64>),
+    /// A type this parser does not know, kept as it was written
+    Other(String),
+}
+
+impl Kind {
+    /// Parses a SurrealQL type. Types that are not understood become [`Kind::Other`]
+    pub fn parse(kind: &str) -> Kind {
+        let kind = kind.trim();
+
+        let either = split_top_level(kind, '|');
+
+        if either.len() > 1 {
+            return Kind::Either(either.into_iter().map(Kind::parse).collect());
+        }
+
+        let (name, args) = match kind.strip_suffix('>').and_then(|k| k.split_once('<')) {
+            Some((name, args)) => (name.trim(), Some(split_top_level(args, ','))),
+            None => (kind, None),
+        };
+
+        let inner = |args: &Option<Vec<&str>>| {
+            args.as_ref()
+                .and_then(|args| args.first())
+                .map_or(Kind::Any, |kind| Kind::parse(kind))
+        };
+
+        let max = |args: &Option<Vec<&str>>| {
+            args.as_ref()
+                .and_then(|args| args.get(1))
+                .and_then(|max| max.trim().parse().ok())
+        };
+
+        let names = |args: &Option<Vec<&str>>| {
+            args.as_ref()
+                .and_then(|args| args.first())
+                .map(|names| names.split('|').map(|n| n.trim().to_string()).collect())
+                .unwrap_or_default()
+        };
+
+        match (name, &args) {
+            ("any", None) => Kind::Any,
+            ("null", None) => Kind::Null,
+            ("bool", None) => Kind::Bool,
+            ("bytes", None) => Kind::Bytes,
+            ("datetime", None) => Kind::Datetime,
+            ("decimal", None) => Kind::Decimal,
+            ("duration", None) => Kind::Duration,
+            ("float", None) => Kind::Float,
+            ("int", None) => Kind::Int,
+            ("number", None) => Kind::Number,
+            ("object", None) => Kind::Object,
+            ("point", None) => Kind::Point,
+            ("string", None) => Kind::String,
+            ("uuid", None) => Kind::Uuid,
+            ("record", _) => Kind::Record(names(&args)),
+            ("geometry", _) => Kind::Geometry(names(&args)),
+            ("option", Some(_)) => Kind::Option(Box::new(inner(&args))),
+            ("set", _) => Kind::Set(Box::new(inner(&args)), max(&args)),
+            ("array", _) => Kind::Array(Box::new(inner(&args)), max(&args)),
+            _ => Kind::Other(kind.to_string()),
+        }
+    }
+}
+
+/// Splits on `separator`, except inside `<...>`
+fn split_top_level(kind: &str, separator: char) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut depth = 0usize;
+    let mut start = 0;
+
+    for (i, c) in kind.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => depth = depth.saturating_sub(1),
+            c if c == separator && depth == 0 => {
+                parts.push(kind[start..i].trim());
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+
+    parts.push(kind[start..].trim());
+
+    parts
+}
+
+impl QueryError {
+    /// The type that was expected by a [`QueryError::FieldCheck`], [`QueryError::CoerceTo`] or
+    /// [`QueryError::ConvertTo`] error, or `None` for every other variant
+    pub fn expected_kind(&self) -> Option<Kind> {
+        match self {
+            QueryError::FieldCheck { check, .. } => Some(Kind::parse(check)),
+            QueryError::CoerceTo { into, .. } | QueryError::ConvertTo { into, .. } => {
+                Some(Kind::parse(into))
+            }
+            _ => None,
+        }
+    }
+}
@@ -1,6 +1,73 @@
-use str_pattern_macro::StrPattern;
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "std")]
+use dialect::Dialect;
+pub use str_pattern_macro::StrPattern;
+
+// The derive refers to its runtime support by its absolute path, which has to work in this crate
+// too
+extern crate self as surreal_error_parser;
+
+// Code generated with `#[str_pattern(no_std)]` takes `Vec` and `String` from `alloc`, and so does
+// `capture`
+extern crate alloc;
+
+/// The dependencies of the code generated by [`StrPattern`], so crates deriving it don't need them
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub mod __private {
+    pub use once_cell;
+    pub use regex;
+}
 
+pub mod capture;
+#[cfg(feature = "std")]
+pub mod category;
+pub mod dialect;
+#[cfg(feature = "std")]
+pub mod errors;
+#[cfg(feature = "async-graphql")]
+pub mod graphql;
+#[cfg(feature = "std")]
+pub mod groups;
+#[cfg(feature = "tonic")]
+pub mod grpc;
+#[cfg(feature = "std")]
+pub mod http;
+#[cfg(feature = "std")]
+pub mod kind;
+#[cfg(feature = "std")]
+pub mod lines;
+pub mod pattern;
+#[cfg(feature = "std")]
+pub mod redact;
+#[cfg(feature = "std")]
+pub mod registry;
+#[cfg(feature = "std")]
+pub mod render;
+#[cfg(feature = "std")]
+pub mod rest;
+#[cfg(feature = "std")]
+pub mod rpc;
+#[cfg(feature = "surrealdb")]
+pub mod sdk;
+#[cfg(feature = "metrics")]
+pub mod telemetry;
+#[cfg(all(feature = "std", any(test, feature = "test-support")))]
+pub mod test_support;
+#[cfg(feature = "std")]
+pub mod thing;
+#[cfg(feature = "std")]
+pub mod thrown;
+#[cfg(feature = "std")]
+pub mod unique;
+
+#[cfg(feature = "std")]
 #[derive(StrPattern, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "code", content = "fields"))]
+#[str_pattern(dialect = Dialect, kind = QueryErrorKind, from_str = ParseQueryError)]
+#[cfg_attr(feature = "metrics", str_pattern(on_parse = telemetry::record))]
 #[non_exhaustive]
 pub enum QueryError {
     /// This error is used for ignoring a document when processing a query
@@ -15,8 +82,12 @@ pub enum QueryError {
     #[str_pattern("Continue statement has been reached")]
     Continue,
 
+    /// The database encountered unreachable logic (with a message)
+    #[str_pattern("The database encountered unreachable logic: {message}", dialect = Dialect::V2)]
+    UnreachableDetail { message: String },
+
     /// The database encountered unreachable logic
-    #[str_pattern("The database encountered unreachable logic")]
+    #[str_pattern("The database encountered unreachable logic", dialect = Dialect::V1)]
     Unreachable,
 
     /// A custom error has been thrown
@@ -88,13 +159,22 @@ pub enum QueryError {
     UnknownAuth,
 
     /// There was an error with the SQL query
-    #[str_pattern("Parse error on line {line} at character {char} when parsing '{sql}'")]
+    #[str_pattern(
+        "Parse error on line {line} at character {char} when parsing '{sql}'",
+        dialect = Dialect::V1,
+        line = r"\d+",
+        char = r"\d+"
+    )]
     InvalidQuery {
-        line: String,
-        char: String,
+        line: usize,
+        char: usize,
         sql: String,
     },
 
+    /// There was an error with the SQL query (as reported by SurrealDB 2.x)
+    #[str_pattern("Parse error: {message}", dialect = Dialect::V2, dotall)]
+    InvalidQueryDetail { message: String },
+
     /// There was an error with the provided JSON Patch
     #[str_pattern("The JSON Patch contains invalid operations. {message}")]
     InvalidPatch { message: String },
@@ -108,20 +188,21 @@ pub enum QueryError {
     HttpDisabled,
 
     /// it is not possible to set a variable with the specified name
-    #[str_pattern("Found '{name}' but it is not possible to set a variable with this name")]
+    #[str_pattern("Found '{name}' but it is not possible to set a variable with this name", dialect = Dialect::V1)]
+    #[str_pattern("'{name}' is a protected variable and cannot be set", dialect = Dialect::V2)]
     InvalidParam { name: String },
 
     #[str_pattern("Found '{field}' in SELECT clause on line {line}, but field is not an aggregate function, and is not present in GROUP BY expression")]
-    InvalidField { line: String, field: String },
+    InvalidField { line: usize, field: String },
 
     #[str_pattern("Found '{field}' in SPLIT ON clause on line {line}, but field is not present in SELECT expression")]
-    InvalidSplit { line: String, field: String },
+    InvalidSplit { line: usize, field: String },
 
     #[str_pattern("Found '{field}' in ORDER BY clause on line {line}, but field is not present in SELECT expression")]
-    InvalidOrder { line: String, field: String },
+    InvalidOrder { line: usize, field: String },
 
     #[str_pattern("Found '{field}' in GROUP BY clause on line {line}, but field is not present in SELECT expression")]
-    InvalidGroup { line: String, field: String },
+    InvalidGroup { line: usize, field: String },
 
     /// The LIMIT clause must evaluate to a positive integer
     #[str_pattern("Found {value} but the LIMIT clause must evaluate to a positive integer")]
@@ -132,7 +213,7 @@ pub enum QueryError {
     InvalidStart { value: String },
 
     /// There was an error with the provided JavaScript code
-    #[str_pattern("Problem with embedded script function. {message}")]
+    #[str_pattern("Problem with embedded script function. {message}", dotall)]
     InvalidScript { message: String },
 
     /// There was a problem running the specified function
@@ -176,7 +257,7 @@ pub enum QueryError {
     NsNotFound { value: String },
 
     /// The requested namespace token does not exist
-    #[str_pattern("The namespace token '{value}' does not exist")]
+    #[str_pattern("The namespace token '{value}' does not exist", dialect = Dialect::V1)]
     NtNotFound { value: String },
 
     /// The requested namespace login does not exist
@@ -188,7 +269,7 @@ pub enum QueryError {
     DbNotFound { value: String },
 
     /// The requested database token does not exist
-    #[str_pattern("The database token '{value}' does not exist")]
+    #[str_pattern("The database token '{value}' does not exist", dialect = Dialect::V1)]
     DtNotFound { value: String },
 
     /// The requested database login does not exist
@@ -200,9 +281,13 @@ pub enum QueryError {
     FcNotFound { value: String },
 
     /// The requested scope does not exist
-    #[str_pattern("The scope '{value}' does not exist")]
+    #[str_pattern("The scope '{value}' does not exist", dialect = Dialect::V1)]
     ScNotFound { value: String },
 
+    /// The scope of a signup or signin does not exist, reported without its name
+    #[str_pattern("The scope does not exist", dialect = Dialect::V1)]
+    NoScopeFound,
+
     // The cluster node already exists
     #[str_pattern("The node '{value}' already exists")]
     ClAlreadyExists { value: String },
@@ -212,7 +297,7 @@ pub enum QueryError {
     NdNotFound { value: String },
 
     /// The requested scope token does not exist
-    #[str_pattern("The scope token '{value}' does not exist")]
+    #[str_pattern("The scope token '{value}' does not exist", dialect = Dialect::V1)]
     StNotFound { value: String },
 
     /// The requested param does not exist
@@ -332,7 +417,8 @@ pub enum QueryError {
     },
 
     /// Found a record id for the record but we are creating a specific record
-    #[str_pattern("Found {value} for the id field, but a specific record has been specified")]
+    #[str_pattern("Found {value} for the id field, but a specific record has been specified", dialect = Dialect::V1)]
+    #[str_pattern("Found {value} for the `id` field, but a specific record has been specified", dialect = Dialect::V2)]
     IdMismatch { value: String },
 
     /// Found a record id for the record but this is not a valid id
@@ -340,16 +426,16 @@ pub enum QueryError {
     IdInvalid { value: String },
 
     /// Unable to coerce to a value to another value
-    #[str_pattern("Expected a {into} but found {from}")]
+    #[str_pattern("Expected a {into} but found {from}", into = lazy)]
     CoerceTo { from: String, into: String },
 
     /// Unable to convert a value to another value
-    #[str_pattern("Expected a {into} but cannot convert {from} into a {into}")]
+    #[str_pattern("Expected a {into} but cannot convert {from} into a {into}", into = lazy)]
     ConvertTo { from: String, into: String },
 
     /// Unable to coerce to a value to another value
     #[str_pattern("Expected a {kind} but the array had {size} items")]
-    LengthInvalid { kind: String, size: String },
+    LengthInvalid { kind: String, size: usize },
 
     /// Cannot perform addition
     #[str_pattern("Cannot perform addition with '{0}' and '{1}'")]
@@ -403,12 +489,17 @@ pub enum QueryError {
     #[str_pattern("Versioned error: {0}")]
     Revision(String),
 
+    /// The index has been found to be inconsistent (with a message)
+    #[str_pattern("Index is corrupted: {message}", dialect = Dialect::V2)]
+    CorruptedIndexDetail { message: String },
+
     /// The index has been found to be inconsistent
-    #[str_pattern("Index is corrupted")]
+    #[str_pattern("Index is corrupted", dialect = Dialect::V1)]
     CorruptedIndex,
 
     /// The query planner did not find an index able to support the match @@ operator on a given expression
-    #[str_pattern("There was no suitable full-text index supporting the expression '{value}'")]
+    #[str_pattern("There was no suitable full-text index supporting the expression '{value}'", dialect = Dialect::V1)]
+    #[str_pattern("There was no suitable index supporting the expression '{value}'", dialect = Dialect::V2)]
     NoIndexFoundForMatch { value: String },
 
     /// Represents an error when analyzing a value
@@ -479,12 +570,278 @@ pub enum QueryError {
     #[str_pattern("Access to network target '{0}' is not allowed")]
     NetTargetNotAllowed(String),
 
+    //
+    // SurrealDB 2.x
+    //
+    /// This error is used for returning early from a block
+    #[str_pattern("Return statement has been reached", dialect = Dialect::V2)]
+    Return,
+
+    /// The session has expired
+    #[str_pattern("The session has expired")]
+    ExpiredSession,
+
+    /// The token has expired
+    #[str_pattern("The token has expired", dialect = Dialect::V2)]
+    ExpiredToken,
+
+    /// The signup query failed
+    #[str_pattern("The signup query failed")]
+    SignupQueryFailed,
+
+    /// The signin query failed
+    #[str_pattern("The signin query failed")]
+    SigninQueryFailed,
+
+    /// There was an error with signing up
+    #[str_pattern("There was a problem with signing up")]
+    InvalidSignup,
+
+    /// The password did not verify
+    #[str_pattern("The password did not verify")]
+    InvalidPass,
+
+    /// The username or password was not provided
+    #[str_pattern("Username or Password was not provided")]
+    MissingUserOrPass,
+
+    /// No record was returned
+    #[str_pattern("No record was returned")]
+    NoRecordFound,
+
+    /// The requested root access method does not exist
+    #[str_pattern("The root access method '{ac}' does not exist", dialect = Dialect::V2)]
+    AccessRootNotFound { ac: String },
+
+    /// The requested namespace access method does not exist
+    #[str_pattern("The access method '{ac}' does not exist in the namespace '{ns}'", dialect = Dialect::V2)]
+    AccessNsNotFound { ac: String, ns: String },
+
+    /// The requested database access method does not exist
+    #[str_pattern("The access method '{ac}' does not exist in the database '{db}'", dialect = Dialect::V2)]
+    AccessDbNotFound { ac: String, db: String },
+
+    /// The requested root access grant does not exist
+    #[str_pattern("The root access grant '{gr}' does not exist", dialect = Dialect::V2)]
+    AccessGrantRootNotFound { gr: String },
+
+    /// The requested namespace access grant does not exist
+    #[str_pattern("The access grant '{gr}' does not exist in the namespace '{ns}'", dialect = Dialect::V2)]
+    AccessGrantNsNotFound { gr: String, ns: String },
+
+    /// The requested database access grant does not exist
+    #[str_pattern("The access grant '{gr}' does not exist in the database '{db}'", dialect = Dialect::V2)]
+    AccessGrantDbNotFound { gr: String, db: String },
+
+    /// The access method does not exist
+    #[str_pattern("The access method does not exist", dialect = Dialect::V2)]
+    AccessNotFound,
+
+    /// The access method cannot be used in the requested operation
+    #[str_pattern("The access method cannot be used in the requested operation", dialect = Dialect::V2)]
+    AccessMethodMismatch,
+
+    /// The access grant has been revoked
+    #[str_pattern("This access grant has been revoked", dialect = Dialect::V2)]
+    AccessGrantRevoked,
+
+    /// The record access signup query failed
+    #[str_pattern("The record access signup query failed", dialect = Dialect::V2)]
+    AccessRecordSignupQueryFailed,
+
+    /// The record access signin query failed
+    #[str_pattern("The record access signin query failed", dialect = Dialect::V2)]
+    AccessRecordSigninQueryFailed,
+
+    /// The record access method does not allow signup
+    #[str_pattern("This record access method does not allow signup", dialect = Dialect::V2)]
+    AccessRecordNoSignup,
+
+    /// The record access method does not allow signin
+    #[str_pattern("This record access method does not allow signin", dialect = Dialect::V2)]
+    AccessRecordNoSignin,
+
+    /// The requested event does not exist
+    #[str_pattern("The event '{value}' does not exist")]
+    EvNotFound { value: String },
+
+    /// The requested field does not exist
+    #[str_pattern("The field '{value}' does not exist")]
+    FdNotFound { value: String },
+
+    /// The requested model does not exist
+    #[str_pattern("The model 'ml::{value}' does not exist")]
+    MlNotFound { value: String },
+
+    /// The requested record does not exist
+    #[str_pattern("The record '{value}' does not exist", dialect = Dialect::V2)]
+    IdNotFound { value: String },
+
+    /// The requested namespace already exists
+    #[str_pattern("The namespace '{value}' already exists")]
+    NsAlreadyExists { value: String },
+
+    /// The requested database already exists
+    #[str_pattern("The database '{value}' already exists")]
+    DbAlreadyExists { value: String },
+
+    /// The requested table already exists
+    #[str_pattern("The table '{value}' already exists")]
+    TbAlreadyExists { value: String },
+
+    /// The requested field already exists
+    #[str_pattern("The field '{value}' already exists")]
+    FdAlreadyExists { value: String },
+
+    /// The requested index already exists
+    #[str_pattern("The index '{value}' already exists")]
+    IxAlreadyExists { value: String },
+
+    /// The requested event already exists
+    #[str_pattern("The event '{value}' already exists")]
+    EvAlreadyExists { value: String },
+
+    /// The requested function already exists
+    #[str_pattern("The function 'fn::{value}' already exists")]
+    FcAlreadyExists { value: String },
+
+    /// The requested param already exists
+    #[str_pattern("The param '${value}' already exists")]
+    PaAlreadyExists { value: String },
+
+    /// The requested analyzer already exists
+    #[str_pattern("The analyzer '{value}' already exists")]
+    AzAlreadyExists { value: String },
+
+    /// The requested model already exists
+    #[str_pattern("The model '{value}' already exists")]
+    MlAlreadyExists { value: String },
+
+    /// The requested root access method already exists
+    #[str_pattern("The root access method '{ac}' already exists", dialect = Dialect::V2)]
+    AccessRootAlreadyExists { ac: String },
+
+    /// The requested namespace access method already exists
+    #[str_pattern("The access method '{ac}' already exists in the namespace '{ns}'", dialect = Dialect::V2)]
+    AccessNsAlreadyExists { ac: String, ns: String },
+
+    /// The requested database access method already exists
+    #[str_pattern("The access method '{ac}' already exists in the database '{db}'", dialect = Dialect::V2)]
+    AccessDbAlreadyExists { ac: String, db: String },
+
+    /// The requested root user already exists
+    #[str_pattern("The root user '{value}' already exists", dialect = Dialect::V2)]
+    UserRootAlreadyExists { value: String },
+
+    /// The requested namespace user already exists
+    #[str_pattern("The user '{value}' already exists in the namespace '{ns}'")]
+    UserNsAlreadyExists { value: String, ns: String },
+
+    /// The requested database user already exists
+    #[str_pattern("The user '{value}' already exists in the database '{db}'")]
+    UserDbAlreadyExists { value: String, db: String },
+
+    /// The index for the specified table is still being built
+    #[str_pattern("Database index `{index}` is currently building", dialect = Dialect::V2)]
+    IndexAlreadyBuilding { index: String },
+
+    /// Can not execute UPSERT statement using the specified value
+    #[str_pattern("Can not execute UPSERT statement using value '{value}'", dialect = Dialect::V2)]
+    UpsertStatement { value: String },
+
+    /// Can not execute RELATE statement with the specified `in` value
+    #[str_pattern("Can not execute RELATE statement where property 'in' is '{value}'", dialect = Dialect::V2)]
+    RelateStatementIn { value: String },
+
+    /// Can not execute RELATE statement with the specified `id` value
+    #[str_pattern("Can not execute RELATE statement where property 'id' is '{value}'", dialect = Dialect::V2)]
+    RelateStatementId { value: String },
+
+    /// Can not execute RELATE statement with the specified `out` value
+    #[str_pattern("Can not execute RELATE statement where property 'out' is '{value}'", dialect = Dialect::V2)]
+    RelateStatementOut { value: String },
+
+    /// Can not execute INSERT statement with the specified `in` value
+    #[str_pattern("Can not execute INSERT statement where property 'in' is '{value}'", dialect = Dialect::V2)]
+    InsertStatementIn { value: String },
+
+    /// Can not execute INSERT statement with the specified `id` value
+    #[str_pattern("Can not execute INSERT statement where property 'id' is '{value}'", dialect = Dialect::V2)]
+    InsertStatementId { value: String },
+
+    /// Can not execute INSERT statement with the specified `out` value
+    #[str_pattern("Can not execute INSERT statement where property 'out' is '{value}'", dialect = Dialect::V2)]
+    InsertStatementOut { value: String },
+
+    /// The value can not be used in a CONTENT clause
+    #[str_pattern("Can not use {value} in a CONTENT clause")]
+    InvalidContent { value: String },
+
+    /// The value can not be used in a MERGE clause
+    #[str_pattern("Can not use {value} in a MERGE clause")]
+    InvalidMerge { value: String },
+
+    /// The ONLY keyword was used but the statement returned more than one result
+    #[str_pattern("Expected a single result output when using the ONLY keyword")]
+    SingleOnlyOutput,
+
+    /// The permissions do not allow viewing this param
+    #[str_pattern("You don't have permission to view the ${name} parameter")]
+    ParamPermissions { name: String },
+
+    /// The permissions do not allow running this function
+    #[str_pattern("You don't have permission to run the fn::{name} function")]
+    FunctionPermissions { name: String },
+
+    /// The specified field is readonly and its value was changed
+    #[str_pattern(
+        "Found changed value for field `{field}`, with record `{thing}`, but field is readonly"
+    )]
+    FieldReadonly { field: String, thing: String },
+
+    /// The specified field on a SCHEMAFULL table was not defined
+    #[str_pattern("Found field '{field}', but no such field exists for table '{table}'", dialect = Dialect::V2)]
+    FieldUndefined { field: String, table: String },
+
+    /// The specified value did not conform to the LET type check
+    #[str_pattern("Found {value} for param ${name}, but expected a {check}", dialect = Dialect::V2)]
+    SetCheck {
+        value: String,
+        name: String,
+        check: String,
+    },
+
+    /// Found a table name for the record but this is not a valid table
+    #[str_pattern("Found {value} for the Record ID but this is not a valid table name", dialect = Dialect::V2)]
+    TbInvalid { value: String },
+
+    /// Cannot perform remainder
+    #[str_pattern("Cannot perform remainder with '{0}' and '{1}'")]
+    TryRem(String, String),
+
+    /// The operation results in an overflow
+    #[str_pattern("Failed to compute: \"{0}\", as the operation results in an overflow.", dialect = Dialect::V2)]
+    ArithmeticOverflow(String),
+
+    /// The query is larger than the database accepts
+    #[str_pattern("Size of query script exceeded maximum supported size of 4,294,967,295 bytes.", dialect = Dialect::V2)]
+    QueryTooLarge,
+
     /// Statement has been deprecated
-    #[str_pattern("{0}")]
+    // The message is nothing but the inner text, so it can't be told apart from an unknown error
+    // and is never parsed
+    #[str_pattern("{0}", parse = false)]
     Deprecated(String),
+
+    /// The message did not match any known error
+    #[str_pattern(fallback)]
+    Unknown(String),
 }
 
-#[cfg(test)]
+#[cfg(feature = "std")]
+impl std::error::Error for QueryError {}
+
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
 
@@ -515,6 +872,17 @@ mod tests {
         assert_eq!(error, QueryError::Continue);
     }
 
+    #[test]
+    fn unreachable_detail() {
+        let message = "a message".to_string();
+
+        let error_string = format!("The database encountered unreachable logic: {message}");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::UnreachableDetail { message });
+    }
+
     #[test]
     fn unreachable() {
         let error_string = "The database encountered unreachable logic";
@@ -691,8 +1059,8 @@ mod tests {
 
     #[test]
     fn invalid_query() {
-        let line = "line".to_string();
-        let char = "char".to_string();
+        let line = 1;
+        let char = 14;
         let sql = "sql query".to_string();
 
         let error_string = format!(
@@ -707,6 +1075,26 @@ mod tests {
         assert_eq!(error, QueryError::InvalidQuery { line, char, sql });
     }
 
+    #[test]
+    fn invalid_query_with_non_numeric_line() {
+        let error_string = "Parse error on line one at character 14 when parsing 'sql query'";
+
+        let error = QueryError::parse(error_string);
+
+        assert_eq!(error, QueryError::Unknown(error_string.to_string()));
+    }
+
+    #[test]
+    fn invalid_query_detail() {
+        let message = "Unexpected token `FORM`, expected FROM".to_string();
+
+        let error_string = format!("Parse error: {message}");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::InvalidQueryDetail { message });
+    }
+
     #[test]
     fn invalid_patch() {
         let message = "unexpected ',' at line 1 column 2".to_string();
@@ -756,9 +1144,20 @@ mod tests {
         assert_eq!(error, QueryError::InvalidParam { name });
     }
 
+    #[test]
+    fn invalid_param_2_x() {
+        let name = "a name".to_string();
+
+        let error_string = format!("'{name}' is a protected variable and cannot be set");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::InvalidParam { name });
+    }
+
     #[test]
     fn invalid_field() {
-        let line = "89".to_string();
+        let line = 89;
         let field = "a field".to_string();
 
         let error_string = format!("Found '{field}' in SELECT clause on line {line}, but field is not an aggregate function, and is not present in GROUP BY expression", field = field, line = line);
@@ -770,7 +1169,7 @@ mod tests {
 
     #[test]
     fn invalid_split() {
-        let line = "1000".to_string();
+        let line = 1000;
         let field = "name".to_string();
 
         let error_string = format!("Found '{field}' in SPLIT ON clause on line {line}, but field is not present in SELECT expression", field = field, line = line);
@@ -782,7 +1181,7 @@ mod tests {
 
     #[test]
     fn invalid_order() {
-        let line = "301".to_string();
+        let line = 301;
         let field = "a field".to_string();
 
         let error_string = format!("Found '{field}' in ORDER BY clause on line {line}, but field is not present in SELECT expression", line = line, field = field);
@@ -794,7 +1193,7 @@ mod tests {
 
     #[test]
     fn invalid_group() {
-        let line = "1000".to_string();
+        let line = 1000;
         let field = "name".to_string();
 
         let error_string = format!("Found '{field}' in GROUP BY clause on line {line}, but field is not present in SELECT expression", line = line, field = field);
@@ -846,6 +1245,22 @@ mod tests {
         assert_eq!(error, QueryError::InvalidScript { message });
     }
 
+    #[test]
+    fn invalid_script_multiline() {
+        // The pattern is `dotall`, so stack traces stay in the capture
+        let message =
+            "Uncaught Error: boom\n    at fn (eval_script:1:7)\n    at <eval>:2:1".to_string();
+
+        let error_string = format!(
+            "Problem with embedded script function. {message}",
+            message = message
+        );
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::InvalidScript { message });
+    }
+
     #[test]
     fn invalid_function() {
         let message = "error message".to_string();
@@ -1052,6 +1467,15 @@ mod tests {
         assert_eq!(error, QueryError::ScNotFound { value });
     }
 
+    #[test]
+    fn no_scope_found() {
+        let error_string = "The scope does not exist";
+
+        let error = QueryError::from_string(error_string).unwrap();
+
+        assert_eq!(error, QueryError::NoScopeFound);
+    }
+
     #[test]
     fn cl_already_exists() {
         let value = "a node".to_string();
@@ -1454,6 +1878,18 @@ mod tests {
         assert_eq!(error, QueryError::IdMismatch { value });
     }
 
+    #[test]
+    fn id_mismatch_2_x() {
+        let value = ":sliduarorjc".to_string();
+
+        let error_string =
+            format!("Found {value} for the `id` field, but a specific record has been specified");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::IdMismatch { value });
+    }
+
     #[test]
     fn id_invalid() {
         let value = "aslikd47asljdkv".to_string();
@@ -1484,17 +1920,27 @@ mod tests {
         assert_eq!(error, QueryError::CoerceTo { from, into });
     }
 
+    #[test]
+    fn coerce_to_with_separators_in_from() {
+        let into = "int".to_string();
+        let from = "'a but found b'".to_string();
+
+        let error_string = format!("Expected a {into} but found {from}");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::CoerceTo { from, into });
+    }
+
     #[test]
     fn convert_to() {
         let into = "aslkdu4rlasjdv".to_string();
         let from = "847aasldjvla".to_string();
-        let into_2 = "alksid74*jf238764$%^".to_string();
 
         let error_string = format!(
-            "Expected a {into} but cannot convert {from} into a {into_2}",
+            "Expected a {into} but cannot convert {from} into a {into}",
             from = from,
-            into = into,
-            into_2 = into_2
+            into = into
         );
 
         let error = QueryError::from_string(&error_string).unwrap();
@@ -1502,10 +1948,32 @@ mod tests {
         assert_eq!(error, QueryError::ConvertTo { from, into });
     }
 
+    #[test]
+    fn convert_to_with_separators_in_from() {
+        let into = "int".to_string();
+        let from = "'a but cannot convert b into a c'".to_string();
+
+        let error_string = format!("Expected a {into} but cannot convert {from} into a {into}");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::ConvertTo { from, into });
+    }
+
+    #[test]
+    fn convert_to_with_mismatched_into() {
+        let error_string =
+            "Expected a aslkdu4rlasjdv but cannot convert 847aasldjvla into a alksid74*jf238764$%^";
+
+        let error = QueryError::parse(error_string);
+
+        assert_eq!(error, QueryError::Unknown(error_string.to_string()));
+    }
+
     #[test]
     fn length_invalid() {
         let kind = "7as4lksd".to_string();
-        let size = "q38247q9".to_string();
+        let size = 38247;
 
         let error_string = format!(
             "Expected a {kind} but the array had {size} items",
@@ -1697,6 +2165,17 @@ mod tests {
         assert_eq!(error, QueryError::Revision(first));
     }
 
+    #[test]
+    fn corrupted_index_detail() {
+        let message = "a message".to_string();
+
+        let error_string = format!("Index is corrupted: {message}");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::CorruptedIndexDetail { message });
+    }
+
     #[test]
     fn corrupted_index() {
         let error_string = "Index is corrupted";
@@ -1720,6 +2199,29 @@ mod tests {
         assert_eq!(error, QueryError::NoIndexFoundForMatch { value });
     }
 
+    #[test]
+    fn no_index_found_for_match_2_x() {
+        let value = "zlxk;cu5t".to_string();
+
+        let error_string =
+            format!("There was no suitable index supporting the expression '{value}'");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::NoIndexFoundForMatch { value });
+    }
+
+    #[test]
+    fn alias_displays_as_first_pattern() {
+        let error =
+            QueryError::from_string("'a name' is a protected variable and cannot be set").unwrap();
+
+        assert_eq!(
+            error.to_string(),
+            "Found 'a name' but it is not possible to set a variable with this name"
+        );
+    }
+
     #[test]
     fn analyzer_error() {
         let first = ";litr7".to_string();
@@ -1867,7 +2369,7 @@ mod tests {
     fn scripting_not_allowed() {
         let error_string = "Scripting functions are not allowed";
 
-        let error = QueryError::from_string(&error_string).unwrap();
+        let error = QueryError::from_string(error_string).unwrap();
 
         assert_eq!(error, QueryError::ScriptingNotAllowed);
     }
@@ -1901,11 +2403,2594 @@ mod tests {
     }
 
     #[test]
-    fn deprecated() {
-        let error_string = ""; // This can match anything
+    fn return_statement() {
+        let error_string = "Return statement has been reached";
+
+        let error = QueryError::from_string(error_string).unwrap();
+
+        assert_eq!(error, QueryError::Return);
+    }
+
+    #[test]
+    fn expired_session() {
+        let error_string = "The session has expired";
+
+        let error = QueryError::from_string(error_string).unwrap();
+
+        assert_eq!(error, QueryError::ExpiredSession);
+    }
+
+    #[test]
+    fn expired_token() {
+        let error_string = "The token has expired";
+
+        let error = QueryError::from_string(error_string).unwrap();
+
+        assert_eq!(error, QueryError::ExpiredToken);
+    }
+
+    #[test]
+    fn signup_query_failed() {
+        let error_string = "The signup query failed";
+
+        let error = QueryError::from_string(error_string).unwrap();
+
+        assert_eq!(error, QueryError::SignupQueryFailed);
+    }
+
+    #[test]
+    fn signin_query_failed() {
+        let error_string = "The signin query failed";
+
+        let error = QueryError::from_string(error_string).unwrap();
+
+        assert_eq!(error, QueryError::SigninQueryFailed);
+    }
+
+    #[test]
+    fn invalid_signup() {
+        let error_string = "There was a problem with signing up";
+
+        let error = QueryError::from_string(error_string).unwrap();
+
+        assert_eq!(error, QueryError::InvalidSignup);
+    }
+
+    #[test]
+    fn invalid_pass() {
+        let error_string = "The password did not verify";
+
+        let error = QueryError::from_string(error_string).unwrap();
+
+        assert_eq!(error, QueryError::InvalidPass);
+    }
+
+    #[test]
+    fn missing_user_or_pass() {
+        let error_string = "Username or Password was not provided";
+
+        let error = QueryError::from_string(error_string).unwrap();
+
+        assert_eq!(error, QueryError::MissingUserOrPass);
+    }
+
+    #[test]
+    fn no_record_found() {
+        let error_string = "No record was returned";
+
+        let error = QueryError::from_string(error_string).unwrap();
+
+        assert_eq!(error, QueryError::NoRecordFound);
+    }
+
+    #[test]
+    fn access_root_not_found() {
+        let ac = "an access method".to_string();
+
+        let error_string = format!("The root access method '{ac}' does not exist");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::AccessRootNotFound { ac });
+    }
+
+    #[test]
+    fn access_ns_not_found() {
+        let ac = "an access method".to_string();
+        let ns = "a namespace".to_string();
+
+        let error_string =
+            format!("The access method '{ac}' does not exist in the namespace '{ns}'");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::AccessNsNotFound { ac, ns });
+    }
+
+    #[test]
+    fn access_db_not_found() {
+        let ac = "an access method".to_string();
+        let db = "a database".to_string();
+
+        let error_string =
+            format!("The access method '{ac}' does not exist in the database '{db}'");
 
         let error = QueryError::from_string(&error_string).unwrap();
 
-        assert_eq!(error, QueryError::Deprecated("".to_string()));
+        assert_eq!(error, QueryError::AccessDbNotFound { ac, db });
+    }
+
+    #[test]
+    fn access_grant_root_not_found() {
+        let gr = "a grant".to_string();
+
+        let error_string = format!("The root access grant '{gr}' does not exist");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::AccessGrantRootNotFound { gr });
+    }
+
+    #[test]
+    fn access_grant_ns_not_found() {
+        let gr = "a grant".to_string();
+        let ns = "a namespace".to_string();
+
+        let error_string =
+            format!("The access grant '{gr}' does not exist in the namespace '{ns}'");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::AccessGrantNsNotFound { gr, ns });
+    }
+
+    #[test]
+    fn access_grant_db_not_found() {
+        let gr = "a grant".to_string();
+        let db = "a database".to_string();
+
+        let error_string = format!("The access grant '{gr}' does not exist in the database '{db}'");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::AccessGrantDbNotFound { gr, db });
+    }
+
+    #[test]
+    fn access_not_found() {
+        let error_string = "The access method does not exist";
+
+        let error = QueryError::from_string(error_string).unwrap();
+
+        assert_eq!(error, QueryError::AccessNotFound);
+    }
+
+    #[test]
+    fn access_method_mismatch() {
+        let error_string = "The access method cannot be used in the requested operation";
+
+        let error = QueryError::from_string(error_string).unwrap();
+
+        assert_eq!(error, QueryError::AccessMethodMismatch);
+    }
+
+    #[test]
+    fn access_grant_revoked() {
+        let error_string = "This access grant has been revoked";
+
+        let error = QueryError::from_string(error_string).unwrap();
+
+        assert_eq!(error, QueryError::AccessGrantRevoked);
+    }
+
+    #[test]
+    fn access_record_signup_query_failed() {
+        let error_string = "The record access signup query failed";
+
+        let error = QueryError::from_string(error_string).unwrap();
+
+        assert_eq!(error, QueryError::AccessRecordSignupQueryFailed);
+    }
+
+    #[test]
+    fn access_record_signin_query_failed() {
+        let error_string = "The record access signin query failed";
+
+        let error = QueryError::from_string(error_string).unwrap();
+
+        assert_eq!(error, QueryError::AccessRecordSigninQueryFailed);
+    }
+
+    #[test]
+    fn access_record_no_signup() {
+        let error_string = "This record access method does not allow signup";
+
+        let error = QueryError::from_string(error_string).unwrap();
+
+        assert_eq!(error, QueryError::AccessRecordNoSignup);
+    }
+
+    #[test]
+    fn access_record_no_signin() {
+        let error_string = "This record access method does not allow signin";
+
+        let error = QueryError::from_string(error_string).unwrap();
+
+        assert_eq!(error, QueryError::AccessRecordNoSignin);
+    }
+
+    #[test]
+    fn ev_not_found() {
+        let value = "a value".to_string();
+
+        let error_string = format!("The event '{value}' does not exist");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::EvNotFound { value });
+    }
+
+    #[test]
+    fn fd_not_found() {
+        let value = "a value".to_string();
+
+        let error_string = format!("The field '{value}' does not exist");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::FdNotFound { value });
+    }
+
+    #[test]
+    fn ml_not_found() {
+        let value = "a value".to_string();
+
+        let error_string = format!("The model 'ml::{value}' does not exist");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::MlNotFound { value });
+    }
+
+    #[test]
+    fn id_not_found() {
+        let value = "a value".to_string();
+
+        let error_string = format!("The record '{value}' does not exist");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::IdNotFound { value });
+    }
+
+    #[test]
+    fn ns_already_exists() {
+        let value = "a value".to_string();
+
+        let error_string = format!("The namespace '{value}' already exists");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::NsAlreadyExists { value });
+    }
+
+    #[test]
+    fn db_already_exists() {
+        let value = "a value".to_string();
+
+        let error_string = format!("The database '{value}' already exists");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::DbAlreadyExists { value });
+    }
+
+    #[test]
+    fn tb_already_exists() {
+        let value = "a value".to_string();
+
+        let error_string = format!("The table '{value}' already exists");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::TbAlreadyExists { value });
+    }
+
+    #[test]
+    fn fd_already_exists() {
+        let value = "a value".to_string();
+
+        let error_string = format!("The field '{value}' already exists");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::FdAlreadyExists { value });
+    }
+
+    #[test]
+    fn ix_already_exists() {
+        let value = "a value".to_string();
+
+        let error_string = format!("The index '{value}' already exists");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::IxAlreadyExists { value });
+    }
+
+    #[test]
+    fn ev_already_exists() {
+        let value = "a value".to_string();
+
+        let error_string = format!("The event '{value}' already exists");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::EvAlreadyExists { value });
+    }
+
+    #[test]
+    fn fc_already_exists() {
+        let value = "a value".to_string();
+
+        let error_string = format!("The function 'fn::{value}' already exists");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::FcAlreadyExists { value });
+    }
+
+    #[test]
+    fn pa_already_exists() {
+        let value = "a value".to_string();
+
+        let error_string = format!("The param '${value}' already exists");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::PaAlreadyExists { value });
+    }
+
+    #[test]
+    fn az_already_exists() {
+        let value = "a value".to_string();
+
+        let error_string = format!("The analyzer '{value}' already exists");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::AzAlreadyExists { value });
+    }
+
+    #[test]
+    fn ml_already_exists() {
+        let value = "a value".to_string();
+
+        let error_string = format!("The model '{value}' already exists");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::MlAlreadyExists { value });
+    }
+
+    #[test]
+    fn access_root_already_exists() {
+        let ac = "an access method".to_string();
+
+        let error_string = format!("The root access method '{ac}' already exists");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::AccessRootAlreadyExists { ac });
+    }
+
+    #[test]
+    fn access_ns_already_exists() {
+        let ac = "an access method".to_string();
+        let ns = "a namespace".to_string();
+
+        let error_string =
+            format!("The access method '{ac}' already exists in the namespace '{ns}'");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::AccessNsAlreadyExists { ac, ns });
+    }
+
+    #[test]
+    fn access_db_already_exists() {
+        let ac = "an access method".to_string();
+        let db = "a database".to_string();
+
+        let error_string =
+            format!("The access method '{ac}' already exists in the database '{db}'");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::AccessDbAlreadyExists { ac, db });
+    }
+
+    #[test]
+    fn user_root_already_exists() {
+        let value = "a value".to_string();
+
+        let error_string = format!("The root user '{value}' already exists");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::UserRootAlreadyExists { value });
+    }
+
+    #[test]
+    fn user_ns_already_exists() {
+        let value = "a value".to_string();
+        let ns = "a namespace".to_string();
+
+        let error_string = format!("The user '{value}' already exists in the namespace '{ns}'");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::UserNsAlreadyExists { value, ns });
+    }
+
+    #[test]
+    fn user_db_already_exists() {
+        let value = "a value".to_string();
+        let db = "a database".to_string();
+
+        let error_string = format!("The user '{value}' already exists in the database '{db}'");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::UserDbAlreadyExists { value, db });
+    }
+
+    #[test]
+    fn index_already_building() {
+        let index = "an index".to_string();
+
+        let error_string = format!("Database index `{index}` is currently building");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::IndexAlreadyBuilding { index });
+    }
+
+    #[test]
+    fn upsert_statement() {
+        let value = "a value".to_string();
+
+        let error_string = format!("Can not execute UPSERT statement using value '{value}'");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::UpsertStatement { value });
+    }
+
+    #[test]
+    fn relate_statement_in() {
+        let value = "a value".to_string();
+
+        let error_string =
+            format!("Can not execute RELATE statement where property 'in' is '{value}'");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::RelateStatementIn { value });
+    }
+
+    #[test]
+    fn relate_statement_id() {
+        let value = "a value".to_string();
+
+        let error_string =
+            format!("Can not execute RELATE statement where property 'id' is '{value}'");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::RelateStatementId { value });
+    }
+
+    #[test]
+    fn relate_statement_out() {
+        let value = "a value".to_string();
+
+        let error_string =
+            format!("Can not execute RELATE statement where property 'out' is '{value}'");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::RelateStatementOut { value });
+    }
+
+    #[test]
+    fn insert_statement_in() {
+        let value = "a value".to_string();
+
+        let error_string =
+            format!("Can not execute INSERT statement where property 'in' is '{value}'");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::InsertStatementIn { value });
+    }
+
+    #[test]
+    fn insert_statement_id() {
+        let value = "a value".to_string();
+
+        let error_string =
+            format!("Can not execute INSERT statement where property 'id' is '{value}'");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::InsertStatementId { value });
+    }
+
+    #[test]
+    fn insert_statement_out() {
+        let value = "a value".to_string();
+
+        let error_string =
+            format!("Can not execute INSERT statement where property 'out' is '{value}'");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::InsertStatementOut { value });
+    }
+
+    #[test]
+    fn invalid_content() {
+        let value = "a value".to_string();
+
+        let error_string = format!("Can not use {value} in a CONTENT clause");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::InvalidContent { value });
+    }
+
+    #[test]
+    fn invalid_merge() {
+        let value = "a value".to_string();
+
+        let error_string = format!("Can not use {value} in a MERGE clause");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::InvalidMerge { value });
+    }
+
+    #[test]
+    fn single_only_output() {
+        let error_string = "Expected a single result output when using the ONLY keyword";
+
+        let error = QueryError::from_string(error_string).unwrap();
+
+        assert_eq!(error, QueryError::SingleOnlyOutput);
+    }
+
+    #[test]
+    fn param_permissions() {
+        let name = "a name".to_string();
+
+        let error_string = format!("You don't have permission to view the ${name} parameter");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::ParamPermissions { name });
+    }
+
+    #[test]
+    fn function_permissions() {
+        let name = "a name".to_string();
+
+        let error_string = format!("You don't have permission to run the fn::{name} function");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::FunctionPermissions { name });
+    }
+
+    #[test]
+    fn field_readonly() {
+        let field = "a field".to_string();
+        let thing = "a thing".to_string();
+
+        let error_string = format!(
+            "Found changed value for field `{field}`, with record `{thing}`, but field is readonly"
+        );
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::FieldReadonly { field, thing });
+    }
+
+    #[test]
+    fn field_undefined() {
+        let field = "a field".to_string();
+        let table = "a table".to_string();
+
+        let error_string =
+            format!("Found field '{field}', but no such field exists for table '{table}'");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::FieldUndefined { field, table });
+    }
+
+    #[test]
+    fn set_check() {
+        let value = "a value".to_string();
+        let name = "a name".to_string();
+        let check = "a check".to_string();
+
+        let error_string = format!("Found {value} for param ${name}, but expected a {check}");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::SetCheck { value, name, check });
+    }
+
+    #[test]
+    fn tb_invalid() {
+        let value = "a value".to_string();
+
+        let error_string =
+            format!("Found {value} for the Record ID but this is not a valid table name");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::TbInvalid { value });
+    }
+
+    #[test]
+    fn try_rem() {
+        let first = "89423ajd".to_string();
+        let second = "q9328paslu;d".to_string();
+
+        let error_string = format!("Cannot perform remainder with '{first}' and '{second}'");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::TryRem(first, second));
+    }
+
+    #[test]
+    fn arithmetic_overflow() {
+        let value = "a value".to_string();
+
+        let error_string =
+            format!("Failed to compute: \"{value}\", as the operation results in an overflow.");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::ArithmeticOverflow(value));
+    }
+
+    #[test]
+    fn query_too_large() {
+        let error_string =
+            "Size of query script exceeded maximum supported size of 4,294,967,295 bytes.";
+
+        let error = QueryError::from_string(error_string).unwrap();
+
+        assert_eq!(error, QueryError::QueryTooLarge);
+    }
+
+    #[test]
+    fn deprecated() {
+        let error = QueryError::Deprecated("a message".to_string());
+
+        assert_eq!(error.to_string(), "a message");
+        assert_eq!(
+            QueryError::parse("a message"),
+            QueryError::Unknown("a message".to_string())
+        );
+    }
+
+    #[test]
+    fn unknown() {
+        let error_string = "Something the parser has never seen before";
+
+        assert_eq!(QueryError::from_string(error_string), None);
+
+        let error = QueryError::parse(error_string);
+
+        assert_eq!(error, QueryError::Unknown(error_string.to_string()));
+        assert_eq!(error.to_string(), error_string);
+    }
+
+    #[test]
+    fn only_whole_messages_match() {
+        assert_eq!(
+            QueryError::from_string("prefix The table 'x' does not exist"),
+            None
+        );
+        assert_eq!(
+            QueryError::from_string("The table 'x' does not exist suffix"),
+            None
+        );
+    }
+
+    #[test]
+    fn multi_line_message() {
+        let message =
+            "Unexpected token `FORM`\n --> [1:10]\n  |\n1 | SELECT * FORM user".to_string();
+
+        let error_string = format!("Parse error: {message}");
+
+        let error = QueryError::from_string(&error_string).unwrap();
+
+        assert_eq!(error, QueryError::InvalidQueryDetail { message });
+
+        // Vars only span lines in `dotall` patterns
+        assert_eq!(
+            QueryError::from_string("The table 'a\nb' does not exist"),
+            None
+        );
+    }
+
+    #[derive(StrPattern, Debug, PartialEq)]
+    enum Specificity {
+        #[str_pattern("{0}")]
+        CatchAll(String),
+        #[str_pattern("The {0} '{1}' does not exist")]
+        NotFound(String, String),
+        #[str_pattern("The table '{0}' does not exist")]
+        TbNotFound(String),
+    }
+
+    #[derive(StrPattern, Debug, PartialEq)]
+    enum Priority {
+        #[str_pattern("The table '{0}' does not exist")]
+        TbNotFound(String),
+        #[str_pattern("The {0} '{1}' does not exist", priority = 1)]
+        NotFound(String, String),
+    }
+
+    #[derive(StrPattern, Debug, PartialEq)]
+    enum Bounded {
+        #[str_pattern("Found `{field}` in '{value}'", field = bounded)]
+        Found { field: String, value: String },
+        #[str_pattern("Cannot add '{0}' and '{1}'", 0 = bounded)]
+        Add(String, String),
+        #[str_pattern("Cannot join '{0}' and '{1}' with {2}", captures = bounded)]
+        Join(String, String, String),
+        #[str_pattern("Split {0} on {1} into {2}", captures = lazy, 2 = greedy)]
+        Split(String, String, String),
+    }
+
+    #[derive(StrPattern, Debug, PartialEq)]
+    enum Typed {
+        #[str_pattern("Retry {attempt} of {max} after {delay}s, idempotent: {idempotent}")]
+        Retry {
+            attempt: u32,
+            max: u8,
+            delay: f64,
+            idempotent: bool,
+        },
+        #[str_pattern("Retry {0}")]
+        Other(String),
+    }
+
+    #[test]
+    fn typed_fields() {
+        assert_eq!(
+            Typed::from_string("Retry 2 of 5 after 0.5s, idempotent: true"),
+            Some(Typed::Retry {
+                attempt: 2,
+                max: 5,
+                delay: 0.5,
+                idempotent: true
+            })
+        );
+        assert_eq!(
+            Typed::from_string("Retry 2 of 500 after 0.5s, idempotent: true"),
+            Some(Typed::Other(
+                "2 of 500 after 0.5s, idempotent: true".to_string()
+            ))
+        );
+        assert_eq!(
+            Typed::from_string("Retry 2 of 5 after 0.5s, idempotent: yes"),
+            Some(Typed::Other(
+                "2 of 5 after 0.5s, idempotent: yes".to_string()
+            ))
+        );
+    }
+
+    #[derive(StrPattern, Debug, PartialEq)]
+    enum Constrained {
+        #[str_pattern("Key {table}{id}", table = r"[a-z]+")]
+        Key { table: String, id: u64 },
+    }
+
+    #[test]
+    fn regex_capture() {
+        assert_eq!(
+            Constrained::from_string("Key user42"),
+            Some(Constrained::Key {
+                table: "user".to_string(),
+                id: 42
+            })
+        );
+        assert_eq!(Constrained::from_string("Key User42"), None);
+    }
+
+    #[derive(StrPattern, Debug, PartialEq)]
+    enum Aliases {
+        #[str_pattern("The table '{value}' does not exist")]
+        #[str_pattern("Table '{value}' not found")]
+        #[str_pattern("No such table: {value}")]
+        TbNotFound { value: String },
+    }
+
+    #[test]
+    fn every_alias_matches() {
+        for message in [
+            "The table 'product' does not exist",
+            "Table 'product' not found",
+            "No such table: product",
+        ] {
+            let error = Aliases::from_string(message).unwrap();
+
+            assert_eq!(
+                error,
+                Aliases::TbNotFound {
+                    value: "product".to_string()
+                }
+            );
+            assert_eq!(error.to_string(), "The table 'product' does not exist");
+        }
+    }
+
+    #[derive(StrPattern, Debug, PartialEq)]
+    enum Raw {
+        #[str_pattern("The table '{value}' does not exist")]
+        #[str_pattern(regex = r"^(?:Table|Relation) `(?<value>[^`]+)` is missing$")]
+        TbNotFound { value: String },
+        #[str_pattern("Expected {0} rows, got {1}")]
+        #[str_pattern(regex = r"^(?<_1>\d+) of (?<_0>\d+) rows$", priority = 1)]
+        Rows(u32, u32),
+    }
+
+    #[test]
+    fn raw_regex() {
+        for message in [
+            "The table 'product' does not exist",
+            "Table `product` is missing",
+            "Relation `product` is missing",
+        ] {
+            let error = Raw::from_string(message).unwrap();
+
+            assert_eq!(
+                error,
+                Raw::TbNotFound {
+                    value: "product".to_string()
+                }
+            );
+            assert_eq!(error.to_string(), "The table 'product' does not exist");
+        }
+
+        let error = Raw::from_string("3 of 5 rows").unwrap();
+
+        assert_eq!(error, Raw::Rows(5, 3));
+        assert_eq!(error.to_string(), "Expected 5 rows, got 3");
+        assert_eq!(Raw::from_string("x of 5 rows"), None);
+    }
+
+    #[derive(StrPattern, Debug, PartialEq)]
+    enum Casing {
+        #[str_pattern("The table '{value}' does not exist", case_insensitive)]
+        TbNotFound { value: String },
+        #[str_pattern("Query timed out")]
+        QueryTimedout,
+    }
+
+    #[derive(StrPattern, Debug, PartialEq)]
+    #[str_pattern(case_insensitive)]
+    enum AnyCasing {
+        #[str_pattern("Query timed out")]
+        QueryTimedout,
+        #[str_pattern("The query was cancelled", parse = false)]
+        #[str_pattern(regex = r"^Query cancell?ed$")]
+        QueryCancelled,
+    }
+
+    #[test]
+    fn case_insensitive() {
+        assert_eq!(
+            Casing::from_string("THE TABLE 'Product' DOES NOT EXIST"),
+            Some(Casing::TbNotFound {
+                value: "Product".to_string()
+            })
+        );
+        assert_eq!(Casing::from_string("query timed out"), None);
+        assert_eq!(
+            AnyCasing::from_string("query TIMED out"),
+            Some(AnyCasing::QueryTimedout)
+        );
+        assert_eq!(
+            AnyCasing::from_string("QUERY CANCELLED"),
+            Some(AnyCasing::QueryCancelled)
+        );
+    }
+
+    #[derive(StrPattern, Debug, PartialEq)]
+    #[str_pattern(display = false)]
+    enum OwnDisplay {
+        #[str_pattern("The table '{value}' does not exist")]
+        TbNotFound { value: String },
+        #[str_pattern(regex = r"^Query (?:timed out|took too long)$")]
+        QueryTimedout,
+    }
+
+    impl std::fmt::Display for OwnDisplay {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str(self.code())
+        }
+    }
+
+    #[test]
+    fn display_opt_out() {
+        assert_eq!(
+            OwnDisplay::from_string("The table 'product' does not exist")
+                .unwrap()
+                .to_string(),
+            "TbNotFound"
+        );
+        assert_eq!(
+            OwnDisplay::from_string("Query took too long"),
+            Some(OwnDisplay::QueryTimedout)
+        );
+    }
+
+    #[derive(StrPattern, Debug, PartialEq)]
+    enum FallbackFirst {
+        #[str_pattern(fallback)]
+        Unknown(String),
+        #[str_pattern("The table '{value}' does not exist")]
+        TbNotFound { value: String },
+    }
+
+    #[test]
+    fn fallback_is_tried_last() {
+        assert_eq!(
+            FallbackFirst::parse("The table 'product' does not exist"),
+            FallbackFirst::TbNotFound {
+                value: "product".to_string()
+            }
+        );
+        assert_eq!(
+            FallbackFirst::parse("anything else"),
+            FallbackFirst::Unknown("anything else".to_string())
+        );
+        assert_eq!(FallbackFirst::from_string("anything else"), None);
+        assert_eq!(
+            FallbackFirst::Unknown("anything else".to_string()).to_string(),
+            "anything else"
+        );
+    }
+
+    #[derive(StrPattern, Debug, PartialEq)]
+    enum Optional {
+        #[str_pattern("Failed to commit the transaction[: {message}]")]
+        TxFailure { message: Option<String> },
+        #[str_pattern("Retry {0}[ after {1}s] [of {2}]", captures = lazy)]
+        Retry(u32, Option<f64>, Option<u32>),
+    }
+
+    #[test]
+    fn optional_segments() {
+        for (message, expected) in [
+            (
+                "Failed to commit the transaction",
+                Optional::TxFailure { message: None },
+            ),
+            (
+                "Failed to commit the transaction: conflict",
+                Optional::TxFailure {
+                    message: Some("conflict".to_string()),
+                },
+            ),
+            ("Retry 2 ", Optional::Retry(2, None, None)),
+            ("Retry 2 after 0.5s ", Optional::Retry(2, Some(0.5), None)),
+            (
+                "Retry 2 after 0.5s of 5",
+                Optional::Retry(2, Some(0.5), Some(5)),
+            ),
+        ] {
+            let error = Optional::from_string(message).unwrap();
+
+            assert_eq!(error, expected);
+            assert_eq!(error.to_string(), message);
+        }
+
+        assert_eq!(Optional::from_string("Retry 2 after soons "), None);
+        assert_eq!(
+            Optional::Retry(2, None, Some(5)).fields(),
+            vec![("0", "2".to_string()), ("2", "5".to_string())]
+        );
+    }
+
+    #[derive(StrPattern, Debug, PartialEq)]
+    #[str_pattern("{timestamp} {level} {message}", timestamp = r"\S+", level = r"[A-Z]+")]
+    struct LogLine {
+        timestamp: String,
+        level: String,
+        message: String,
+    }
+
+    #[derive(StrPattern, Debug, PartialEq)]
+    #[str_pattern("Retrying in {0}ms")]
+    #[str_pattern(regex = r"^Retry after (?<_0>\d+)ms$")]
+    struct RetryIn(u64);
+
+    #[test]
+    fn structs() {
+        let line = "2024-05-01T12:00:00Z ERROR The table 'product' does not exist";
+
+        let parsed = LogLine::from_string(line).unwrap();
+
+        assert_eq!(
+            parsed,
+            LogLine {
+                timestamp: "2024-05-01T12:00:00Z".to_string(),
+                level: "ERROR".to_string(),
+                message: "The table 'product' does not exist".to_string(),
+            }
+        );
+        assert_eq!(parsed.to_string(), line);
+        assert_eq!(parsed.code(), "LogLine");
+        assert_eq!(LogLine::from_string("no level here"), None);
+
+        assert_eq!(RetryIn::from_string("Retry after 50ms"), Some(RetryIn(50)));
+        assert_eq!(RetryIn(50).to_string(), "Retrying in 50ms");
+        assert_eq!(RetryIn(50).fields(), vec![("0", "50".to_string())]);
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct TableName(String);
+
+    impl capture::FromCapture for TableName {
+        fn from_capture(capture: &str) -> Option<Self> {
+            (!capture.is_empty()).then(|| TableName(capture.to_string()))
+        }
+    }
+
+    impl std::fmt::Display for TableName {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str(&self.0)
+        }
+    }
+
+    #[derive(StrPattern, Debug, PartialEq)]
+    enum Generic<T: capture::FromCapture + std::fmt::Display> {
+        #[str_pattern("The table '{table}' does not exist")]
+        TbNotFound { table: TableName },
+        #[str_pattern("Expected {0}")]
+        Expected(T),
+    }
+
+    #[test]
+    fn custom_field_types() {
+        assert_eq!(
+            Generic::<u32>::from_string("The table 'product' does not exist"),
+            Some(Generic::TbNotFound {
+                table: TableName("product".to_string())
+            })
+        );
+        assert_eq!(
+            Generic::<u32>::from_string("The table '' does not exist"),
+            None
+        );
+        assert_eq!(
+            Generic::<u32>::from_string("Expected 42"),
+            Some(Generic::Expected(42))
+        );
+        assert_eq!(Generic::<u32>::from_string("Expected x"), None);
+        assert_eq!(Generic::Expected(true).to_string(), "Expected true");
+    }
+
+    #[derive(StrPattern, Debug, PartialEq)]
+    #[str_pattern(crate = "crate")]
+    enum CratePath {
+        #[str_pattern("The table '{0}' does not exist")]
+        TbNotFound(String),
+    }
+
+    #[derive(StrPattern, Debug, PartialEq)]
+    #[str_pattern(crate = "crate")]
+    #[str_pattern("Retrying in {0}ms")]
+    struct CratePathStruct(u64);
+
+    #[test]
+    fn crate_path() {
+        assert_eq!(
+            CratePath::from_string("The table 'product' does not exist"),
+            Some(CratePath::TbNotFound("product".to_string()))
+        );
+        assert_eq!(
+            CratePathStruct::from_string("Retrying in 50ms"),
+            Some(CratePathStruct(50))
+        );
+    }
+
+    #[derive(StrPattern, Debug, PartialEq)]
+    #[str_pattern(no_std, crate = "crate")]
+    enum NoStd {
+        #[str_pattern("Conditional clause is not truthy")]
+        Ignore,
+        #[str_pattern("The table '{0}' does not exist")]
+        TbNotFound(String),
+        #[str_pattern("Found {value} for field `{field}`, but expected a {kind}", value = lazy)]
+        FieldCheck {
+            value: String,
+            field: String,
+            kind: String,
+        },
+        #[str_pattern("'{a}' equals '{a}' in {b}ms", a = bounded)]
+        Repeated { a: String, b: u64 },
+        #[str_pattern("{0} {1} {2} {3} {4} {5}.")]
+        Words(String, String, String, String, String, String),
+    }
+
+    #[derive(StrPattern, Debug, PartialEq)]
+    #[str_pattern(no_std)]
+    #[str_pattern(crate = "crate")]
+    #[str_pattern("Retrying in {0}ms")]
+    struct NoStdStruct(u64);
+
+    #[test]
+    fn no_std() {
+        assert_eq!(
+            NoStd::from_string("Conditional clause is not truthy"),
+            Some(NoStd::Ignore)
+        );
+        assert_eq!(
+            NoStd::from_string("The table 'it's' does not exist"),
+            Some(NoStd::TbNotFound("it's".to_string()))
+        );
+        assert_eq!(
+            NoStd::from_string("Found 1, but 2 for field `price`, but expected a decimal"),
+            Some(NoStd::FieldCheck {
+                value: "1, but 2".to_string(),
+                field: "price".to_string(),
+                kind: "decimal".to_string(),
+            })
+        );
+        assert_eq!(
+            NoStd::from_string("'x' equals 'x' in 5ms"),
+            Some(NoStd::Repeated {
+                a: "x".to_string(),
+                b: 5,
+            })
+        );
+        assert_eq!(NoStd::from_string("'x' equals 'y' in 5ms"), None);
+        assert_eq!(NoStd::from_string("'x' equals 'x' in 5sms"), None);
+        assert_eq!(NoStd::from_string("Conditional clause"), None);
+        assert_eq!(NoStd::from_string("The table 'a\nb' does not exist"), None);
+        // Every split of the words between the vars fails, which has to be found out without
+        // trying each one
+        assert_eq!(NoStd::from_string(&"word ".repeat(200)), None);
+        assert_eq!(
+            NoStd::TbNotFound("product".to_string()).fields(),
+            vec![("0", "product".to_string())]
+        );
+        assert_eq!(
+            NoStdStruct::from_string("Retrying in 50ms"),
+            Some(NoStdStruct(50))
+        );
+        assert_eq!(NoStdStruct(50).to_string(), "Retrying in 50ms");
+    }
+
+    #[derive(StrPattern, Debug, PartialEq)]
+    enum Padded {
+        #[str_pattern("Found {value} for field `{field}`", dotall)]
+        FieldCheck {
+            #[str_pattern(collapse_whitespace)]
+            value: String,
+            #[str_pattern(trim)]
+            field: String,
+        },
+        #[str_pattern("Retrying in {0}ms[ ({1})]", 0 = lazy)]
+        RetryIn(
+            #[str_pattern(trim)] u64,
+            #[str_pattern(trim, collapse_whitespace)] Option<String>,
+        ),
+    }
+
+    #[test]
+    fn whitespace() {
+        assert_eq!(
+            Padded::from_string("Found   'a \n  b'  for field ` price\t`"),
+            Some(Padded::FieldCheck {
+                value: "'a b'".to_string(),
+                field: "price".to_string(),
+            })
+        );
+        assert_eq!(
+            Padded::from_string("Retrying in  50 ms ( after  a  conflict )"),
+            Some(Padded::RetryIn(50, Some("after a conflict".to_string())))
+        );
+        assert_eq!(
+            Padded::from_string("Retrying in 50 ms"),
+            Some(Padded::RetryIn(50, None))
+        );
+    }
+
+    #[test]
+    fn most_specific_pattern_wins() {
+        assert_eq!(
+            Specificity::from_string("The table 'x' does not exist"),
+            Some(Specificity::TbNotFound("x".to_string()))
+        );
+        assert_eq!(
+            Specificity::from_string("The index 'x' does not exist"),
+            Some(Specificity::NotFound("index".to_string(), "x".to_string()))
+        );
+        assert_eq!(
+            Specificity::from_string("anything else"),
+            Some(Specificity::CatchAll("anything else".to_string()))
+        );
+    }
+
+    #[test]
+    fn priority_overrides_specificity() {
+        assert_eq!(
+            Priority::from_string("The table 'x' does not exist"),
+            Some(Priority::NotFound("table".to_string(), "x".to_string()))
+        );
+    }
+
+    #[test]
+    fn bounded_capture() {
+        assert_eq!(
+            Bounded::from_string("Found `a` in 'x` in 'y'"),
+            Some(Bounded::Found {
+                field: "a".to_string(),
+                value: "x` in 'y".to_string()
+            })
+        );
+        assert_eq!(
+            Bounded::from_string("Cannot add 'a' and 'b' and 'c'"),
+            Some(Bounded::Add("a".to_string(), "b' and 'c".to_string()))
+        );
+        assert_eq!(
+            Bounded::from_string("Cannot join 'a' and 'b' with x with y"),
+            Some(Bounded::Join(
+                "a".to_string(),
+                "b".to_string(),
+                "x with y".to_string()
+            ))
+        );
+        assert_eq!(
+            Bounded::from_string("Cannot join 'a' and 'b' and 'c' with x"),
+            None
+        );
+        assert_eq!(
+            Bounded::from_string("Split a on b on c into d into e"),
+            Some(Bounded::Split(
+                "a".to_string(),
+                "b on c".to_string(),
+                "d into e".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn from_string_with_untagged_pattern() {
+        let error_string = "The table 'product' does not exist";
+
+        let expected = QueryError::TbNotFound {
+            value: "product".to_string(),
+        };
+
+        assert_eq!(
+            QueryError::from_string_with(error_string, Dialect::V1),
+            Some(expected.clone())
+        );
+        assert_eq!(
+            QueryError::from_string_with(error_string, Dialect::V2),
+            Some(expected)
+        );
+    }
+
+    #[test]
+    fn from_string_with_v1() {
+        let error = QueryError::from_string_with(
+            "Found 'a name' but it is not possible to set a variable with this name",
+            Dialect::V1,
+        )
+        .unwrap();
+
+        assert_eq!(
+            error,
+            QueryError::InvalidParam {
+                name: "a name".to_string()
+            }
+        );
+
+        let error_string = "'a name' is a protected variable and cannot be set";
+
+        assert_eq!(
+            QueryError::from_string_with(error_string, Dialect::V1),
+            None
+        );
+    }
+
+    #[test]
+    fn from_string_with_v2() {
+        let error = QueryError::from_string_with(
+            "'a name' is a protected variable and cannot be set",
+            Dialect::V2,
+        )
+        .unwrap();
+
+        assert_eq!(
+            error,
+            QueryError::InvalidParam {
+                name: "a name".to_string()
+            }
+        );
+
+        let error_string = "The namespace token 'a value' does not exist";
+
+        assert_eq!(
+            QueryError::from_string_with(error_string, Dialect::V2),
+            None
+        );
+    }
+
+    #[test]
+    fn category() {
+        let error = QueryError::from_string("Break statement has been reached").unwrap();
+
+        assert_eq!(error.category(), category::Category::ControlFlow);
+
+        let error = QueryError::from_string("The table 'product' does not exist").unwrap();
+
+        assert_eq!(error.category(), category::Category::NotFound);
+    }
+
+    #[test]
+    fn is_retryable() {
+        let error =
+            QueryError::from_string("The query was not executed because it exceeded the timeout")
+                .unwrap();
+
+        assert!(error.is_retryable());
+        assert!(!error.is_client_error());
+    }
+
+    #[test]
+    fn is_client_error() {
+        let error =
+            QueryError::from_string("Database record `user:tester` already exists").unwrap();
+
+        assert!(error.is_client_error());
+        assert!(!error.is_retryable());
+
+        let error = QueryError::from_string("Index is corrupted").unwrap();
+
+        assert!(!error.is_client_error());
+        assert!(!error.is_retryable());
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(
+            QueryError::Ignore.to_string(),
+            "Conditional clause is not truthy"
+        );
+
+        assert_eq!(
+            QueryError::TryAdd("1".to_string(), "a".to_string()).to_string(),
+            "Cannot perform addition with '1' and 'a'"
+        );
+
+        assert_eq!(
+            QueryError::InvalidQuery {
+                line: 1,
+                char: 14,
+                sql: "SELECT * FORM user".to_string()
+            }
+            .to_string(),
+            "Parse error on line 1 at character 14 when parsing 'SELECT * FORM user'"
+        );
+
+        assert_eq!(
+            QueryError::ConvertTo {
+                from: "'abc'".to_string(),
+                into: "int".to_string()
+            }
+            .to_string(),
+            "Expected a int but cannot convert 'abc' into a int"
+        );
+    }
+
+    #[test]
+    fn error() {
+        let error: Box<dyn std::error::Error> =
+            Box::new(QueryError::from_string("Index is corrupted").unwrap());
+
+        assert_eq!(error.to_string(), "Index is corrupted");
+    }
+
+    #[test]
+    fn round_trip() {
+        // Values can contain the quotes and separators of the templates around them. The two
+        // exceptions documented on `from_string` are left out
+        let values = [
+            ("a", "b"),
+            ("say \"hi\", then: x", "one, two: three"),
+            ("it's `quoted`", "x' or 'y"),
+            ("a. b", "c, but d"),
+        ];
+
+        for (first, second) in values {
+            let a = || first.to_string();
+            let b = || second.to_string();
+
+            let errors = [
+                QueryError::Ignore,
+                QueryError::Break,
+                QueryError::Continue,
+                QueryError::UnreachableDetail { message: a() },
+                QueryError::Unreachable,
+                QueryError::Thrown(a()),
+                QueryError::Ds(a()),
+                QueryError::Tx(a()),
+                QueryError::TxFailure,
+                QueryError::TxFinished,
+                QueryError::TxReadonly,
+                QueryError::TxConditionNotMet,
+                QueryError::TxKeyAlreadyExists,
+                QueryError::TxKeyTooLarge,
+                QueryError::TxValueTooLarge,
+                QueryError::TxTooLarge,
+                QueryError::NsEmpty,
+                QueryError::DbEmpty,
+                QueryError::QueryEmpty,
+                QueryError::QueryRemaining,
+                QueryError::InvalidAuth,
+                QueryError::UnknownAuth,
+                QueryError::InvalidQuery {
+                    line: 1,
+                    char: 2,
+                    sql: a(),
+                },
+                QueryError::InvalidQueryDetail { message: a() },
+                QueryError::InvalidPatch { message: a() },
+                QueryError::PatchTest {
+                    expected: a(),
+                    got: b(),
+                },
+                QueryError::HttpDisabled,
+                QueryError::InvalidParam { name: a() },
+                QueryError::InvalidField {
+                    line: 1,
+                    field: a(),
+                },
+                QueryError::InvalidSplit {
+                    line: 1,
+                    field: a(),
+                },
+                QueryError::InvalidOrder {
+                    line: 1,
+                    field: a(),
+                },
+                QueryError::InvalidGroup {
+                    line: 1,
+                    field: a(),
+                },
+                QueryError::InvalidLimit { value: a() },
+                QueryError::InvalidStart { value: a() },
+                QueryError::InvalidScript { message: a() },
+                QueryError::InvalidFunction {
+                    name: a(),
+                    message: b(),
+                },
+                QueryError::InvalidArguments {
+                    name: a(),
+                    message: b(),
+                },
+                QueryError::InvalidUrl(a()),
+                QueryError::QueryTimedout,
+                QueryError::QueryCancelled,
+                QueryError::QueryNotExecutedDetail { message: a() },
+                QueryError::QueryNotExecuted,
+                QueryError::NsNotAllowed { ns: a() },
+                QueryError::DbNotAllowed { db: a() },
+                QueryError::NsNotFound { value: a() },
+                QueryError::NtNotFound { value: a() },
+                QueryError::NlNotFound { value: a() },
+                QueryError::DbNotFound { value: a() },
+                QueryError::DtNotFound { value: a() },
+                QueryError::DlNotFound { value: a() },
+                QueryError::FcNotFound { value: a() },
+                QueryError::ScNotFound { value: a() },
+                QueryError::NoScopeFound,
+                QueryError::ClAlreadyExists { value: a() },
+                QueryError::NdNotFound { value: a() },
+                QueryError::StNotFound { value: a() },
+                QueryError::PaNotFound { value: a() },
+                QueryError::TbNotFound { value: a() },
+                QueryError::LvNotFound { value: a() },
+                QueryError::LqNotFound { value: a() },
+                QueryError::AzNotFound { value: a() },
+                QueryError::IxNotFound { value: a() },
+                QueryError::UserRootNotFound { value: a() },
+                QueryError::UserNsNotFound {
+                    value: a(),
+                    ns: b(),
+                },
+                QueryError::UserDbNotFound {
+                    value: a(),
+                    db: b(),
+                },
+                QueryError::RealtimeDisabled,
+                QueryError::ComputationDepthExceeded,
+                QueryError::InvalidStatementTarget { value: a() },
+                QueryError::CreateStatement { value: a() },
+                QueryError::UpdateStatement { value: a() },
+                QueryError::RelateStatement { value: a() },
+                QueryError::DeleteStatement { value: a() },
+                QueryError::InsertStatement { value: a() },
+                QueryError::LiveStatement { value: a() },
+                QueryError::KillStatement { value: a() },
+                QueryError::TablePermissions { table: a() },
+                QueryError::TableIsView { table: a() },
+                QueryError::RecordExists { thing: a() },
+                QueryError::IndexExists {
+                    thing: a(),
+                    index: b(),
+                    value: "c".to_string(),
+                },
+                QueryError::FieldCheck {
+                    thing: a(),
+                    value: b(),
+                    field: "c".to_string(),
+                    check: "d".to_string(),
+                },
+                QueryError::FieldValue {
+                    thing: a(),
+                    value: b(),
+                    field: "c".to_string(),
+                    check: "d".to_string(),
+                },
+                QueryError::IdMismatch { value: a() },
+                QueryError::IdInvalid { value: a() },
+                QueryError::CoerceTo {
+                    from: a(),
+                    into: b(),
+                },
+                QueryError::ConvertTo {
+                    from: a(),
+                    into: b(),
+                },
+                QueryError::LengthInvalid { kind: a(), size: 3 },
+                QueryError::TryAdd(a(), b()),
+                QueryError::TrySub(a(), b()),
+                QueryError::TryMul(a(), b()),
+                QueryError::TryDiv(a(), b()),
+                QueryError::TryPow(a(), b()),
+                QueryError::TryNeg(a()),
+                QueryError::TryFrom(a(), b()),
+                QueryError::Http(a()),
+                QueryError::Channel(a()),
+                QueryError::Io(a()),
+                QueryError::Encode(a()),
+                QueryError::Decode(a()),
+                QueryError::Revision(a()),
+                QueryError::CorruptedIndexDetail { message: a() },
+                QueryError::CorruptedIndex,
+                QueryError::NoIndexFoundForMatch { value: a() },
+                QueryError::AnalyzerError(a()),
+                QueryError::HighlightError(a()),
+                QueryError::Bincode(a()),
+                QueryError::FstError(a()),
+                QueryError::Utf8Error(a()),
+                QueryError::FeatureNotYetImplemented { feature: a() },
+                QueryError::DuplicatedMatchRef { mr: a() },
+                QueryError::TimestampOverflow(a()),
+                QueryError::Internal(a()),
+                QueryError::Unimplemented(a()),
+                QueryError::CorruptedVersionstampInKey(a()),
+                QueryError::InvalidLevel(a()),
+                QueryError::IamError(a()),
+                QueryError::ScriptingNotAllowed,
+                QueryError::FunctionNotAllowed(a()),
+                QueryError::NetTargetNotAllowed(a()),
+                QueryError::Return,
+                QueryError::ExpiredSession,
+                QueryError::ExpiredToken,
+                QueryError::SignupQueryFailed,
+                QueryError::SigninQueryFailed,
+                QueryError::InvalidSignup,
+                QueryError::InvalidPass,
+                QueryError::MissingUserOrPass,
+                QueryError::NoRecordFound,
+                QueryError::AccessRootNotFound { ac: a() },
+                QueryError::AccessNsNotFound { ac: a(), ns: b() },
+                QueryError::AccessDbNotFound { ac: a(), db: b() },
+                QueryError::AccessGrantRootNotFound { gr: a() },
+                QueryError::AccessGrantNsNotFound { gr: a(), ns: b() },
+                QueryError::AccessGrantDbNotFound { gr: a(), db: b() },
+                QueryError::AccessNotFound,
+                QueryError::AccessMethodMismatch,
+                QueryError::AccessGrantRevoked,
+                QueryError::AccessRecordSignupQueryFailed,
+                QueryError::AccessRecordSigninQueryFailed,
+                QueryError::AccessRecordNoSignup,
+                QueryError::AccessRecordNoSignin,
+                QueryError::EvNotFound { value: a() },
+                QueryError::FdNotFound { value: a() },
+                QueryError::MlNotFound { value: a() },
+                QueryError::IdNotFound { value: a() },
+                QueryError::NsAlreadyExists { value: a() },
+                QueryError::DbAlreadyExists { value: a() },
+                QueryError::TbAlreadyExists { value: a() },
+                QueryError::FdAlreadyExists { value: a() },
+                QueryError::IxAlreadyExists { value: a() },
+                QueryError::EvAlreadyExists { value: a() },
+                QueryError::FcAlreadyExists { value: a() },
+                QueryError::PaAlreadyExists { value: a() },
+                QueryError::AzAlreadyExists { value: a() },
+                QueryError::MlAlreadyExists { value: a() },
+                QueryError::AccessRootAlreadyExists { ac: a() },
+                QueryError::AccessNsAlreadyExists { ac: a(), ns: b() },
+                QueryError::AccessDbAlreadyExists { ac: a(), db: b() },
+                QueryError::UserRootAlreadyExists { value: a() },
+                QueryError::UserNsAlreadyExists {
+                    value: a(),
+                    ns: b(),
+                },
+                QueryError::UserDbAlreadyExists {
+                    value: a(),
+                    db: b(),
+                },
+                QueryError::IndexAlreadyBuilding { index: a() },
+                QueryError::UpsertStatement { value: a() },
+                QueryError::RelateStatementIn { value: a() },
+                QueryError::RelateStatementId { value: a() },
+                QueryError::RelateStatementOut { value: a() },
+                QueryError::InsertStatementIn { value: a() },
+                QueryError::InsertStatementId { value: a() },
+                QueryError::InsertStatementOut { value: a() },
+                QueryError::InvalidContent { value: a() },
+                QueryError::InvalidMerge { value: a() },
+                QueryError::SingleOnlyOutput,
+                QueryError::ParamPermissions { name: a() },
+                QueryError::FunctionPermissions { name: a() },
+                QueryError::FieldReadonly {
+                    field: a(),
+                    thing: b(),
+                },
+                QueryError::FieldUndefined {
+                    field: a(),
+                    table: b(),
+                },
+                QueryError::SetCheck {
+                    value: a(),
+                    name: b(),
+                    check: a(),
+                },
+                QueryError::TbInvalid { value: a() },
+                QueryError::TryRem(a(), b()),
+                QueryError::ArithmeticOverflow(a()),
+                QueryError::QueryTooLarge,
+                QueryError::Unknown(a()),
+            ];
+
+            for error in errors {
+                let error_string = error.to_string();
+
+                let parsed = QueryError::parse(&error_string);
+
+                assert_eq!(parsed, error);
+                assert_eq!(parsed.to_string(), error_string);
+            }
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialize() {
+        let error = QueryError::TbNotFound {
+            value: "product".to_string(),
+        };
+
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            serde_json::json!({ "code": "TbNotFound", "fields": { "value": "product" } })
+        );
+
+        assert_eq!(
+            serde_json::to_value(QueryError::QueryEmpty).unwrap(),
+            serde_json::json!({ "code": "QueryEmpty" })
+        );
+
+        assert_eq!(
+            serde_json::to_value(QueryError::TryAdd("1".to_string(), "a".to_string())).unwrap(),
+            serde_json::json!({ "code": "TryAdd", "fields": ["1", "a"] })
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialize() {
+        let errors = [
+            QueryError::QueryEmpty,
+            QueryError::Thrown("CART_EMPTY".to_string()),
+            QueryError::TryAdd("1".to_string(), "a".to_string()),
+            QueryError::InvalidQuery {
+                line: 1,
+                char: 14,
+                sql: "SELECT * FORM user".to_string(),
+            },
+        ];
+
+        for error in errors {
+            let json = serde_json::to_string(&error).unwrap();
+
+            assert_eq!(serde_json::from_str::<QueryError>(&json).unwrap(), error);
+        }
+    }
+
+    #[test]
+    fn redacted() {
+        let error = QueryError::from_string(
+            "Found 'jane@example.com' for field `email`, with record `user:jane`, but field must conform to: string::is::email($value)",
+        )
+        .unwrap();
+
+        assert_eq!(
+            error.redacted(),
+            QueryError::FieldValue {
+                thing: "user:[redacted]".to_string(),
+                value: "[redacted]".to_string(),
+                field: "email".to_string(),
+                check: "string::is::email($value)".to_string(),
+            }
+        );
+
+        assert_eq!(
+            QueryError::RecordExists {
+                thing: "jane".to_string()
+            }
+            .redacted(),
+            QueryError::RecordExists {
+                thing: "[redacted]".to_string()
+            }
+        );
+
+        assert_eq!(
+            QueryError::Ds("conflict on key user:jane".to_string()).redacted(),
+            QueryError::Ds("[redacted]".to_string())
+        );
+
+        let error = QueryError::TbNotFound {
+            value: "user".to_string(),
+        };
+
+        assert_eq!(error.redacted(), error);
+    }
+
+    #[test]
+    fn patterns() {
+        let patterns = QueryError::PATTERNS;
+
+        assert_eq!(
+            patterns.first(),
+            Some(&pattern::PatternInfo {
+                variant: "Ignore",
+                kind: QueryErrorKind::Ignore,
+                template: "Conditional clause is not truthy",
+                fields: &[],
+            })
+        );
+        assert!(patterns.contains(&pattern::PatternInfo {
+            variant: "IndexExists",
+            kind: QueryErrorKind::IndexExists,
+            template: "Database index `{index}` already contains {value}, with record `{thing}`",
+            fields: &["thing", "index", "value"],
+        }));
+        assert!(patterns.contains(&pattern::PatternInfo {
+            variant: "TryAdd",
+            kind: QueryErrorKind::TryAdd,
+            template: "Cannot perform addition with '{0}' and '{1}'",
+            fields: &["0", "1"],
+        }));
+        assert!(!patterns.iter().any(|pattern| pattern.variant == "Unknown"));
+        assert!(patterns
+            .iter()
+            .all(|pattern| format!("{:?}", pattern.kind) == pattern.variant));
+        assert_eq!(QueryError::patterns(), patterns);
+    }
+
+    #[test]
+    fn round_trip_corpus() {
+        let mismatches = test_support::round_trip_mismatches(20, 0x5eed);
+
+        // Deprecated is only used for display, so its messages are never parsed back
+        let unexpected = mismatches
+            .iter()
+            .filter(|mismatch| mismatch.code != "Deprecated")
+            .collect::<Vec<_>>();
+
+        assert!(unexpected.is_empty(), "{unexpected:#?}");
+    }
+
+    #[test]
+    fn kind() {
+        let error = QueryError::from_string("The table 'product' does not exist").unwrap();
+
+        assert_eq!(error.kind(), QueryErrorKind::TbNotFound);
+        assert_eq!(QueryError::QueryEmpty.kind(), QueryErrorKind::QueryEmpty);
+        assert_eq!(
+            QueryError::TryAdd("1".to_string(), "'a'".to_string()).kind(),
+            QueryErrorKind::TryAdd
+        );
+
+        let conflicts = std::collections::HashSet::from([
+            QueryErrorKind::RecordExists,
+            QueryErrorKind::IndexExists,
+        ]);
+
+        assert!(!conflicts.contains(&error.kind()));
+        assert!(conflicts.contains(
+            &QueryError::RecordExists {
+                thing: "product:1".to_string()
+            }
+            .kind()
+        ));
+    }
+
+    #[test]
+    fn groups() {
+        use crate::groups::{AuthError, SchemaNotFound, TxError, TypeError};
+
+        assert_eq!(QueryError::TxFailure.as_tx_error(), Some(TxError::Failure));
+        assert_eq!(QueryError::TxFailure.as_auth_error(), None);
+
+        assert_eq!(
+            QueryError::AccessRecordSigninQueryFailed.as_auth_error(),
+            Some(AuthError::SigninFailed)
+        );
+
+        let error = QueryError::from_string("The table 'product' does not exist").unwrap();
+
+        assert_eq!(
+            error.as_schema_not_found(),
+            Some(SchemaNotFound::Table("product".to_string()))
+        );
+        assert_eq!(error.as_type_error(), None);
+
+        let error =
+            QueryError::from_string("Expected a int but cannot convert 'abc' into a int").unwrap();
+
+        assert_eq!(
+            error.as_type_error(),
+            Some(TypeError::ConvertTo {
+                from: "'abc'".to_string(),
+                into: "int".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn code_and_fields() {
+        let error = QueryError::from_string("The table 'product' does not exist").unwrap();
+
+        assert_eq!(error.code(), "TbNotFound");
+        assert_eq!(error.fields(), vec![("value", "product".to_string())]);
+
+        let error = QueryError::TryAdd("1".to_string(), "'a'".to_string());
+
+        assert_eq!(error.code(), "TryAdd");
+        assert_eq!(
+            error.fields(),
+            vec![("0", "1".to_string()), ("1", "'a'".to_string())]
+        );
+
+        assert_eq!(QueryError::QueryEmpty.code(), "QueryEmpty");
+        assert!(QueryError::QueryEmpty.fields().is_empty());
+    }
+
+    #[test]
+    fn render() {
+        let catalog = std::collections::HashMap::from([
+            ("TbNotFound", "We could not find any {value}s"),
+            ("FieldCheck", "Please enter a valid {field} ({missing})"),
+        ]);
+
+        let error = QueryError::from_string("The table 'product' does not exist").unwrap();
+
+        assert_eq!(
+            error.render(&catalog),
+            Some("We could not find any products".to_string())
+        );
+
+        let error = QueryError::from_string(
+            "Found '{value}' for field `price`, with record `product:1`, but expected a decimal",
+        )
+        .unwrap();
+
+        assert_eq!(
+            error.render(&catalog),
+            Some("Please enter a valid price ({missing})".to_string())
+        );
+
+        assert_eq!(QueryError::QueryEmpty.render(&catalog), None);
+    }
+
+    #[test]
+    fn severity() {
+        use crate::category::Severity;
+
+        assert_eq!(QueryError::Ignore.severity(), Severity::Info);
+        assert_eq!(QueryError::InvalidAuth.severity(), Severity::Warn);
+        assert_eq!(QueryError::TxFailure.severity(), Severity::Warn);
+        assert_eq!(QueryError::CorruptedIndex.severity(), Severity::Error);
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn telemetry() {
+        use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+
+        metrics::with_local_recorder(&recorder, || {
+            QueryError::from_string("The table 'product' does not exist");
+            QueryError::parse("The table 'product' does not exist");
+            QueryError::parse("a message");
+            QueryError::from_string("a message");
+        });
+
+        let mut counters = snapshotter
+            .snapshot()
+            .into_vec()
+            .into_iter()
+            .map(|(key, _, _, value)| {
+                let labels = key
+                    .key()
+                    .labels()
+                    .map(|label| format!("{}={}", label.key(), label.value()))
+                    .collect::<Vec<_>>();
+
+                (key.key().name().to_string(), labels, value)
+            })
+            .collect::<Vec<_>>();
+
+        counters.sort_by(|a, b| a.1.cmp(&b.1));
+
+        assert_eq!(
+            counters,
+            vec![
+                (
+                    telemetry::COUNTER.to_string(),
+                    vec![
+                        "code=TbNotFound".to_string(),
+                        "category=not_found".to_string()
+                    ],
+                    DebugValue::Counter(2)
+                ),
+                (
+                    telemetry::COUNTER.to_string(),
+                    vec!["code=Unknown".to_string(), "category=internal".to_string()],
+                    DebugValue::Counter(1)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn retry_hint() {
+        use std::time::Duration;
+
+        assert_eq!(
+            QueryError::TxFailure.retry_hint(),
+            Some(Duration::from_millis(50))
+        );
+        assert_eq!(
+            QueryError::QueryTimedout.retry_hint(),
+            Some(Duration::from_secs(1))
+        );
+        assert_eq!(QueryError::InvalidAuth.retry_hint(), None);
+    }
+
+    #[test]
+    fn http_status() {
+        assert_eq!(QueryError::InvalidAuth.http_status(), 401);
+        assert_eq!(
+            QueryError::TbNotFound {
+                value: "product".to_string()
+            }
+            .http_status(),
+            404
+        );
+        assert_eq!(QueryError::TxFailure.http_status(), 503);
+        assert_eq!(
+            QueryError::Unknown("a message".to_string()).http_status(),
+            500
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn to_json() {
+        let error = QueryError::from_string("The table 'product' does not exist").unwrap();
+
+        assert_eq!(
+            error.to_json(),
+            serde_json::json!({
+                "code": "TbNotFound",
+                "message": "The table 'product' does not exist",
+                "fields": { "value": "product" },
+            })
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn to_problem_details() {
+        let error = QueryError::from_string(
+            "Database index `unique_sku` already contains 'ABC-123', with record `product:1`",
+        )
+        .unwrap();
+
+        assert_eq!(
+            error.to_problem_details("https://errors.example.com/"),
+            serde_json::json!({
+                "type": "https://errors.example.com/IndexExists",
+                "title": "IndexExists",
+                "status": 409,
+                "detail": "Database index `unique_sku` already contains 'ABC-123', with record `product:1`",
+                "thing": "product:1",
+                "index": "unique_sku",
+                "value": "'ABC-123'",
+            })
+        );
+    }
+
+    #[cfg(feature = "actix")]
+    #[test]
+    fn error_response() {
+        use actix_web::{http::StatusCode, ResponseError};
+
+        let error = QueryError::RecordExists {
+            thing: "product:1".to_string(),
+        };
+
+        assert_eq!(error.status_code(), StatusCode::CONFLICT);
+
+        let response = error.error_response();
+
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        assert_eq!(
+            response.headers().get("content-type").unwrap(),
+            "application/json"
+        );
+    }
+
+    #[cfg(feature = "axum")]
+    #[test]
+    fn into_response() {
+        use axum::{http::StatusCode, response::IntoResponse};
+
+        let response = QueryError::RecordExists {
+            thing: "product:1".to_string(),
+        }
+        .into_response();
+
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        assert_eq!(response.headers()["content-type"], "application/json");
+    }
+
+    #[cfg(feature = "async-graphql")]
+    #[test]
+    fn graphql_error() {
+        use async_graphql::{ErrorExtensions, Value};
+
+        let error = QueryError::TbNotFound {
+            value: "product".to_string(),
+        }
+        .extend();
+
+        assert_eq!(error.message, "The table 'product' does not exist");
+
+        let extensions = error.extensions.unwrap();
+
+        assert_eq!(extensions.get("code"), Some(&Value::from("TbNotFound")));
+        assert_eq!(
+            extensions.get("fields"),
+            Some(&Value::from_json(serde_json::json!({ "value": "product" })).unwrap())
+        );
+    }
+
+    #[cfg(feature = "tonic")]
+    #[test]
+    fn into_status() {
+        use tonic::{Code, Status};
+
+        let status = Status::from(QueryError::TbNotFound {
+            value: "product".to_string(),
+        });
+
+        assert_eq!(status.code(), Code::NotFound);
+        assert_eq!(status.message(), "The table 'product' does not exist");
+        assert_eq!(
+            status.metadata().get(grpc::CODE_METADATA_KEY).unwrap(),
+            "TbNotFound"
+        );
+
+        assert_eq!(
+            Status::from(QueryError::QueryTimedout).code(),
+            Code::DeadlineExceeded
+        );
+    }
+
+    #[test]
+    fn rest_failure() {
+        use crate::rest::RestFailure;
+
+        let failure = RestFailure {
+            code: 400,
+            details: "Request problems detected".to_string(),
+            description: None,
+            information: Some("The table 'product' does not exist".to_string()),
+        };
+
+        assert_eq!(
+            failure.query_error(),
+            Some(QueryError::TbNotFound {
+                value: "product".to_string()
+            })
+        );
+
+        let failure = RestFailure {
+            information: None,
+            ..failure
+        };
+
+        assert_eq!(failure.query_error(), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialize_rest_failure() {
+        use crate::rest::RestFailure;
+
+        let failure: RestFailure = serde_json::from_str(
+            r#"{"code":403,"details":"Authentication failed","description":"Your authentication details are invalid. Reauthenticate using valid authentication parameters."}"#,
+        )
+        .unwrap();
+
+        assert_eq!(failure.code, 403);
+        assert_eq!(failure.information, None);
+        assert_eq!(failure.query_error(), None);
+    }
+
+    #[test]
+    fn rpc_error() {
+        use crate::rpc::{RpcError, RpcFailure};
+
+        let error = RpcError::from(RpcFailure {
+            code: -32000,
+            message: "There was a problem with the database: The table 'product' does not exist"
+                .to_string(),
+        });
+
+        assert_eq!(
+            error,
+            RpcError::Query(QueryError::TbNotFound {
+                value: "product".to_string()
+            })
+        );
+
+        let error = RpcError::from(RpcFailure {
+            code: -32000,
+            message: "An error occurred: CART_EMPTY".to_string(),
+        });
+
+        assert_eq!(
+            error,
+            RpcError::Query(QueryError::Thrown("CART_EMPTY".to_string()))
+        );
+
+        let error = RpcError::from(RpcFailure {
+            code: -32601,
+            message: "Method not found".to_string(),
+        });
+
+        assert_eq!(
+            error,
+            RpcError::MethodNotFound("Method not found".to_string())
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialize_rpc_failure() {
+        use crate::rpc::RpcFailure;
+
+        let failure: RpcFailure =
+            serde_json::from_str(r#"{"code":-32602,"message":"Invalid params"}"#).unwrap();
+
+        assert_eq!(
+            failure,
+            RpcFailure {
+                code: -32602,
+                message: "Invalid params".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn query_errors() {
+        use crate::errors::QueryErrors;
+
+        let errors = QueryErrors(vec![
+            (1, QueryError::Ignore),
+            (
+                2,
+                QueryError::TbNotFound {
+                    value: "product".to_string(),
+                },
+            ),
+            (3, QueryError::QueryNotExecuted),
+        ]);
+
+        assert_eq!(
+            errors.first_non_control_flow(),
+            Some(&(
+                2,
+                QueryError::TbNotFound {
+                    value: "product".to_string()
+                }
+            ))
+        );
+        assert!(errors.any_retryable());
+        assert_eq!(
+            errors.to_string(),
+            "3 statements failed; statement 1: Conditional clause is not truthy; statement 2: The table 'product' does not exist; statement 3: The query was not executed due to a failed transaction"
+        );
+
+        let errors = QueryErrors(vec![(0, QueryError::Ignore)]);
+
+        assert_eq!(errors.first_non_control_flow(), None);
+        assert!(!errors.any_retryable());
+        assert_eq!(
+            errors.to_string(),
+            "statement 0 failed: Conditional clause is not truthy"
+        );
+    }
+
+    #[test]
+    fn record_id() {
+        let error =
+            QueryError::from_string("Database record `product:01HQ` already exists").unwrap();
+
+        assert_eq!(
+            error.record_id(),
+            Some(("product".to_string(), "01HQ".to_string()))
+        );
+
+        let error = QueryError::RecordExists {
+            thing: "⟨order-line⟩:⟨2024:01⟩".to_string(),
+        };
+
+        assert_eq!(
+            error.record_id(),
+            Some(("order-line".to_string(), "2024:01".to_string()))
+        );
+
+        let error = QueryError::RecordExists {
+            thing: "price:{ sku: 'a:b', at: d'2024-01-01' }".to_string(),
+        };
+
+        assert_eq!(
+            error.record_id(),
+            Some((
+                "price".to_string(),
+                "{ sku: 'a:b', at: d'2024-01-01' }".to_string()
+            ))
+        );
+
+        assert_eq!(QueryError::QueryEmpty.record_id(), None);
+    }
+
+    #[test]
+    fn unique_violation() {
+        use crate::unique::UniqueViolation;
+
+        let error = QueryError::from_string(
+            "Database index `unique_sku` already contains 'ABC-123', with record `product:01HQ`",
+        )
+        .unwrap();
+
+        assert_eq!(
+            error.unique_violation(),
+            Some(UniqueViolation {
+                table: "product".to_string(),
+                record_id: "product:01HQ".to_string(),
+                index: "unique_sku".to_string(),
+                conflicting_values: vec!["ABC-123".to_string()],
+            })
+        );
+
+        let error = QueryError::from_string(
+            "Database index `unique_name` already contains ['shop', 'it\\'s, mine', 3], with record `product:1`",
+        )
+        .unwrap();
+
+        assert_eq!(
+            error.unique_violation().unwrap().conflicting_values,
+            vec![
+                "shop".to_string(),
+                "it's, mine".to_string(),
+                "3".to_string()
+            ]
+        );
+
+        assert_eq!(QueryError::QueryEmpty.unique_violation(), None);
+    }
+
+    #[test]
+    fn expected_kind() {
+        use crate::kind::Kind;
+
+        let error = QueryError::from_string(
+            "Found 'abc' for field `price`, with record `product:1`, but expected a decimal",
+        )
+        .unwrap();
+
+        assert_eq!(error.expected_kind(), Some(Kind::Decimal));
+
+        let error = QueryError::from_string(
+            "Found 'abc' for field `owner`, with record `product:1`, but expected a option<record<user | team>>",
+        )
+        .unwrap();
+
+        assert_eq!(
+            error.expected_kind(),
+            Some(Kind::Option(Box::new(Kind::Record(vec![
+                "user".to_string(),
+                "team".to_string()
+            ]))))
+        );
+
+        let error =
+            QueryError::from_string("Expected a array<string | int, 5> but found 'abc'").unwrap();
+
+        assert_eq!(
+            error.expected_kind(),
+            Some(Kind::Array(
+                Box::new(Kind::Either(vec![Kind::String, Kind::Int])),
+                Some(5)
+            ))
+        );
+
+        assert_eq!(
+            Kind::parse("literal<'a'>"),
+            Kind::Other("literal<'a'>".to_string())
+        );
+        assert_eq!(QueryError::QueryEmpty.expected_kind(), None);
+    }
+
+    #[test]
+    fn parse_lines() {
+        let log = "2024-03-01T10:00:00Z INFO surreal::dbs: Started
+2024-03-01T10:00:01Z ERROR surreal::rpc: The table 'product' does not exist
+2024-03-01T10:00:02Z ERROR surreal::rpc: An error occurred: CART_EMPTY
+The table 'user' does not exist";
+
+        let mut lines = QueryError::parse_lines(log.lines());
+
+        assert_eq!(
+            lines.by_ref().collect::<Vec<_>>(),
+            vec![
+                (1, None),
+                (
+                    2,
+                    Some(QueryError::TbNotFound {
+                        value: "product".to_string()
+                    })
+                ),
+                (3, Some(QueryError::Thrown("CART_EMPTY".to_string()))),
+                (
+                    4,
+                    Some(QueryError::TbNotFound {
+                        value: "user".to_string()
+                    })
+                ),
+            ]
+        );
+
+        let summary = lines.summary();
+
+        assert_eq!(summary.lines, 4);
+        assert_eq!(summary.matched, 3);
+        assert_eq!(
+            summary.by_code.iter().collect::<Vec<_>>(),
+            vec![(&"TbNotFound", &2), (&"Thrown", &1)]
+        );
+        assert_eq!(
+            summary.by_category.iter().collect::<Vec<_>>(),
+            vec![(&"not_found", &2), (&"validation", &1)]
+        );
+    }
+
+    #[test]
+    fn from_str() {
+        assert_eq!(
+            "The table 'product' does not exist".parse::<QueryError>(),
+            Ok(QueryError::TbNotFound {
+                value: "product".to_string()
+            })
+        );
+
+        let error = "The table 'product' is gone"
+            .parse::<QueryError>()
+            .unwrap_err();
+
+        assert_eq!(error.input, "The table 'product' is gone");
+        assert!(error.closest.unwrap().starts_with("The table '{"));
+
+        assert_eq!(
+            "Something else".parse::<QueryError>().unwrap_err().closest,
+            None
+        );
+    }
+
+    #[test]
+    fn registry() {
+        use crate::registry::{Parsed, Registry};
+
+        #[derive(Debug, PartialEq)]
+        enum AppError {
+            CartEmpty { message: String },
+            OutOfStock { sku: String },
+        }
+
+        let mut registry = Registry::new();
+
+        registry
+            .register(r"CART_EMPTY: (?<message>.*)", |caps| {
+                Some(AppError::CartEmpty {
+                    message: caps["message"].to_string(),
+                })
+            })
+            .unwrap()
+            .register(r"OUT_OF_STOCK: (?<sku>\S+)", |caps| {
+                Some(AppError::OutOfStock {
+                    sku: caps["sku"].to_string(),
+                })
+            })
+            .unwrap();
+
+        assert_eq!(
+            registry.from_string("An error occurred: CART_EMPTY: the cart has no items"),
+            Some(Parsed::App(AppError::CartEmpty {
+                message: "the cart has no items".to_string()
+            }))
+        );
+        assert_eq!(
+            registry.parse("An error occurred: OUT_OF_STOCK: ABC-123"),
+            Parsed::App(AppError::OutOfStock {
+                sku: "ABC-123".to_string()
+            })
+        );
+        assert_eq!(
+            registry.parse("An error occurred: OUT_OF_STOCK: ABC 123"),
+            Parsed::Query(QueryError::Thrown("OUT_OF_STOCK: ABC 123".to_string()))
+        );
+        assert_eq!(
+            registry.parse("Conditional clause is not truthy"),
+            Parsed::Query(QueryError::Ignore)
+        );
+        assert_eq!(registry.from_string("a message"), None);
+    }
+
+    #[test]
+    fn matches_thrown_prefix() {
+        let error = QueryError::from_string("An error occurred: CART_EMPTY: the cart has no items")
+            .unwrap();
+
+        assert_eq!(error.thrown(), Some("CART_EMPTY: the cart has no items"));
+        assert!(error.matches_thrown_prefix("CART_"));
+        assert!(!error.matches_thrown_prefix("STOCK_"));
+
+        assert_eq!(QueryError::QueryEmpty.thrown(), None);
+        assert!(!QueryError::QueryEmpty.matches_thrown_prefix(""));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn thrown_json() {
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        struct AppError {
+            code: String,
+            items: u32,
+        }
+
+        let error =
+            QueryError::from_string(r#"An error occurred: {"code":"CART_EMPTY","items":0}"#)
+                .unwrap();
+
+        assert_eq!(
+            error.thrown_json::<AppError>().unwrap().unwrap(),
+            AppError {
+                code: "CART_EMPTY".to_string(),
+                items: 0
+            }
+        );
+
+        let error = QueryError::Thrown("CART_EMPTY".to_string());
+
+        assert!(error.thrown_json::<AppError>().unwrap().is_err());
+
+        assert!(QueryError::QueryEmpty.thrown_json::<AppError>().is_none());
+    }
+
+    #[cfg(feature = "surrealdb")]
+    #[test]
+    fn try_from_surrealdb_error() {
+        let error = surrealdb::Error::Db(surrealdb::error::Db::QueryTimedout);
+
+        assert_eq!(QueryError::try_from(&error), Ok(QueryError::QueryTimedout));
+
+        let error = surrealdb::Error::Api(surrealdb::error::Api::Query(
+            "The table 'product' does not exist".to_string(),
+        ));
+
+        assert_eq!(
+            QueryError::try_from(&error),
+            Ok(QueryError::TbNotFound {
+                value: "product".to_string()
+            })
+        );
+
+        let error = surrealdb::Error::Api(surrealdb::error::Api::ConnectionUninitialised);
+
+        assert_eq!(QueryError::try_from(&error), Err(sdk::NotAQueryError));
+    }
+
+    #[cfg(feature = "surrealdb")]
+    #[tokio::test]
+    async fn take_query_errors() {
+        use surrealdb::engine::local::Mem;
+
+        let db = surrealdb::Surreal::new::<Mem>(()).await.unwrap();
+
+        db.use_ns("test").use_db("test").await.unwrap();
+
+        let mut response = db
+            .query("CREATE user:tester")
+            .query("CREATE user:tester")
+            .query("SELECT * FROM user")
+            .query("THROW 'CART_EMPTY'")
+            .await
+            .unwrap();
+
+        let errors = sdk::take_query_errors(&mut response);
+
+        assert_eq!(
+            errors,
+            vec![
+                (
+                    1,
+                    QueryError::RecordExists {
+                        thing: "user:tester".to_string()
+                    }
+                ),
+                (3, QueryError::Thrown("CART_EMPTY".to_string())),
+            ]
+        );
+
+        assert!(response.check().is_ok());
     }
 }
@@ -0,0 +1,87 @@
+use std::collections::BTreeMap;
+
+use crate::QueryError;
+
+/// Counts of the lines read by [`ParsedLines`]
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct LineSummary {
+    pub lines: usize,
+    /// Lines that contained an error
+    pub matched: usize,
+    /// Matched lines by [`QueryError::code`]
+    pub by_code: BTreeMap<&'static str, usize>,
+    /// Matched lines by [`QueryError::category`], named by [`crate::category::Category::as_str`]
+    pub by_category: BTreeMap<&'static str, usize>,
+}
+
+/// Iterator returned by [`QueryError::parse_lines`]
+pub struct ParsedLines<'a, I: Iterator<Item = &'a str>> {
+    lines: std::iter::Enumerate<I>,
+    summary: LineSummary,
+}
+
+impl<'a, I: Iterator<Item = &'a str>> ParsedLines<'a, I> {
+    /// The counts of the lines read so far
+    pub fn summary(&self) -> &LineSummary {
+        &self.summary
+    }
+}
+
+impl<'a, I: Iterator<Item = &'a str>> Iterator for ParsedLines<'a, I> {
+    type Item = (usize, Option<QueryError>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (i, line) = self.lines.next()?;
+
+        let error = parse_line(line.trim());
+
+        self.summary.lines += 1;
+
+        if let Some(error) = &error {
+            self.summary.matched += 1;
+            *self.summary.by_code.entry(error.code()).or_default() += 1;
+            *self
+                .summary
+                .by_category
+                .entry(error.category().as_str())
+                .or_default() += 1;
+        }
+
+        Some((i + 1, error))
+    }
+}
+
+/// Log lines start with a timestamp, level and target, so the message is found by trying every
+/// suffix that starts a word, longest first
+fn parse_line(line: &str) -> Option<QueryError> {
+    let starts = std::iter::once(0).chain(
+        line.char_indices()
+            .filter(|(_, c)| c.is_whitespace())
+            .map(|(i, c)| i + c.len_utf8()),
+    );
+
+    for start in starts {
+        let message = line[start..].trim_start();
+
+        if message.is_empty() {
+            continue;
+        }
+
+        if let Some(error) = QueryError::from_string(message) {
+            return Some(error);
+        }
+    }
+
+    None
+}
+
+impl QueryError {
+    /// Parses a log, one line at a time. Yields the 1-based line number of every line with the
+    /// error found in it, if any. The summary of the lines read so far is kept by the iterator
+    pub fn parse_lines<'a, I: Iterator<Item = &'a str>>(lines: I) -> ParsedLines<'a, I> {
+        ParsedLines {
+            lines: lines.enumerate(),
+            summary: LineSummary::default(),
+        }
+    }
+}
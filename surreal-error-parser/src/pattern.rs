@@ -0,0 +1,11 @@
+/// A template of a `StrPattern` type, as listed in its `PATTERNS`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PatternInfo<K = ()> {
+    /// The name of the variant the template parses into, the same as its `code()`
+    pub variant: &'static str,
+    /// The variant as its `kind()`, or `()` for a type without `kind = Name`
+    pub kind: K,
+    pub template: &'static str,
+    /// The names of the variant's fields, with unnamed fields named by their index
+    pub fields: &'static [&'static str],
+}
@@ -0,0 +1,338 @@
+use crate::QueryError;
+
+/// Placeholder that replaces redacted values
+pub const REDACTED: &str = "[redacted]";
+
+fn value(_: &str) -> String {
+    REDACTED.to_string()
+}
+
+/// Keeps the table of a record id, since it is part of the schema rather than the data
+fn thing(thing: &str) -> String {
+    match thing.split_once(':') {
+        Some((table, _)) => format!("{table}:{REDACTED}"),
+        None => value(thing),
+    }
+}
+
+impl QueryError {
+    /// Returns a copy with the values that may come from user data (record ids, field values,
+    /// thrown payloads, SQL text, user names, datastore messages, ...) replaced by [`REDACTED`], so the error can be
+    /// logged. Schema names such as tables, fields and indexes are kept
+    pub fn redacted(&self) -> Self {
+        match self {
+            QueryError::Thrown(payload) => QueryError::Thrown(value(payload)),
+            QueryError::InvalidQuery { line, char, sql } => QueryError::InvalidQuery {
+                line: *line,
+                char: *char,
+                sql: value(sql),
+            },
+            QueryError::InvalidQueryDetail { message } => QueryError::InvalidQueryDetail {
+                message: value(message),
+            },
+            QueryError::PatchTest { expected, got } => QueryError::PatchTest {
+                expected: value(expected),
+                got: value(got),
+            },
+            QueryError::InvalidLimit { value: v } => QueryError::InvalidLimit { value: value(v) },
+            QueryError::InvalidStart { value: v } => QueryError::InvalidStart { value: value(v) },
+            QueryError::InvalidScript { message } => QueryError::InvalidScript {
+                message: value(message),
+            },
+            QueryError::InvalidFunction { name, message } => QueryError::InvalidFunction {
+                name: name.clone(),
+                message: value(message),
+            },
+            QueryError::InvalidArguments { name, message } => QueryError::InvalidArguments {
+                name: name.clone(),
+                message: value(message),
+            },
+            QueryError::InvalidUrl(url) => QueryError::InvalidUrl(value(url)),
+            QueryError::NlNotFound { value: v } => QueryError::NlNotFound { value: value(v) },
+            QueryError::DlNotFound { value: v } => QueryError::DlNotFound { value: value(v) },
+            QueryError::UserRootNotFound { value: v } => {
+                QueryError::UserRootNotFound { value: value(v) }
+            }
+            QueryError::UserNsNotFound { value: v, ns } => QueryError::UserNsNotFound {
+                value: value(v),
+                ns: ns.clone(),
+            },
+            QueryError::UserDbNotFound { value: v, db } => QueryError::UserDbNotFound {
+                value: value(v),
+                db: db.clone(),
+            },
+            QueryError::UserRootAlreadyExists { value: v } => {
+                QueryError::UserRootAlreadyExists { value: value(v) }
+            }
+            QueryError::UserNsAlreadyExists { value: v, ns } => QueryError::UserNsAlreadyExists {
+                value: value(v),
+                ns: ns.clone(),
+            },
+            QueryError::UserDbAlreadyExists { value: v, db } => QueryError::UserDbAlreadyExists {
+                value: value(v),
+                db: db.clone(),
+            },
+            QueryError::InvalidStatementTarget { value: v } => {
+                QueryError::InvalidStatementTarget { value: value(v) }
+            }
+            QueryError::CreateStatement { value: v } => {
+                QueryError::CreateStatement { value: value(v) }
+            }
+            QueryError::UpdateStatement { value: v } => {
+                QueryError::UpdateStatement { value: value(v) }
+            }
+            QueryError::RelateStatement { value: v } => {
+                QueryError::RelateStatement { value: value(v) }
+            }
+            QueryError::DeleteStatement { value: v } => {
+                QueryError::DeleteStatement { value: value(v) }
+            }
+            QueryError::InsertStatement { value: v } => {
+                QueryError::InsertStatement { value: value(v) }
+            }
+            QueryError::LiveStatement { value: v } => QueryError::LiveStatement { value: value(v) },
+            QueryError::UpsertStatement { value: v } => {
+                QueryError::UpsertStatement { value: value(v) }
+            }
+            QueryError::RelateStatementIn { value: v } => {
+                QueryError::RelateStatementIn { value: value(v) }
+            }
+            QueryError::RelateStatementId { value: v } => {
+                QueryError::RelateStatementId { value: value(v) }
+            }
+            QueryError::RelateStatementOut { value: v } => {
+                QueryError::RelateStatementOut { value: value(v) }
+            }
+            QueryError::InsertStatementIn { value: v } => {
+                QueryError::InsertStatementIn { value: value(v) }
+            }
+            QueryError::InsertStatementId { value: v } => {
+                QueryError::InsertStatementId { value: value(v) }
+            }
+            QueryError::InsertStatementOut { value: v } => {
+                QueryError::InsertStatementOut { value: value(v) }
+            }
+            QueryError::RecordExists { thing: t } => QueryError::RecordExists { thing: thing(t) },
+            QueryError::IndexExists {
+                thing: t,
+                index,
+                value: v,
+            } => QueryError::IndexExists {
+                thing: thing(t),
+                index: index.clone(),
+                value: value(v),
+            },
+            QueryError::FieldCheck {
+                thing: t,
+                value: v,
+                field,
+                check,
+            } => QueryError::FieldCheck {
+                thing: thing(t),
+                value: value(v),
+                field: field.clone(),
+                check: check.clone(),
+            },
+            QueryError::FieldValue {
+                thing: t,
+                value: v,
+                field,
+                check,
+            } => QueryError::FieldValue {
+                thing: thing(t),
+                value: value(v),
+                field: field.clone(),
+                check: check.clone(),
+            },
+            QueryError::FieldReadonly { field, thing: t } => QueryError::FieldReadonly {
+                field: field.clone(),
+                thing: thing(t),
+            },
+            QueryError::SetCheck {
+                value: v,
+                name,
+                check,
+            } => QueryError::SetCheck {
+                value: value(v),
+                name: name.clone(),
+                check: check.clone(),
+            },
+            QueryError::IdMismatch { value: v } => QueryError::IdMismatch { value: value(v) },
+            QueryError::IdInvalid { value: v } => QueryError::IdInvalid { value: value(v) },
+            QueryError::IdNotFound { value: v } => QueryError::IdNotFound { value: thing(v) },
+            QueryError::TbInvalid { value: v } => QueryError::TbInvalid { value: value(v) },
+            QueryError::InvalidContent { value: v } => {
+                QueryError::InvalidContent { value: value(v) }
+            }
+            QueryError::InvalidMerge { value: v } => QueryError::InvalidMerge { value: value(v) },
+            QueryError::NoIndexFoundForMatch { value: v } => {
+                QueryError::NoIndexFoundForMatch { value: value(v) }
+            }
+            QueryError::CoerceTo { from, into } => QueryError::CoerceTo {
+                from: value(from),
+                into: into.clone(),
+            },
+            QueryError::ConvertTo { from, into } => QueryError::ConvertTo {
+                from: value(from),
+                into: into.clone(),
+            },
+            QueryError::TryAdd(a, b) => QueryError::TryAdd(value(a), value(b)),
+            QueryError::TrySub(a, b) => QueryError::TrySub(value(a), value(b)),
+            QueryError::TryMul(a, b) => QueryError::TryMul(value(a), value(b)),
+            QueryError::TryDiv(a, b) => QueryError::TryDiv(value(a), value(b)),
+            QueryError::TryRem(a, b) => QueryError::TryRem(value(a), value(b)),
+            QueryError::TryPow(a, b) => QueryError::TryPow(value(a), value(b)),
+            QueryError::TryNeg(a) => QueryError::TryNeg(value(a)),
+            QueryError::TryFrom(a, into) => QueryError::TryFrom(value(a), into.clone()),
+            QueryError::ArithmeticOverflow(a) => QueryError::ArithmeticOverflow(value(a)),
+            QueryError::Deprecated(message) => QueryError::Deprecated(value(message)),
+            QueryError::Unknown(message) => QueryError::Unknown(value(message)),
+            QueryError::UnreachableDetail { message } => QueryError::UnreachableDetail {
+                message: value(message),
+            },
+            QueryError::InvalidPatch { message } => QueryError::InvalidPatch {
+                message: value(message),
+            },
+            QueryError::QueryNotExecutedDetail { message } => QueryError::QueryNotExecutedDetail {
+                message: value(message),
+            },
+            QueryError::CorruptedIndexDetail { message } => QueryError::CorruptedIndexDetail {
+                message: value(message),
+            },
+            QueryError::Ds(message) => QueryError::Ds(value(message)),
+            QueryError::Tx(message) => QueryError::Tx(value(message)),
+            QueryError::Http(message) => QueryError::Http(value(message)),
+            QueryError::Channel(message) => QueryError::Channel(value(message)),
+            QueryError::Io(message) => QueryError::Io(value(message)),
+            QueryError::Encode(message) => QueryError::Encode(value(message)),
+            QueryError::Decode(message) => QueryError::Decode(value(message)),
+            QueryError::Revision(message) => QueryError::Revision(value(message)),
+            QueryError::AnalyzerError(message) => QueryError::AnalyzerError(value(message)),
+            QueryError::HighlightError(message) => QueryError::HighlightError(value(message)),
+            QueryError::Bincode(message) => QueryError::Bincode(value(message)),
+            QueryError::FstError(message) => QueryError::FstError(value(message)),
+            QueryError::Utf8Error(message) => QueryError::Utf8Error(value(message)),
+            QueryError::TimestampOverflow(message) => QueryError::TimestampOverflow(value(message)),
+            QueryError::Internal(message) => QueryError::Internal(value(message)),
+            QueryError::Unimplemented(message) => QueryError::Unimplemented(value(message)),
+            QueryError::CorruptedVersionstampInKey(message) => {
+                QueryError::CorruptedVersionstampInKey(value(message))
+            }
+            QueryError::InvalidLevel(message) => QueryError::InvalidLevel(value(message)),
+            QueryError::IamError(message) => QueryError::IamError(value(message)),
+            QueryError::NetTargetNotAllowed(message) => {
+                QueryError::NetTargetNotAllowed(value(message))
+            }
+            // Live query ids are handed out per session, like tokens
+            QueryError::KillStatement { value: v } => QueryError::KillStatement { value: value(v) },
+            QueryError::LvNotFound { value: v } => QueryError::LvNotFound { value: value(v) },
+            QueryError::LqNotFound { value: v } => QueryError::LqNotFound { value: value(v) },
+            QueryError::AccessGrantRootNotFound { gr } => {
+                QueryError::AccessGrantRootNotFound { gr: value(gr) }
+            }
+            QueryError::AccessGrantNsNotFound { gr, ns } => QueryError::AccessGrantNsNotFound {
+                gr: value(gr),
+                ns: ns.clone(),
+            },
+            QueryError::AccessGrantDbNotFound { gr, db } => QueryError::AccessGrantDbNotFound {
+                gr: value(gr),
+                db: db.clone(),
+            },
+            // Nothing but schema names, or nothing at all
+            QueryError::InvalidParam { .. }
+            | QueryError::InvalidField { .. }
+            | QueryError::InvalidSplit { .. }
+            | QueryError::InvalidOrder { .. }
+            | QueryError::InvalidGroup { .. }
+            | QueryError::NsNotAllowed { .. }
+            | QueryError::DbNotAllowed { .. }
+            | QueryError::NsNotFound { .. }
+            | QueryError::NtNotFound { .. }
+            | QueryError::DbNotFound { .. }
+            | QueryError::DtNotFound { .. }
+            | QueryError::FcNotFound { .. }
+            | QueryError::ScNotFound { .. }
+            | QueryError::ClAlreadyExists { .. }
+            | QueryError::NdNotFound { .. }
+            | QueryError::StNotFound { .. }
+            | QueryError::PaNotFound { .. }
+            | QueryError::TbNotFound { .. }
+            | QueryError::AzNotFound { .. }
+            | QueryError::IxNotFound { .. }
+            | QueryError::TablePermissions { .. }
+            | QueryError::TableIsView { .. }
+            | QueryError::LengthInvalid { .. }
+            | QueryError::FeatureNotYetImplemented { .. }
+            | QueryError::DuplicatedMatchRef { .. }
+            | QueryError::AccessRootNotFound { .. }
+            | QueryError::AccessNsNotFound { .. }
+            | QueryError::AccessDbNotFound { .. }
+            | QueryError::EvNotFound { .. }
+            | QueryError::FdNotFound { .. }
+            | QueryError::MlNotFound { .. }
+            | QueryError::NsAlreadyExists { .. }
+            | QueryError::DbAlreadyExists { .. }
+            | QueryError::TbAlreadyExists { .. }
+            | QueryError::FdAlreadyExists { .. }
+            | QueryError::IxAlreadyExists { .. }
+            | QueryError::EvAlreadyExists { .. }
+            | QueryError::FcAlreadyExists { .. }
+            | QueryError::PaAlreadyExists { .. }
+            | QueryError::AzAlreadyExists { .. }
+            | QueryError::MlAlreadyExists { .. }
+            | QueryError::AccessRootAlreadyExists { .. }
+            | QueryError::AccessNsAlreadyExists { .. }
+            | QueryError::AccessDbAlreadyExists { .. }
+            | QueryError::IndexAlreadyBuilding { .. }
+            | QueryError::ParamPermissions { .. }
+            | QueryError::FunctionPermissions { .. }
+            | QueryError::FieldUndefined { .. }
+            | QueryError::FunctionNotAllowed(_)
+            | QueryError::Ignore
+            | QueryError::Break
+            | QueryError::Continue
+            | QueryError::Unreachable
+            | QueryError::TxFailure
+            | QueryError::TxFinished
+            | QueryError::TxReadonly
+            | QueryError::TxConditionNotMet
+            | QueryError::TxKeyAlreadyExists
+            | QueryError::TxKeyTooLarge
+            | QueryError::TxValueTooLarge
+            | QueryError::TxTooLarge
+            | QueryError::NsEmpty
+            | QueryError::DbEmpty
+            | QueryError::QueryEmpty
+            | QueryError::QueryRemaining
+            | QueryError::InvalidAuth
+            | QueryError::UnknownAuth
+            | QueryError::HttpDisabled
+            | QueryError::QueryTimedout
+            | QueryError::QueryCancelled
+            | QueryError::QueryNotExecuted
+            | QueryError::NoScopeFound
+            | QueryError::RealtimeDisabled
+            | QueryError::ComputationDepthExceeded
+            | QueryError::CorruptedIndex
+            | QueryError::ScriptingNotAllowed
+            | QueryError::Return
+            | QueryError::ExpiredSession
+            | QueryError::ExpiredToken
+            | QueryError::SignupQueryFailed
+            | QueryError::SigninQueryFailed
+            | QueryError::InvalidSignup
+            | QueryError::InvalidPass
+            | QueryError::MissingUserOrPass
+            | QueryError::NoRecordFound
+            | QueryError::AccessNotFound
+            | QueryError::AccessMethodMismatch
+            | QueryError::AccessGrantRevoked
+            | QueryError::AccessRecordSignupQueryFailed
+            | QueryError::AccessRecordSigninQueryFailed
+            | QueryError::AccessRecordNoSignup
+            | QueryError::AccessRecordNoSignin
+            | QueryError::SingleOnlyOutput
+            | QueryError::QueryTooLarge => self.clone(),
+        }
+    }
+}
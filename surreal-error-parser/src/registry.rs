@@ -0,0 +1,70 @@
+use regex::{Captures, Regex};
+
+use crate::QueryError;
+
+type Build<T> = Box<dyn Fn(&Captures) -> Option<T> + Send + Sync>;
+
+/// Result of parsing with a [`Registry`]: either one of the application's own errors or a
+/// regular [`QueryError`]
+#[derive(Debug, PartialEq, Clone)]
+pub enum Parsed<T> {
+    App(T),
+    Query(QueryError),
+}
+
+/// Application error patterns registered at runtime. They are matched against the payload of a
+/// thrown error (e.g. `THROW 'CART_EMPTY: ...'`) before it falls back to [`QueryError::Thrown`]
+pub struct Registry<T> {
+    patterns: Vec<(Regex, Build<T>)>,
+}
+
+impl<T> Default for Registry<T> {
+    fn default() -> Self {
+        Self {
+            patterns: Vec::new(),
+        }
+    }
+}
+
+impl<T> Registry<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a regex that must match the whole thrown payload. `build` turns its captures into
+    /// the application error, or returns `None` to let the next pattern try. Patterns are tried in
+    /// the order they were registered
+    pub fn register<F>(&mut self, pattern: &str, build: F) -> Result<&mut Self, regex::Error>
+    where
+        F: Fn(&Captures) -> Option<T> + Send + Sync + 'static,
+    {
+        let regex = Regex::new(&format!("(?s)^(?:{pattern})$"))?;
+
+        self.patterns.push((regex, Box::new(build)));
+
+        Ok(self)
+    }
+
+    pub fn from_string(&self, string: &str) -> Option<Parsed<T>> {
+        let error = QueryError::from_string(string)?;
+
+        Some(self.resolve(error))
+    }
+
+    /// Like [`Registry::from_string`], but unmatched messages become [`QueryError::Unknown`]
+    pub fn parse(&self, string: &str) -> Parsed<T> {
+        self.resolve(QueryError::parse(string))
+    }
+
+    fn resolve(&self, error: QueryError) -> Parsed<T> {
+        if let QueryError::Thrown(payload) = &error {
+            for (regex, build) in &self.patterns {
+                if let Some(app) = regex.captures(payload).and_then(|caps| build(&caps)) {
+                    return Parsed::App(app);
+                }
+            }
+        }
+
+        Parsed::Query(error)
+    }
+}
@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+
+use crate::QueryError;
+
+/// User-facing message templates keyed by error code (see [`QueryError::code`]). Templates
+/// refer to the parsed fields with `{field}`, or `{0}` for unnamed fields
+pub trait Catalog {
+    fn template(&self, code: &str) -> Option<&str>;
+}
+
+impl<S: BuildHasher> Catalog for HashMap<String, String, S> {
+    fn template(&self, code: &str) -> Option<&str> {
+        self.get(code).map(String::as_str)
+    }
+}
+
+impl<S: BuildHasher> Catalog for HashMap<&str, &str, S> {
+    fn template(&self, code: &str) -> Option<&str> {
+        self.get(code).copied()
+    }
+}
+
+impl QueryError {
+    /// Renders the catalog's template for this error, or `None` when the catalog has no template
+    /// for its code. Placeholders that name no field are kept as they are
+    pub fn render(&self, catalog: &impl Catalog) -> Option<String> {
+        let template = catalog.template(self.code())?;
+        let fields = self.fields();
+
+        let mut rendered = String::with_capacity(template.len());
+        let mut rest = template;
+
+        // A single pass, so values that contain braces are never interpolated themselves
+        while let Some(start) = rest.find('{') {
+            rendered.push_str(&rest[..start]);
+            rest = &rest[start..];
+
+            let field = rest.find('}').and_then(|end| {
+                let (_, value) = fields.iter().find(|(name, _)| *name == &rest[1..end])?;
+
+                Some((end, value))
+            });
+
+            match field {
+                Some((end, value)) => {
+                    rendered.push_str(value);
+                    rest = &rest[end + 1..];
+                }
+                None => {
+                    rendered.push('{');
+                    rest = &rest[1..];
+                }
+            }
+        }
+
+        rendered.push_str(rest);
+
+        Some(rendered)
+    }
+}
@@ -0,0 +1,25 @@
+use crate::QueryError;
+
+/// The JSON body of a failed request to one of the HTTP endpoints
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RestFailure {
+    /// The HTTP status code
+    pub code: u16,
+    pub details: String,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub description: Option<String>,
+    /// The message of the error raised by the database, if there was one
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub information: Option<String>,
+}
+
+impl RestFailure {
+    /// Parses [`RestFailure::information`]. Returns `None` when the body has no information or
+    /// no pattern matches it
+    pub fn query_error(&self) -> Option<QueryError> {
+        self.information
+            .as_deref()
+            .and_then(QueryError::from_string)
+    }
+}
@@ -0,0 +1,56 @@
+use crate::QueryError;
+
+/// The error object of a failed WebSocket RPC response
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RpcFailure {
+    pub code: i64,
+    pub message: String,
+}
+
+/// A failed WebSocket RPC request. Errors raised while running a query are parsed into a
+/// [`QueryError`], the others are errors of the RPC protocol itself
+#[derive(Debug, PartialEq, Clone)]
+pub enum RpcError {
+    /// -32700, the request was not valid JSON or CBOR
+    ParseError(String),
+    /// -32600
+    InvalidRequest(String),
+    /// -32601
+    MethodNotFound(String),
+    /// -32602
+    InvalidParams(String),
+    /// -32603
+    InternalError(String),
+    /// -32604, a live query was made on a connection that does not support them
+    LiveQueryNotSupported(String),
+    /// -32605, realtime is enabled for the session but live queries are not supported
+    BadLiveQueryConfig(String),
+    /// -32606, a GraphQL request was made on a connection that does not support it
+    BadGraphqlConfig(String),
+    /// Any other code, which the server uses for errors raised by the database
+    Query(QueryError),
+}
+
+impl From<RpcFailure> for RpcError {
+    fn from(failure: RpcFailure) -> Self {
+        let RpcFailure { code, message } = failure;
+
+        match code {
+            -32700 => RpcError::ParseError(message),
+            -32600 => RpcError::InvalidRequest(message),
+            -32601 => RpcError::MethodNotFound(message),
+            -32602 => RpcError::InvalidParams(message),
+            -32603 => RpcError::InternalError(message),
+            -32604 => RpcError::LiveQueryNotSupported(message),
+            -32605 => RpcError::BadLiveQueryConfig(message),
+            -32606 => RpcError::BadGraphqlConfig(message),
+            // Depending on the version, database errors are sent as they are or wrapped
+            _ => RpcError::Query(QueryError::parse(
+                message
+                    .strip_prefix("There was a problem with the database: ")
+                    .unwrap_or(&message),
+            )),
+        }
+    }
+}
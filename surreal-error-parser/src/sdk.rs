@@ -0,0 +1,46 @@
+use std::fmt::Display;
+
+use crate::QueryError;
+
+/// Returned when a [`surrealdb::Error`] was raised by the client library itself (e.g. a
+/// connection problem) rather than by the database while running a query
+#[derive(Debug, PartialEq, Clone)]
+pub struct NotAQueryError;
+
+impl Display for NotAQueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("the error was not raised by the database while running a query")
+    }
+}
+
+impl std::error::Error for NotAQueryError {}
+
+impl TryFrom<&surrealdb::Error> for QueryError {
+    type Error = NotAQueryError;
+
+    fn try_from(error: &surrealdb::Error) -> Result<Self, Self::Error> {
+        let message = match error {
+            // Embedded engines hand us the database error directly
+            surrealdb::Error::Db(error) => error.to_string(),
+            // Remote engines only pass along the message the server responded with
+            surrealdb::Error::Api(surrealdb::error::Api::Query(message)) => message.clone(),
+            surrealdb::Error::Api(_) => return Err(NotAQueryError),
+        };
+
+        Ok(QueryError::parse(&message))
+    }
+}
+
+/// Takes the errors of all failed statements out of `response` and parses them, ordered by
+/// statement index. The results of the successful statements are left in the response
+pub fn take_query_errors(response: &mut surrealdb::Response) -> Vec<(usize, QueryError)> {
+    let mut errors = response
+        .take_errors()
+        .into_iter()
+        .filter_map(|(index, error)| Some((index, QueryError::try_from(&error).ok()?)))
+        .collect::<Vec<_>>();
+
+    errors.sort_unstable_by_key(|(index, _)| *index);
+
+    errors
+}
@@ -0,0 +1,16 @@
+use crate::QueryError;
+
+/// Name of the counter incremented for every parsed error, labeled with `code` and `category`
+pub const COUNTER: &str = "surrealdb_query_errors_total";
+
+/// Counts a parsed error with the installed `metrics` recorder. Called for every error parsed
+/// by `from_string`, `from_string_with` and `parse`, and does nothing when no recorder is
+/// installed
+pub fn record(error: &QueryError) {
+    metrics::counter!(
+        COUNTER,
+        "code" => error.code(),
+        "category" => error.category().as_str(),
+    )
+    .increment(1);
+}
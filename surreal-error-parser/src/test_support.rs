@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+
+use crate::QueryError;
+
+static TEMPLATE_VAR: Lazy<Regex> = Lazy::new(|| Regex::new(r"\{(\w+)\}").unwrap());
+
+/// A message rendered from a template that did not parse back into its own variant
+#[derive(Debug, PartialEq, Clone)]
+pub struct Mismatch {
+    /// The code of the variant the message was rendered for
+    pub code: &'static str,
+    pub message: String,
+    /// What the message was parsed into instead
+    pub parsed: Option<QueryError>,
+}
+
+/// A small seeded generator of field values, so a failing corpus can be reproduced
+pub struct Values {
+    state: u64,
+}
+
+impl Values {
+    pub fn new(seed: u64) -> Self {
+        // xorshift gets stuck on 0
+        Self { state: seed.max(1) }
+    }
+
+    fn next(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    /// Between 1 and 16 characters of words, digits and the punctuation found in identifiers
+    pub fn text(&mut self) -> String {
+        const CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789 -_.";
+
+        let len = 1 + self.next() % 16;
+
+        let text = (0..len)
+            .map(|_| CHARS[(self.next() % CHARS.len() as u64) as usize] as char)
+            .collect::<String>();
+
+        // Values are never padded in messages
+        match text.trim() {
+            "" => "x".to_string(),
+            text => text.to_string(),
+        }
+    }
+
+    /// A value that also parses into numeric fields
+    pub fn number(&mut self) -> String {
+        (self.next() % 100_000).to_string()
+    }
+}
+
+/// Renders `template`, asking `value` for the value of each var. A var that appears more than
+/// once gets the same value everywhere
+pub fn render(template: &str, mut value: impl FnMut(&str) -> String) -> String {
+    let mut values = HashMap::new();
+
+    TEMPLATE_VAR
+        .replace_all(template, |caps: &Captures| {
+            values
+                .entry(caps[1].to_string())
+                .or_insert_with(|| value(&caps[1]))
+                .clone()
+        })
+        .into_owned()
+}
+
+/// Renders every template of [`QueryError::PATTERNS`] `samples` times with random values and
+/// returns the messages that `from_string` did not parse into the variant they were rendered
+/// for. Numeric values are used when a message with text values does not parse at all, since
+/// the field may not be a string
+pub fn round_trip_mismatches(samples: usize, seed: u64) -> Vec<Mismatch> {
+    let mut values = Values::new(seed);
+    let mut mismatches = vec![];
+
+    for pattern in QueryError::PATTERNS {
+        for _ in 0..samples {
+            let mut message = render(pattern.template, |_| values.text());
+            let mut parsed = QueryError::from_string(&message);
+
+            if parsed.is_none() {
+                message = render(pattern.template, |_| values.number());
+                parsed = QueryError::from_string(&message);
+            }
+
+            if parsed.as_ref().map(QueryError::code) != Some(pattern.variant) {
+                mismatches.push(Mismatch {
+                    code: pattern.variant,
+                    message,
+                    parsed,
+                });
+            }
+        }
+    }
+
+    mismatches
+}
@@ -0,0 +1,52 @@
+use crate::QueryError;
+
+/// Splits a record id like `user:⟨jane:doe⟩` into its table and id. The `⟨...⟩` or backtick
+/// escaping around either part is removed, and complex ids (objects, arrays) are kept as
+/// SurrealQL. Returns `None` when there is no table
+pub fn split_record_id(thing: &str) -> Option<(String, String)> {
+    let mut escape = None;
+
+    for (i, c) in thing.char_indices() {
+        match (escape, c) {
+            (None, '⟨') => escape = Some('⟩'),
+            (None, '`') => escape = Some('`'),
+            (Some(end), c) if c == end => escape = None,
+            (None, ':') => {
+                let table = unescape(&thing[..i]);
+                let id = unescape(&thing[i + 1..]);
+
+                return Some((table, id));
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+fn unescape(part: &str) -> String {
+    for (start, end) in [('⟨', '⟩'), ('`', '`')] {
+        if let Some(inner) = part
+            .strip_prefix(start)
+            .and_then(|part| part.strip_suffix(end))
+        {
+            return inner.replace(&format!("\\{end}"), &end.to_string());
+        }
+    }
+
+    part.to_string()
+}
+
+impl QueryError {
+    /// The table and id of the record the error is about, for the variants that carry one
+    pub fn record_id(&self) -> Option<(String, String)> {
+        match self {
+            QueryError::RecordExists { thing }
+            | QueryError::IndexExists { thing, .. }
+            | QueryError::FieldCheck { thing, .. }
+            | QueryError::FieldValue { thing, .. }
+            | QueryError::FieldReadonly { thing, .. } => split_record_id(thing),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use crate::QueryError;
+
+impl QueryError {
+    /// The payload of a [`QueryError::Thrown`] error, or `None` for every other variant
+    pub fn thrown(&self) -> Option<&str> {
+        match self {
+            QueryError::Thrown(payload) => Some(payload),
+            _ => None,
+        }
+    }
+
+    /// Whether this is a [`QueryError::Thrown`] error whose payload starts with `prefix`, e.g.
+    /// `error.matches_thrown_prefix("CART_")`
+    pub fn matches_thrown_prefix(&self, prefix: &str) -> bool {
+        self.thrown()
+            .is_some_and(|payload| payload.starts_with(prefix))
+    }
+
+    /// Parses the payload of a [`QueryError::Thrown`] error as JSON, for functions that `THROW`
+    /// structured errors. Returns `None` for every other variant
+    #[cfg(feature = "serde")]
+    pub fn thrown_json<T: serde::de::DeserializeOwned>(
+        &self,
+    ) -> Option<Result<T, serde_json::Error>> {
+        self.thrown().map(serde_json::from_str)
+    }
+}
@@ -0,0 +1,101 @@
+use crate::{thing::split_record_id, QueryError};
+
+/// A unique index rejected a write, parsed from [`QueryError::IndexExists`]
+#[derive(Debug, PartialEq, Clone)]
+pub struct UniqueViolation {
+    /// The table of the record that already holds the values
+    pub table: String,
+    /// The full id of the record that already holds the values, e.g. `product:1`
+    pub record_id: String,
+    pub index: String,
+    /// One value per indexed field, in the order the fields were declared in the index. Strings
+    /// are unquoted, other values are kept as SurrealQL
+    pub conflicting_values: Vec<String>,
+}
+
+impl QueryError {
+    /// Parses an [`QueryError::IndexExists`] error further, or returns `None` for every other
+    /// variant
+    pub fn unique_violation(&self) -> Option<UniqueViolation> {
+        let QueryError::IndexExists {
+            thing,
+            index,
+            value,
+        } = self
+        else {
+            return None;
+        };
+
+        let table = split_record_id(thing).map_or_else(|| thing.clone(), |(table, _)| table);
+
+        // Indexes on several fields report all their values as an array
+        let conflicting_values = match value
+            .strip_prefix('[')
+            .and_then(|value| value.strip_suffix(']'))
+        {
+            Some(values) => split_values(values).into_iter().map(unquote).collect(),
+            None => vec![unquote(value)],
+        };
+
+        Some(UniqueViolation {
+            table,
+            record_id: thing.clone(),
+            index: index.clone(),
+            conflicting_values,
+        })
+    }
+}
+
+/// Splits the top level of a comma separated SurrealQL list, leaving nested values and commas in
+/// strings alone
+fn split_values(values: &str) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut depth = 0usize;
+    let mut quote = None;
+    let mut escaped = false;
+    let mut start = 0;
+
+    for (i, c) in values.char_indices() {
+        if let Some(q) = quote {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == q {
+                quote = None;
+            }
+
+            continue;
+        }
+
+        match c {
+            '\'' | '"' | '`' => quote = Some(c),
+            '[' | '{' | '(' => depth += 1,
+            ']' | '}' | ')' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                parts.push(values[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+
+    if !values[start..].trim().is_empty() {
+        parts.push(values[start..].trim());
+    }
+
+    parts
+}
+
+fn unquote(value: &str) -> String {
+    for q in ['\'', '"'] {
+        if let Some(inner) = value
+            .strip_prefix(q)
+            .and_then(|value| value.strip_suffix(q))
+        {
+            return inner.replace(&format!("\\{q}"), &q.to_string());
+        }
+    }
+
+    value.to_string()
+}
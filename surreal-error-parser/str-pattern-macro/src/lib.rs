@@ -1,191 +1,2073 @@
+use std::collections::HashMap;
+
 use proc_macro2::Ident;
 use quote::quote;
-use regex::Match;
 use syn::{spanned::Spanned, Attribute, DeriveInput, FieldsNamed, FieldsUnnamed};
 
-#[proc_macro_derive(StrPattern, attributes(str_pattern))]
-pub fn derive_str_pattern(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    let input = syn::parse_macro_input!(input as DeriveInput);
+#[proc_macro_derive(StrPattern, attributes(str_pattern))]
+pub fn derive_str_pattern(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+
+    let args = match &input.data {
+        // Apart from `crate` and `no_std`, a struct's attributes are its patterns
+        syn::Data::Struct(_) => enum_args(
+            &input
+                .attrs
+                .iter()
+                .filter(|attr| is_type_arg(attr))
+                .cloned()
+                .collect::<Vec<_>>(),
+        ),
+        _ => enum_args(&input.attrs),
+    };
+
+    let args = match args {
+        Ok(args) => args,
+        Err(err) => return proc_macro::TokenStream::from(err.to_compile_error()),
+    };
+
+    let krate = args
+        .krate
+        .clone()
+        .unwrap_or_else(|| syn::parse_quote!(::surreal_error_parser));
+    let alloc = alloc_path(args.no_std);
+
+    let Generated {
+        match_arms,
+        regexes,
+        matchers,
+        display_arms,
+        dialect_arms,
+        code_arms,
+        field_arms,
+        pattern_entries,
+        fallback,
+    } = match &input.data {
+        syn::Data::Enum(d) => match impl_enum(d, &args, false, &krate) {
+            Ok(output) => output,
+            Err(err) => return proc_macro::TokenStream::from(err.to_compile_error()),
+        },
+        // Parsed like an enum with a single variant, built with `Self` instead of `Self::Variant`
+        syn::Data::Struct(d) => {
+            let variant = syn::Variant {
+                attrs: input
+                    .attrs
+                    .iter()
+                    .filter(|attr| !is_type_arg(attr))
+                    .cloned()
+                    .collect(),
+                ident: input.ident.clone(),
+                fields: d.fields.clone(),
+                discriminant: None,
+            };
+
+            let data = syn::DataEnum {
+                enum_token: syn::Token![enum](d.struct_token.span),
+                brace_token: Default::default(),
+                variants: std::iter::once(variant).collect(),
+            };
+
+            match impl_enum(&data, &args, true, &krate) {
+                Ok(output) => output,
+                Err(err) => return proc_macro::TokenStream::from(err.to_compile_error()),
+            }
+        }
+        _ => {
+            return proc_macro::TokenStream::from(
+                syn::Error::new(
+                    input.span(),
+                    "StrPattern can only be derived for enums and structs",
+                )
+                .to_compile_error(),
+            )
+        }
+    };
+
+    let EnumArgs {
+        dialect,
+        on_parse,
+        kind,
+        from_str,
+        skip_display,
+        no_std,
+        ..
+    } = args;
+
+    let record = on_parse.as_ref().map(|on_parse| {
+        quote! {
+            if let Some(value) = &value {
+                #on_parse(value);
+            }
+        }
+    });
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let regexes_ident = Ident::new(&format!("__{}_REGEXES", ident).to_uppercase(), input.span());
+    let regex_set_ident = Ident::new(
+        &format!("__{}_REGEX_SET", ident).to_uppercase(),
+        input.span(),
+    );
+    let sources_ident = Ident::new(
+        &format!("__{}_REGEX_SOURCES", ident).to_uppercase(),
+        input.span(),
+    );
+
+    let matcher_ident = Ident::new(
+        &format!("__str_pattern_{}", ident).to_lowercase(),
+        input.span(),
+    );
+
+    // Re-exported by the runtime crate, so crates deriving `StrPattern` don't need them
+    let regex = quote! { #krate::__private::regex };
+    let once_cell = quote! { #krate::__private::once_cell };
+
+    // How the patterns that may match are found, how the captures of one of them are extracted,
+    // and what holds them
+    let (statics, candidates, captures, captures_type) = if no_std {
+        (
+            matcher_module(&matcher_ident, &matchers, &alloc),
+            quote! { 0..#matcher_ident::PATTERNS.len() },
+            quote! { #matcher_ident::captures(#matcher_ident::PATTERNS[i], string) },
+            quote! { #matcher_ident::Captures },
+        )
+    } else {
+        (
+            quote! {
+                const #sources_ident: &[&str] = &[ #regexes ];
+
+                // All patterns are matched in a single pass, and captures are only extracted
+                // with the individual regexes of the patterns that matched
+                static #regex_set_ident: #once_cell::sync::Lazy<#regex::RegexSet> =
+                    #once_cell::sync::Lazy::new(|| #regex::RegexSet::new(#sources_ident).unwrap());
+
+                // Compiled one by one the first time they match, since most patterns never do
+                static #regexes_ident:
+                    [#once_cell::sync::OnceCell<#regex::Regex>; #sources_ident.len()] =
+                    [const { #once_cell::sync::OnceCell::new() }; #sources_ident.len()];
+
+                impl #impl_generics #ident #ty_generics #where_clause {
+                    fn __str_pattern_regex(i: usize) -> &'static #regex::Regex {
+                        #regexes_ident[i]
+                            .get_or_init(|| #regex::Regex::new(#sources_ident[i]).unwrap())
+                    }
+                }
+            },
+            quote! { #regex_set_ident.matches(string).into_iter() },
+            quote! { Self::__str_pattern_regex(i).captures(string) },
+            quote! { #regex::Captures },
+        )
+    };
+
+    let from_string_with = dialect.map(|dialect| {
+        quote! {
+            pub fn from_string_with(
+                string: &str,
+                dialect: #dialect,
+            ) -> ::core::option::Option<Self> {
+                for i in #candidates {
+                    let in_dialect = match i {
+                        #dialect_arms
+                        _ => true
+                    };
+
+                    if !in_dialect {
+                        continue;
+                    }
+
+                    let Some(caps) = #captures else {
+                        continue;
+                    };
+
+                    let value = Self::__str_pattern_from_captures(i, &caps);
+
+                    if value.is_some() {
+                        #record
+
+                        return value;
+                    }
+                }
+
+                None
+            }
+        }
+    });
+
+    let parse = fallback.map(|fallback| {
+        let record = on_parse
+            .as_ref()
+            .map(|on_parse| quote! { #on_parse(&value); });
+
+        quote! {
+            /// Like `from_string`, but returns the fallback variant holding the whole input
+            /// when no pattern matches
+            pub fn parse(string: &str) -> Self {
+                Self::from_string(string).unwrap_or_else(|| {
+                    let value = Self::#fallback(string.into());
+
+                    #record
+
+                    value
+                })
+            }
+        }
+    });
+
+    // Only an enum gets a `kind = Name` enum, so only its patterns can hold one
+    let pattern_info = match (&kind, &input.data) {
+        (Some(kind), syn::Data::Enum(_)) => quote! { #krate::pattern::PatternInfo<#kind> },
+        _ => quote! { #krate::pattern::PatternInfo },
+    };
+
+    let kind = match (kind, &input.data) {
+        (Some(kind), syn::Data::Enum(data)) => Some(kind_enum(&input, data, &kind)),
+        _ => None,
+    };
+
+    let from_str = from_str.map(|error| from_str_impl(&input, &error, &alloc));
+
+    let display = (!skip_display).then(|| {
+        quote! {
+            impl #impl_generics ::core::fmt::Display for #ident #ty_generics #where_clause {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    match self {
+                        #display_arms
+                    }
+                }
+            }
+        }
+    });
+
+    let output: proc_macro2::TokenStream = {
+        quote! {
+            #statics
+
+            impl #impl_generics #ident #ty_generics #where_clause {
+                /// Parses a message with the first pattern that matches it. A value parsed
+                /// back from its own `Display` comes out the same, quotes and separators in its
+                /// fields included, with two exceptions:
+                ///
+                /// - a field with a line break, unless its pattern is `dotall`, matches no
+                ///   pattern or another one
+                /// - a field containing the whole literal between its var and the next one, like
+                ///   `' and '` in `'{a}' and '{b}'`, splits the message in the wrong place
+                pub fn from_string(string: &str) -> ::core::option::Option<Self> {
+                    // Candidates are yielded in index order, which is the order patterns are
+                    // tried in
+                    for i in #candidates {
+                        let Some(caps) = #captures else {
+                            continue;
+                        };
+
+                        let value = Self::__str_pattern_from_captures(i, &caps);
+
+                        if value.is_some() {
+                            #record
+
+                            return value;
+                        }
+                    }
+
+                    None
+                }
+
+                #from_string_with
+
+                #parse
+
+                /// The name of the variant, which identifies the kind of value independently
+                /// of its wording
+                pub fn code(&self) -> &'static str {
+                    match self {
+                        #code_arms
+                    }
+                }
+
+                /// Every template with its variant, the variant's kind and the names of its
+                /// fields, in declaration order. A variant with aliases is listed once per template, and the
+                /// fallback variant and `regex` patterns are not listed
+                pub const PATTERNS: &'static [#pattern_info] =
+                    &[ #pattern_entries ];
+
+                /// The same as `PATTERNS`, from before it was a constant
+                pub fn patterns() -> &'static [#pattern_info] {
+                    Self::PATTERNS
+                }
+
+                /// The values of the variant's fields, by field name. Unnamed fields are named
+                /// by their index
+                pub fn fields(&self) -> #alloc::vec::Vec<(&'static str, #alloc::string::String)> {
+                    match self {
+                        #field_arms
+                    }
+                }
+
+                // Fields are parsed with `FromCapture`. A capture that does not parse rejects the
+                // pattern, so the next one is tried instead of storing a garbage value
+                fn __str_pattern_from_captures(
+                    i: usize,
+                    caps: &#captures_type,
+                ) -> ::core::option::Option<Self> {
+                    match i {
+                        #match_arms
+                        _ => None
+                    }
+                }
+            }
+
+            #kind
+
+            #from_str
+
+            #display
+        }
+    };
+
+    proc_macro::TokenStream::from(output)
+}
+
+struct Generated {
+    match_arms: proc_macro2::TokenStream,
+    regexes: proc_macro2::TokenStream,
+    /// The segments of every pattern for the matcher generated with `no_std`, in the same order
+    /// as `regexes`
+    matchers: proc_macro2::TokenStream,
+    display_arms: proc_macro2::TokenStream,
+    dialect_arms: proc_macro2::TokenStream,
+    code_arms: proc_macro2::TokenStream,
+    field_arms: proc_macro2::TokenStream,
+    pattern_entries: proc_macro2::TokenStream,
+    fallback: Option<Ident>,
+}
+
+/// The arguments of a variant's `#[str_pattern(...)]` attribute
+enum PatternArgs {
+    /// `#[str_pattern(fallback)]`, for the variant that holds input no pattern matched
+    Fallback,
+    /// `#[str_pattern("...", dialect = <value>, parse = false, priority = <int>, <var> = lazy)]`,
+    /// where all keys are optional. With `parse = false` the template is only used for `Display`.
+    /// Patterns with a higher priority (0 by default) are tried first. A var can also be given a
+    /// regex of its own, like `line = r"\d+"`, and `captures = lazy` changes the default of all
+    /// vars. `case_insensitive` ignores the casing of the message, and `dotall` lets vars span
+    /// lines, for messages that embed a stack trace or a source snippet
+    Template {
+        template: syn::LitStr,
+        dialect: Option<Box<syn::Expr>>,
+        parse: bool,
+        priority: i32,
+        case_insensitive: bool,
+        dotall: bool,
+        default_capture: Option<Capture>,
+        captures: Vec<(String, Capture, proc_macro2::Span)>,
+    },
+    /// `#[str_pattern(regex = r"...", dialect = <value>, priority = <int>, case_insensitive,
+    /// dotall)]`, for messages the template syntax can't describe. Every field needs a named group,
+    /// `(?<_0>...)` for unnamed fields. Regexes can't be used for `Display`, and are tried after
+    /// the templates of the same priority
+    Regex {
+        regex: syn::LitStr,
+        dialect: Option<Box<syn::Expr>>,
+        priority: i32,
+        case_insensitive: bool,
+        dotall: bool,
+    },
+}
+
+/// How much text a template var captures. Vars are greedy by default, which mis-splits a message
+/// when an earlier var's value contains the literal text that follows it
+#[derive(Clone)]
+enum Capture {
+    /// `.*`, as much as possible
+    Greedy,
+    /// `.*?`, as little as possible
+    Lazy,
+    /// Anything but the character the var is wrapped in, e.g. `[^']*` for `'{value}'`
+    Bounded,
+    /// A regex of its own, e.g. `line = r"\d+"`
+    Regex(String),
+}
+
+/// The arguments of the `#[str_pattern(...)]` attributes on the enum
+#[derive(Default)]
+struct EnumArgs {
+    /// `dialect = Type`. When it is set, patterns can be tagged with `dialect = <value>` and a
+    /// `from_string_with` is generated that only tries the untagged patterns and the ones tagged
+    /// with the given dialect
+    dialect: Option<syn::Type>,
+    /// `on_parse = path`, a `fn(&Self)` called with every value that was parsed, including the
+    /// fallback variant returned by `parse`
+    on_parse: Option<syn::Path>,
+    /// `kind = Name`, generates a fieldless enum `Name` with the same variants and a `kind()`
+    /// that returns the variant of a value without its fields
+    kind: Option<Ident>,
+    /// `from_str = Name`, implements `FromStr` with `from_string`, and generates the error type
+    /// `Name` holding the input no pattern matched
+    from_str: Option<Ident>,
+    /// `case_insensitive`, matches every pattern regardless of casing, for messages that went
+    /// through a proxy or logger that changed it
+    case_insensitive: bool,
+    /// `display = false`, for enums with a `Display` of their own. Templates then only describe
+    /// what is parsed, and variants can be matched with `regex` patterns alone
+    skip_display: bool,
+    /// `crate = "path"`, where the generated code finds this crate's runtime support, for crates
+    /// that depend on it under another name. `::surreal_error_parser` by default
+    krate: Option<syn::Path>,
+    /// `no_std`, generates code that only needs `core` and `alloc`, with a backtracking matcher
+    /// instead of `regex`. The matcher doesn't support var regexes, `regex` patterns, optional
+    /// segments or `case_insensitive`, and the crate has to declare `extern crate alloc`. The
+    /// generated code still uses `capture` and `pattern` of the runtime crate, which only need
+    /// `core` and `alloc` once its default `std` feature is turned off
+    no_std: bool,
+}
+
+fn enum_args(attrs: &[Attribute]) -> syn::Result<EnumArgs> {
+    let mut args = EnumArgs::default();
+
+    for attribute in attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("str_pattern"))
+    {
+        attribute.parse_nested_meta(|meta| {
+            if meta.path.is_ident("dialect") {
+                args.dialect = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("on_parse") {
+                args.on_parse = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("kind") {
+                args.kind = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("from_str") {
+                args.from_str = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("case_insensitive") {
+                args.case_insensitive = true;
+            } else if meta.path.is_ident("no_std") {
+                args.no_std = true;
+            } else if meta.path.is_ident("crate") {
+                args.krate = Some(meta.value()?.parse::<syn::LitStr>()?.parse()?);
+            } else if meta.path.is_ident("display") {
+                args.skip_display = !meta.value()?.parse::<syn::LitBool>()?.value;
+            } else {
+                return Err(meta.error(
+                    "expected `dialect = Type`, `on_parse = path`, `kind = Name`, \
+                     `from_str = Name`, `case_insensitive`, `display = false`, \
+                     `crate = \"path\"` or `no_std`",
+                ));
+            }
+
+            Ok(())
+        })?;
+    }
+
+    Ok(args)
+}
+
+/// Whether an attribute is `#[str_pattern(crate = "...")]` or `#[str_pattern(no_std)]`, the only
+/// ones a struct takes besides its patterns
+fn is_type_arg(attribute: &Attribute) -> bool {
+    attribute
+        .parse_args_with(|input: syn::parse::ParseStream| {
+            let is_type_arg = input.peek(syn::Token![crate])
+                || input
+                    .fork()
+                    .parse::<Ident>()
+                    .is_ok_and(|ident| ident == "no_std");
+
+            input.parse::<proc_macro2::TokenStream>()?;
+
+            Ok(is_type_arg)
+        })
+        .unwrap_or(false)
+}
+
+/// Where `Vec` and `String` are found
+fn alloc_path(no_std: bool) -> proc_macro2::TokenStream {
+    if no_std {
+        quote! { ::alloc }
+    } else {
+        quote! { ::std }
+    }
+}
+
+fn pattern_args(attribute: &Attribute) -> syn::Result<PatternArgs> {
+    attribute.parse_args_with(|input: syn::parse::ParseStream| {
+        if input.peek(syn::Ident) {
+            let key = input.parse::<Ident>()?;
+
+            if key == "fallback" {
+                return Ok(PatternArgs::Fallback);
+            }
+
+            if key != "regex" {
+                return Err(syn::Error::new_spanned(
+                    key,
+                    "expected a template, `regex = r\"...\"` or `fallback`",
+                ));
+            }
+
+            input.parse::<syn::Token![=]>()?;
+
+            let regex = input.parse::<syn::LitStr>()?;
+            let mut dialect = None;
+            let mut priority = 0;
+            let mut case_insensitive = false;
+            let mut dotall = false;
+
+            while !input.is_empty() {
+                input.parse::<syn::Token![,]>()?;
+
+                let key = input.parse::<Ident>()?;
+
+                if key == "case_insensitive" {
+                    case_insensitive = true;
+
+                    continue;
+                }
+
+                if key == "dotall" {
+                    dotall = true;
+
+                    continue;
+                }
+
+                input.parse::<syn::Token![=]>()?;
+
+                if key == "dialect" {
+                    dialect = Some(Box::new(input.parse::<syn::Expr>()?));
+                } else if key == "priority" {
+                    priority = input.parse::<syn::LitInt>()?.base10_parse()?;
+                } else {
+                    return Err(syn::Error::new_spanned(
+                        key,
+                        "expected `dialect`, `priority`, `case_insensitive` or `dotall`",
+                    ));
+                }
+            }
+
+            return Ok(PatternArgs::Regex {
+                regex,
+                dialect,
+                priority,
+                case_insensitive,
+                dotall,
+            });
+        }
+
+        let template = input.parse::<syn::LitStr>()?;
+        let mut dialect = None;
+        let mut parse = true;
+        let mut priority = 0;
+        let mut case_insensitive = false;
+        let mut dotall = false;
+        let mut default_capture = None;
+        let mut captures = vec![];
+
+        while !input.is_empty() {
+            input.parse::<syn::Token![,]>()?;
+
+            // Tuple variants name their vars by index
+            let (key, span) = if input.peek(syn::LitInt) {
+                let index = input.parse::<syn::LitInt>()?;
+
+                (index.base10_digits().to_string(), index.span())
+            } else {
+                let ident = input.parse::<Ident>()?;
+
+                (ident.to_string(), ident.span())
+            };
+
+            // A flag, unless it's a var that happens to have the same name
+            if key == "case_insensitive" && !input.peek(syn::Token![=]) {
+                case_insensitive = true;
+
+                continue;
+            }
+
+            if key == "dotall" && !input.peek(syn::Token![=]) {
+                dotall = true;
+
+                continue;
+            }
+
+            input.parse::<syn::Token![=]>()?;
+
+            if key == "dialect" {
+                dialect = Some(Box::new(input.parse::<syn::Expr>()?));
+            } else if key == "parse" {
+                parse = input.parse::<syn::LitBool>()?.value;
+            } else if key == "priority" {
+                priority = input.parse::<syn::LitInt>()?.base10_parse()?;
+            } else if key == "captures" {
+                default_capture = Some(capture(input)?);
+            } else {
+                captures.push((key, capture(input)?, span));
+            }
+        }
+
+        Ok(PatternArgs::Template {
+            template,
+            dialect,
+            parse,
+            priority,
+            case_insensitive,
+            dotall,
+            default_capture,
+            captures,
+        })
+    })
+}
+
+fn capture(input: syn::parse::ParseStream) -> syn::Result<Capture> {
+    if input.peek(syn::LitStr) {
+        let regex = input.parse::<syn::LitStr>()?;
+
+        // Compiled here so a typo fails the build instead of the first parse
+        if let Err(err) = regex::Regex::new(&regex.value()) {
+            return Err(syn::Error::new_spanned(regex, err));
+        }
+
+        return Ok(Capture::Regex(regex.value()));
+    }
+
+    let value = input.parse::<Ident>()?;
+
+    if value == "greedy" {
+        Ok(Capture::Greedy)
+    } else if value == "lazy" {
+        Ok(Capture::Lazy)
+    } else if value == "bounded" {
+        Ok(Capture::Bounded)
+    } else {
+        Err(syn::Error::new_spanned(
+            value,
+            "expected `greedy`, `lazy`, `bounded` or a regex",
+        ))
+    }
+}
+
+/// A pattern that takes part in parsing. They are collected first and only numbered once they
+/// have been put in the order they are tried in
+struct Pattern<'a> {
+    attribute: &'a Attribute,
+    regex: String,
+    guard: proc_macro2::TokenStream,
+    value: proc_macro2::TokenStream,
+    dialect: Option<Box<syn::Expr>>,
+    priority: i32,
+    /// The template with its vars blanked out, or the raw regex. Two patterns with the same
+    /// skeleton match exactly the same messages
+    skeleton: String,
+    /// The number of literal characters. The more a pattern spells out, the fewer messages it can
+    /// match, so it's tried before patterns that leave more to their vars
+    specificity: usize,
+    /// The segments for the matcher generated with `no_std`, empty otherwise
+    matcher: proc_macro2::TokenStream,
+}
+
+fn impl_enum(
+    data: &syn::DataEnum,
+    args: &EnumArgs,
+    is_struct: bool,
+    krate: &syn::Path,
+) -> syn::Result<Generated> {
+    let has_dialect = args.dialect.is_some();
+    let EnumArgs {
+        case_insensitive,
+        skip_display,
+        no_std,
+        ..
+    } = *args;
+    let alloc = alloc_path(no_std);
+
+    let mut patterns = vec![];
+    let mut display_arms = vec![];
+    let mut code_arms = vec![];
+    let mut field_arms = vec![];
+    let mut pattern_entries = vec![];
+    let mut fallback = None;
+
+    for variant in data.variants.iter() {
+        let attributes = variant
+            .attrs
+            .iter()
+            .filter(|attr| {
+                let ident = attr.meta.path().get_ident();
+
+                match ident {
+                    Some(ident) => ident == "str_pattern",
+                    None => false,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        if attributes.is_empty() {
+            return Err(syn::Error::new_spanned(
+                variant,
+                "missing `#[str_pattern(\"...\")]` attribute",
+            ));
+        }
+
+        let path = constructor(variant, is_struct);
+
+        let (code_arm, field_arm) = variant_accessors(variant, &path, &alloc);
+
+        code_arms.push(code_arm);
+        field_arms.push(field_arm);
+
+        let code = syn::LitStr::new(&variant.ident.to_string(), variant.ident.span());
+        let field_names = field_names(variant);
+        let kind = match (&args.kind, is_struct) {
+            (Some(kind), false) => {
+                let variant_ident = &variant.ident;
+
+                quote! { #kind::#variant_ident }
+            }
+            _ => quote! { () },
+        };
+
+        // Every attribute gets its own regex, so a variant can match messages that were worded
+        // differently across server versions. Only the first template is used for `Display`
+        let mut displayed = false;
+
+        for (n, attribute) in attributes.iter().enumerate() {
+            let attribute = *attribute;
+            let canonical = !displayed;
+
+            let variant_ident = &variant.ident;
+
+            let (
+                template,
+                dialect,
+                parse,
+                priority,
+                ignore_case,
+                dotall,
+                default_capture,
+                strategies,
+            ) = match pattern_args(attribute)? {
+                PatternArgs::Fallback if is_struct => {
+                    return Err(syn::Error::new_spanned(
+                        attribute,
+                        "only enums can have a `fallback`",
+                    ));
+                }
+                PatternArgs::Fallback => {
+                    validate_fallback(variant, n, fallback.is_some(), attribute)?;
+
+                    display_arms.push(quote! {
+                        #path(value) => f.write_str(value),
+                    });
+
+                    fallback = Some(variant_ident.clone());
+                    displayed = true;
+
+                    continue;
+                }
+                PatternArgs::Regex {
+                    regex,
+                    dialect,
+                    priority,
+                    case_insensitive: ignore_case,
+                    dotall,
+                } => {
+                    if dialect.is_some() && !has_dialect {
+                        return Err(syn::Error::new_spanned(
+                            attribute,
+                            "`dialect` requires `#[str_pattern(dialect = Type)]` on the enum",
+                        ));
+                    }
+
+                    if no_std {
+                        return Err(syn::Error::new_spanned(
+                            attribute,
+                            "`regex` patterns aren't supported with `no_std`",
+                        ));
+                    }
+
+                    patterns.push(Pattern {
+                        attribute,
+                        regex: with_case(
+                            with_dotall(format!("^(?:{})$", regex.value()), dotall),
+                            case_insensitive || ignore_case,
+                        ),
+                        guard: quote! {},
+                        value: regex_value(variant, &path, &regex, krate)?,
+                        dialect,
+                        priority,
+                        skeleton: format!("regex {}", regex.value()),
+                        specificity: 0,
+                        matcher: quote! {},
+                    });
+
+                    continue;
+                }
+                PatternArgs::Template {
+                    template,
+                    dialect,
+                    parse,
+                    priority,
+                    case_insensitive: ignore_case,
+                    dotall,
+                    default_capture,
+                    captures,
+                } => (
+                    template,
+                    dialect,
+                    parse,
+                    priority,
+                    ignore_case,
+                    dotall,
+                    default_capture,
+                    captures,
+                ),
+            };
+
+            pattern_entries.push(quote! {
+                #krate::pattern::PatternInfo {
+                    variant: #code,
+                    kind: #kind,
+                    template: #template,
+                    fields: &[#(#field_names),*],
+                },
+            });
+
+            if dialect.is_some() && !has_dialect {
+                return Err(syn::Error::new_spanned(
+                    attribute,
+                    "`dialect` requires `#[str_pattern(dialect = Type)]` on the enum",
+                ));
+            }
+
+            let segments = parse_template(&template.value());
+
+            let strategies = validate_captures(&strategies, default_capture.as_ref(), &segments)?;
+
+            let captures = vars(&segments);
+
+            validate_optional(&segments, variant, attribute)?;
+
+            if no_std && (case_insensitive || ignore_case) {
+                return Err(syn::Error::new_spanned(
+                    attribute,
+                    "`case_insensitive` isn't supported with `no_std`",
+                ));
+            }
+
+            // Named like the regex's groups, so the generated code reads both the same way
+            let matcher = if no_std {
+                let group_name = |var: &str| match &variant.fields {
+                    syn::Fields::Unnamed(_) => format!("_{}", var),
+                    _ => var.to_string(),
+                };
+
+                matcher_segments(&segments, &strategies, dotall, group_name, attribute)?
+            } else {
+                quote! {}
+            };
+
+            let (regex, guard, value) = match &variant.fields {
+                syn::Fields::Unit => {
+                    validate_unit(&captures, attribute)?;
+
+                    let (regex, _) = template_regex(&segments, &strategies, |var| var.to_string());
+
+                    if canonical {
+                        display_arms.push(quote! {
+                            #path => f.write_str(#template),
+                        });
+                    }
+
+                    (regex, quote! {}, quote! { Some(#path) })
+                }
+                syn::Fields::Unnamed(fields) => {
+                    validate_unnamed(&captures, attribute, fields)?;
+
+                    let (regex, repeated) =
+                        template_regex(&segments, &strategies, |var| format!("_{}", var));
+
+                    let values = fields
+                        .unnamed
+                        .iter()
+                        .enumerate()
+                        .map(|(i, field)| {
+                            let group = syn::LitStr::new(&format!("_{}", i), variant.span());
+
+                            field_value(&group, field, krate)
+                        })
+                        .collect::<syn::Result<Vec<_>>>()?;
+
+                    let bindings = (0..fields.unnamed.len())
+                        .map(|i| Ident::new(&format!("_{}", i), variant.span()))
+                        .collect::<Vec<Ident>>();
+
+                    let body = display_body(&segments, &|var| {
+                        Ident::new(&format!("_{}", var), variant.span())
+                    });
+
+                    if canonical {
+                        display_arms.push(quote! {
+                            #path(#(#bindings),*) => {
+                                #body
+                                Ok(())
+                            }
+                        });
+                    }
+
+                    (
+                        regex,
+                        repeated_guard(&repeated),
+                        quote! { Some(#path(#(#values),*)) },
+                    )
+                }
+                syn::Fields::Named(fields) => {
+                    validate_named(&captures, attribute, fields)?;
+
+                    let (regex, repeated) =
+                        template_regex(&segments, &strategies, |var| var.to_string());
+
+                    let field_idents = fields
+                        .named
+                        .iter()
+                        .filter_map(|f| f.ident.as_ref())
+                        .collect::<Vec<&Ident>>();
+
+                    let values = fields
+                        .named
+                        .iter()
+                        .map(|f| {
+                            let group =
+                                syn::LitStr::new(&f.ident.as_ref().unwrap().to_string(), f.span());
+
+                            field_value(&group, f, krate)
+                        })
+                        .collect::<syn::Result<Vec<_>>>()?;
+
+                    let body = display_body(&segments, &|var| Ident::new(var, variant.span()));
+
+                    if canonical {
+                        display_arms.push(quote! {
+                            #path { #(#field_idents),* } => {
+                                #body
+                                Ok(())
+                            }
+                        });
+                    }
+
+                    (
+                        regex,
+                        repeated_guard(&repeated),
+                        quote! {
+                            Some(#path {
+                                #(#field_idents: #values),*
+                            })
+                        },
+                    )
+                }
+            };
+
+            displayed = true;
+
+            if parse {
+                patterns.push(Pattern {
+                    attribute,
+                    regex: with_case(with_dotall(regex, dotall), case_insensitive || ignore_case),
+                    guard,
+                    value,
+                    dialect,
+                    priority,
+                    skeleton: skeleton(&segments),
+                    specificity: specificity(&segments),
+                    matcher,
+                });
+            }
+        }
+
+        if !displayed && !skip_display {
+            return Err(syn::Error::new_spanned(
+                variant,
+                "`regex` patterns can't be displayed. Add a template for `Display`, or opt out \
+                 with `#[str_pattern(display = false)]` on the enum",
+            ));
+        }
+    }
+
+    // Stable, so patterns that tie keep their declaration order
+    patterns.sort_by_key(|p| {
+        (
+            std::cmp::Reverse(p.priority),
+            std::cmp::Reverse(p.specificity),
+        )
+    });
+
+    validate_overlap(&patterns)?;
+
+    let mut match_arms = vec![];
+    let mut regexes = vec![];
+    let mut matchers = vec![];
+    let mut dialect_arms = vec![];
+
+    for (i, pattern) in patterns.iter().enumerate() {
+        let Pattern {
+            attribute,
+            regex,
+            guard,
+            value,
+            dialect,
+            matcher,
+            ..
+        } = pattern;
+
+        // The parts of a pattern can be valid on their own and still clash once put together,
+        // like a var regex with a group of the same name as a var. Compiling the whole regex
+        // here fails the build instead of the first parse
+        if let Err(err) = regex::Regex::new(regex) {
+            return Err(syn::Error::new_spanned(
+                attribute,
+                format!("the pattern compiles to an invalid regex: {}", err),
+            ));
+        }
+
+        match_arms.push(quote! { #i #guard => #value, });
+        regexes.push(quote! { #regex, });
+        matchers.push(quote! { #matcher, });
+
+        if let Some(dialect) = dialect {
+            dialect_arms.push(quote! { #i => dialect == #dialect, });
+        }
+    }
+
+    // Patterns that are valid one by one can still exceed the size limit of the set
+    if let Err(err) = regex::RegexSet::new(patterns.iter().map(|p| &p.regex)) {
+        return Err(syn::Error::new_spanned(
+            data.enum_token,
+            format!("the patterns don't fit in a single regex set: {}", err),
+        ));
+    }
+
+    Ok(Generated {
+        match_arms: match_arms.into_iter().collect(),
+        regexes: regexes.into_iter().collect(),
+        matchers: matchers.into_iter().collect(),
+        display_arms: display_arms.into_iter().collect(),
+        dialect_arms: dialect_arms.into_iter().collect(),
+        code_arms: code_arms.into_iter().collect(),
+        field_arms: field_arms.into_iter().collect(),
+        pattern_entries: pattern_entries.into_iter().collect(),
+        fallback,
+    })
+}
+
+fn skeleton(segments: &[Segment]) -> String {
+    segments
+        .iter()
+        .map(|s| match s {
+            Segment::Literal(literal) => literal.clone(),
+            Segment::Var(_) => "{}".to_string(),
+            Segment::Optional(segments) => format!("[{}]", skeleton(segments)),
+        })
+        .collect()
+}
+
+/// Optional segments don't count, since the messages without them match as well
+fn specificity(segments: &[Segment]) -> usize {
+    segments
+        .iter()
+        .map(|s| match s {
+            Segment::Literal(literal) => literal.chars().count(),
+            Segment::Var(_) | Segment::Optional(_) => 0,
+        })
+        .sum()
+}
+
+/// A piece of a `#[str_pattern("...")]` template. Both the regex used for parsing and the
+/// `Display` impl are generated from these, so the two directions can't drift apart
+enum Segment {
+    Literal(String),
+    Var(String),
+    /// `[...]`, a part of the message that's left out by some server versions. Brackets only
+    /// make a segment optional when there's a var between them, which has to be an `Option` field.
+    /// A greedy var right in front of one swallows it, so such vars are best made lazy
+    Optional(Vec<Segment>),
+}
+
+fn parse_template(template: &str) -> Vec<Segment> {
+    let optional_regex = regex::Regex::new(r"\[([^\[\]]*\{\w+\}[^\[\]]*)\]").unwrap();
+
+    let mut segments = vec![];
+    let mut last = 0;
+
+    for c in optional_regex.captures_iter(template) {
+        let m = c.get(0).unwrap();
+
+        segments.extend(parse_vars(&template[last..m.start()]));
+        segments.push(Segment::Optional(parse_vars(&c[1])));
+
+        last = m.end();
+    }
+
+    segments.extend(parse_vars(&template[last..]));
+
+    segments
+}
+
+fn parse_vars(template: &str) -> Vec<Segment> {
+    let template_var_regex = regex::Regex::new(r"\{(\w+)\}").unwrap();
+
+    let mut segments = vec![];
+    let mut last = 0;
+
+    for c in template_var_regex.captures_iter(template) {
+        let m = c.get(0).unwrap();
+
+        if m.start() > last {
+            segments.push(Segment::Literal(template[last..m.start()].to_string()));
+        }
+
+        segments.push(Segment::Var(c[1].to_string()));
+
+        last = m.end();
+    }
+
+    if last < template.len() {
+        segments.push(Segment::Literal(template[last..].to_string()));
+    }
+
+    segments
+}
+
+/// Builds the regex for a template, naming each capture group with `group_name`. A var that is
+/// used more than once gets an extra `{group}__{n}` group per repetition, returned alongside the
+/// regex so that the generated code can check that all of them captured the same text.
+///
+/// The regex is anchored so only whole messages match
+fn template_regex(
+    segments: &[Segment],
+    captures: &HashMap<String, Capture>,
+    group_name: impl Fn(&str) -> String,
+) -> (String, Vec<(String, String)>) {
+    let mut regex = String::from("^");
+    let mut seen: Vec<String> = vec![];
+    let mut repeated = vec![];
+
+    push_segments(
+        segments,
+        captures,
+        &group_name,
+        &mut regex,
+        &mut seen,
+        &mut repeated,
+    );
+
+    regex.push('$');
+
+    (regex, repeated)
+}
+
+fn push_segments(
+    segments: &[Segment],
+    captures: &HashMap<String, Capture>,
+    group_name: &impl Fn(&str) -> String,
+    regex: &mut String,
+    seen: &mut Vec<String>,
+    repeated: &mut Vec<(String, String)>,
+) {
+    for (i, segment) in segments.iter().enumerate() {
+        match segment {
+            Segment::Literal(literal) => regex.push_str(&regex::escape(literal)),
+            Segment::Optional(optional) => {
+                regex.push_str("(?:");
+                push_segments(optional, captures, group_name, regex, seen, repeated);
+                regex.push_str(")?");
+            }
+            Segment::Var(var) => {
+                let group = group_name(var);
+                let count = seen.iter().filter(|s| **s == group).count();
+
+                let class = match captures.get(var) {
+                    None | Some(Capture::Greedy) => ".*".to_string(),
+                    Some(Capture::Lazy) => ".*?".to_string(),
+                    Some(Capture::Bounded) => {
+                        // Checked by `validate_captures`
+                        let delimiter = delimiter(segments, i).unwrap();
+
+                        format!("[^{}]*", regex::escape(&delimiter.to_string()))
+                    }
+                    Some(Capture::Regex(regex)) => format!("(?:{})", regex),
+                };
+
+                if count == 0 {
+                    regex.push_str(&format!(r"(?<{}>{})", group, class));
+                } else {
+                    let repeat = format!("{}__{}", group, count);
+
+                    regex.push_str(&format!(r"(?<{}>{})", repeat, class));
+                    repeated.push((group.clone(), repeat));
+                }
+
+                seen.push(group);
+            }
+        }
+    }
+}
+
+/// The character a var is wrapped in, if the literals on both sides of it agree on one
+fn delimiter(segments: &[Segment], index: usize) -> Option<char> {
+    let before = match segments.get(index.checked_sub(1)?)? {
+        Segment::Literal(literal) => literal.chars().last()?,
+        Segment::Var(_) | Segment::Optional(_) => return None,
+    };
+
+    let after = match segments.get(index + 1)? {
+        Segment::Literal(literal) => literal.chars().next()?,
+        Segment::Var(_) | Segment::Optional(_) => return None,
+    };
+
+    (before == after).then_some(before)
+}
+
+fn repeated_guard(repeated: &[(String, String)]) -> proc_macro2::TokenStream {
+    if repeated.is_empty() {
+        return quote! {};
+    }
+
+    let (first, repeat): (Vec<_>, Vec<_>) = repeated.iter().cloned().unzip();
+
+    quote! { if #(caps[#first] == caps[#repeat])&&* }
+}
+
+/// The segments of a template for the matcher generated with `no_std`, with the same group names
+/// as `template_regex`. The matcher only knows literals and vars that capture any text
+fn matcher_segments(
+    segments: &[Segment],
+    captures: &HashMap<String, Capture>,
+    dotall: bool,
+    group_name: impl Fn(&str) -> String,
+    attribute: &Attribute,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let mut seen: Vec<String> = vec![];
+    let mut matcher = vec![];
+
+    for (i, segment) in segments.iter().enumerate() {
+        match segment {
+            Segment::Literal(literal) => matcher.push(quote! { Segment::Literal(#literal) }),
+            Segment::Optional(_) => {
+                return Err(syn::Error::new_spanned(
+                    attribute,
+                    "optional segments aren't supported with `no_std`",
+                ));
+            }
+            Segment::Var(var) => {
+                let group = group_name(var);
+                let count = seen.iter().filter(|s| **s == group).count();
+
+                let capture = match captures.get(var) {
+                    None | Some(Capture::Greedy) => quote! { Capture::Greedy(#dotall) },
+                    Some(Capture::Lazy) => quote! { Capture::Lazy(#dotall) },
+                    Some(Capture::Bounded) => {
+                        // Checked by `validate_captures`
+                        let delimiter = delimiter(segments, i).unwrap();
+
+                        quote! { Capture::Bounded(#delimiter) }
+                    }
+                    Some(Capture::Regex(_)) => {
+                        return Err(syn::Error::new_spanned(
+                            attribute,
+                            format!("var `{}` can't have a regex with `no_std`", var),
+                        ));
+                    }
+                };
+
+                let name = if count == 0 {
+                    group.clone()
+                } else {
+                    format!("{}__{}", group, count)
+                };
 
-    let (match_arms, regexes) = match &input.data {
-        syn::Data::Enum(d) => match impl_enum(d) {
-            Ok(output) => output,
-            Err(err) => return proc_macro::TokenStream::from(err.to_compile_error()),
-        },
-        _ => {
-            return proc_macro::TokenStream::from(proc_macro2::TokenStream::from(
-                syn::Error::new(input.span(), "StrPattern can only be derived for enums")
-                    .to_compile_error(),
-            ))
+                matcher.push(quote! { Segment::Var(#name, #capture) });
+                seen.push(group);
+            }
         }
-    };
+    }
 
-    let ident = &input.ident;
+    Ok(quote! { &[#(#matcher),*] })
+}
 
-    let regexes_ident = Ident::new(&format!("__{}_REGEXES", ident).to_uppercase(), input.span());
+/// The backtracking matcher that replaces `regex` with `no_std`. It remembers where the rest of
+/// a pattern failed to match, so it takes polynomial time rather than exponential. Its
+/// `Captures` has the part of `regex::Captures` the generated code uses, so the rest of it
+/// doesn't change
+fn matcher_module(
+    module: &Ident,
+    patterns: &proc_macro2::TokenStream,
+    alloc: &proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    quote! {
+        #[doc(hidden)]
+        mod #module {
+            pub const PATTERNS: &[&[Segment]] = &[ #patterns ];
 
-    let output: proc_macro2::TokenStream = {
-        quote! {
-            static #regexes_ident:
-                ::once_cell::sync::Lazy<::std::vec::Vec<::regex::Regex>> =
-                ::once_cell::sync::Lazy::new(|| vec![ #regexes ]);
-
-            impl #ident {
-                pub fn from_string(string: &str) -> ::std::option::Option<Self> {
-                    for (i, re) in #regexes_ident.iter().enumerate() {
-                        if re.is_match(string) {
-                            let caps = re.captures(string)?;
-
-                            return match i {
-                                #match_arms
-                                _ => None
-                            };
-                        }
-                    }
+            pub enum Segment {
+                Literal(&'static str),
+                Var(&'static str, Capture),
+            }
+
+            // Greedy and lazy vars stop at the end of the line, unless the pattern is `dotall`
+            pub enum Capture {
+                Greedy(bool),
+                Lazy(bool),
+                Bounded(char),
+            }
+
+            pub struct Match<'h>(&'h str);
+
+            impl<'h> Match<'h> {
+                pub fn as_str(&self) -> &'h str {
+                    self.0
+                }
+            }
+
+            pub struct Captures<'h>(#alloc::vec::Vec<(&'static str, &'h str)>);
+
+            impl<'h> Captures<'h> {
+                pub fn name(&self, name: &str) -> ::core::option::Option<Match<'h>> {
+                    self.0
+                        .iter()
+                        .find(|(group, _)| *group == name)
+                        .map(|(_, capture)| Match(capture))
+                }
+            }
+
+            impl ::core::ops::Index<&str> for Captures<'_> {
+                type Output = str;
+
+                fn index(&self, name: &str) -> &str {
+                    self.name(name).unwrap().0
+                }
+            }
 
+            pub fn captures<'h>(
+                segments: &[Segment],
+                string: &'h str,
+            ) -> ::core::option::Option<Captures<'h>> {
+                let mut captures = #alloc::vec::Vec::new();
+                let mut failed = #alloc::vec![false; (segments.len() + 1) * (string.len() + 1)];
+
+                if matches(segments, 0, string, 0, &mut captures, &mut failed) {
+                    Some(Captures(captures))
+                } else {
                     None
                 }
             }
-        }
-    };
 
-    return proc_macro::TokenStream::from(output);
-}
+            // Whether the segments from `i` on match `string` from `offset` on. Pairs that didn't
+            // are remembered in `failed`, so each one is tried once instead of once for every
+            // way the vars before it can split the text, which takes exponential time
+            fn matches<'h>(
+                segments: &[Segment],
+                i: usize,
+                string: &'h str,
+                offset: usize,
+                captures: &mut #alloc::vec::Vec<(&'static str, &'h str)>,
+                failed: &mut [bool],
+            ) -> bool {
+                let state = i * (string.len() + 1) + offset;
 
-fn impl_enum(
-    data: &syn::DataEnum,
-) -> syn::Result<(proc_macro2::TokenStream, proc_macro2::TokenStream)> {
-    let str_pattern_regex = regex::Regex::new(r"\\\{\w+\\\}").unwrap();
+                if failed[state] {
+                    return false;
+                }
 
-    let mut match_arms = vec![];
-    let mut regexes = vec![];
+                let rest = &string[offset..];
+
+                let matched = match segments.get(i) {
+                    None => rest.is_empty(),
+                    Some(Segment::Literal(literal)) => {
+                        let end = offset + literal.len();
+
+                        rest.starts_with(literal)
+                            && matches(segments, i + 1, string, end, captures, failed)
+                    }
+                    Some(Segment::Var(group, capture)) => {
+                        let limit = match capture {
+                            Capture::Bounded(delimiter) => {
+                                rest.find(*delimiter).unwrap_or(rest.len())
+                            }
+                            Capture::Greedy(true) | Capture::Lazy(true) => rest.len(),
+                            Capture::Greedy(false) | Capture::Lazy(false) => {
+                                rest.find('\n').unwrap_or(rest.len())
+                            }
+                        };
+
+                        let mut ends = (0..=limit).filter(|end| rest.is_char_boundary(*end));
+
+                        // Greedy vars try the longest capture first, like `.*`, lazy ones the
+                        // shortest
+                        let mut attempt = |end: usize| {
+                            captures.push((*group, &rest[..end]));
 
-    for (i, variant) in data.variants.iter().enumerate() {
-        let Some(attribute) = variant.attrs.iter().find(|attr| {
-            let ident = attr.meta.path().get_ident();
+                            if matches(segments, i + 1, string, offset + end, captures, failed) {
+                                return true;
+                            }
 
-            match ident {
-                Some(ident) => ident == "str_pattern",
-                None => false,
+                            captures.pop();
+
+                            false
+                        };
+
+                        match capture {
+                            Capture::Lazy(_) => ends.any(&mut attempt),
+                            Capture::Greedy(_) | Capture::Bounded(_) => {
+                                ends.rev().any(&mut attempt)
+                            }
+                        }
+                    }
+                };
+
+                if !matched {
+                    failed[state] = true;
+                }
+
+                matched
             }
-        }) else {
-            return Err(syn::Error::new_spanned(
-                variant,
-                "missing `#[str_pattern(\"...\")]` attribute",
-            ));
-        };
+        }
+    }
+}
 
-        let str_value = regex::escape(&attribute.parse_args::<syn::LitStr>()?.value());
+/// The fieldless enum generated for `kind = Name`, and the `kind()` that converts to it. The
+/// variants keep their docs, and the enum is `#[non_exhaustive]` when the derived one is
+fn kind_enum(input: &DeriveInput, data: &syn::DataEnum, kind: &Ident) -> proc_macro2::TokenStream {
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let vis = &input.vis;
 
-        let captures = str_pattern_regex
-            .captures_iter(&str_value)
-            .map(|c| c.iter().flatten().map(strip_brackets).last())
-            .collect::<Vec<_>>();
+    let non_exhaustive = input
+        .attrs
+        .iter()
+        .find(|attr| attr.path().is_ident("non_exhaustive"));
+
+    let variants = data.variants.iter().map(|variant| {
+        let variant_ident = &variant.ident;
+        let docs = variant
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("doc"));
+
+        quote! {
+            #(#docs)*
+            #variant_ident,
+        }
+    });
 
+    let arms = data.variants.iter().map(|variant| {
         let variant_ident = &variant.ident;
 
         match &variant.fields {
-            syn::Fields::Unit => {
-                validate_unit(&captures, attribute)?;
+            syn::Fields::Unit => quote! { Self::#variant_ident => #kind::#variant_ident, },
+            syn::Fields::Unnamed(_) => {
+                quote! { Self::#variant_ident(..) => #kind::#variant_ident, }
+            }
+            syn::Fields::Named(_) => {
+                quote! { Self::#variant_ident { .. } => #kind::#variant_ident, }
+            }
+        }
+    });
 
-                match_arms.push(quote! {
-                    #i => Some(Self::#variant_ident),
-                });
+    let doc = format!("The variants of [`{}`] without their fields", ident);
 
-                regexes.push(quote! { ::regex::Regex::new(#str_value).unwrap(), });
+    quote! {
+        #[doc = #doc]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        #non_exhaustive
+        #vis enum #kind {
+            #(#variants)*
+        }
+
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// The variant of this value, without its fields
+            pub fn kind(&self) -> #kind {
+                match self {
+                    #(#arms)*
+                }
             }
-            syn::Fields::Unnamed(fields) => {
-                validate_unnamed(&captures, attribute, fields)?;
+        }
+    }
+}
 
-                let str_value = str_pattern_regex
-                    .replace_all(&str_value, |c: &regex::Captures| {
-                        let ident = c.iter().flatten().map(strip_brackets).last().unwrap();
+fn from_str_impl(
+    input: &DeriveInput,
+    error: &Ident,
+    alloc: &proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let vis = &input.vis;
 
-                        format!(r"(?<_{}>.*)", ident)
-                    })
-                    .to_string();
+    let doc = format!(
+        "The error returned when a string does not match any pattern of [`{}`]",
+        ident
+    );
 
-                let fields = fields
-                    .unnamed
-                    .iter()
-                    .enumerate()
-                    .map(|(i, _)| syn::LitStr::new(&format!("_{}", i), variant.span()))
-                    .collect::<Vec<syn::LitStr>>();
+    quote! {
+        #[doc = #doc]
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        #vis struct #error {
+            /// The string that was parsed
+            pub input: #alloc::string::String,
+            /// The template that starts with the most of the input's words, if any starts with
+            /// one at all. Usually the message the input was meant to be, with its wording changed
+            pub closest: ::core::option::Option<&'static str>,
+        }
 
-                match_arms.push(quote! {
-                    #i =>
-                    Some(Self::#variant_ident(#(caps[#fields].to_string()),*)),
-                });
+        impl ::core::fmt::Display for #error {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                write!(f, "no pattern matches {:?}", self.input)?;
+
+                if let Some(closest) = self.closest {
+                    write!(f, ", closest is {:?}", closest)?;
+                }
 
-                regexes.push(quote! { ::regex::Regex::new(#str_value).unwrap(), });
+                Ok(())
             }
-            syn::Fields::Named(fields) => {
-                validate_named(&captures, attribute, fields)?;
+        }
 
-                let mut capture_groups: Vec<String> = Vec::with_capacity(fields.named.len());
+        impl ::core::error::Error for #error {}
 
-                let str_value = str_pattern_regex
-                    .replace_all(&str_value, |c: &regex::Captures| {
-                        let ident = c.iter().flatten().map(strip_brackets).last().unwrap();
+        impl #impl_generics ::core::str::FromStr for #ident #ty_generics #where_clause {
+            type Err = #error;
 
-                        if capture_groups.contains(&ident.to_string()) {
-                            return r".*".to_string();
-                        }
+            fn from_str(string: &str) -> ::core::result::Result<Self, Self::Err> {
+                Self::from_string(string).ok_or_else(|| {
+                    // Reversed, so the first of the templates that tie wins
+                    let closest = Self::PATTERNS
+                        .iter()
+                        .rev()
+                        .map(|pattern| {
+                            let shared = pattern
+                                .template
+                                .split(' ')
+                                .zip(string.split(' '))
+                                .take_while(|(a, b)| a == b)
+                                .count();
 
-                        capture_groups.push(ident.to_string());
-                        format!(r"(?<{}>.*)", ident)
-                    })
-                    .to_string();
+                            (shared, pattern.template)
+                        })
+                        .filter(|(shared, _)| *shared > 0)
+                        .max_by_key(|(shared, _)| *shared)
+                        .map(|(_, template)| template);
 
-                let field_idents = fields
-                    .named
-                    .iter()
-                    .map(|f| &f.ident)
-                    .flatten()
-                    .collect::<Vec<&Ident>>();
+                    #error {
+                        input: string.into(),
+                        closest,
+                    }
+                })
+            }
+        }
+    }
+}
 
-                let field_literals = fields
-                    .named
-                    .iter()
-                    .map(|f| syn::LitStr::new(&f.ident.as_ref().unwrap().to_string(), f.span()))
-                    .collect::<Vec<syn::LitStr>>();
-
-                let tokens = quote! {
-                    #i =>
-                         Some(Self::#variant_ident
-                         {
-                            #(#field_idents: caps[#field_literals].to_string()),*
-                         }),
-                };
+/// The names of a variant's fields, with unnamed fields named by their index
+fn field_names(variant: &syn::Variant) -> Vec<syn::LitStr> {
+    variant
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| match &field.ident {
+            Some(ident) => syn::LitStr::new(&ident.to_string(), ident.span()),
+            None => syn::LitStr::new(&i.to_string(), variant.span()),
+        })
+        .collect()
+}
+
+/// The path that builds and matches a variant, which is just `Self` for a struct
+fn constructor(variant: &syn::Variant, is_struct: bool) -> proc_macro2::TokenStream {
+    let variant_ident = &variant.ident;
+
+    if is_struct {
+        quote! { Self }
+    } else {
+        quote! { Self::#variant_ident }
+    }
+}
+
+/// The `code` and `fields` match arms of a variant. `Option` fields are left out when they're
+/// `None`
+fn variant_accessors(
+    variant: &syn::Variant,
+    path: &proc_macro2::TokenStream,
+    alloc: &proc_macro2::TokenStream,
+) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+    let variant_ident = &variant.ident;
+    let code = syn::LitStr::new(&variant_ident.to_string(), variant_ident.span());
+
+    match &variant.fields {
+        syn::Fields::Unit => (
+            quote! { #path => #code, },
+            quote! { #path => #alloc::vec::Vec::new(), },
+        ),
+        syn::Fields::Unnamed(fields) => {
+            let names = field_names(variant);
+
+            let bindings = (0..fields.unnamed.len())
+                .map(|i| Ident::new(&format!("_{}", i), variant.span()))
+                .collect::<Vec<Ident>>();
+
+            let values = fields
+                .unnamed
+                .iter()
+                .zip(&bindings)
+                .map(|(field, binding)| field_string(binding, &field.ty, alloc));
+
+            (
+                quote! { #path(..) => #code, },
+                quote! {
+                    #path(#(#bindings),*) =>
+                        [#((#names, #values)),*]
+                            .into_iter()
+                            .filter_map(|(name, value)| Some((name, value?)))
+                            .collect(),
+                },
+            )
+        }
+        syn::Fields::Named(fields) => {
+            let field_idents = fields
+                .named
+                .iter()
+                .filter_map(|f| f.ident.as_ref())
+                .collect::<Vec<&Ident>>();
+
+            let names = field_names(variant);
+
+            let values = fields
+                .named
+                .iter()
+                .zip(&field_idents)
+                .map(|(field, ident)| field_string(ident, &field.ty, alloc));
+
+            (
+                quote! { #path { .. } => #code, },
+                quote! {
+                    #path { #(#field_idents),* } =>
+                        [#((#names, #values)),*]
+                            .into_iter()
+                            .filter_map(|(name, value)| Some((name, value?)))
+                            .collect(),
+                },
+            )
+        }
+    }
+}
+
+/// The statements of a `Display` arm, writing the template piece by piece with `var_ident`
+/// naming the binding of each var. Optional segments are only written when their vars are `Some`
+fn display_body(
+    segments: &[Segment],
+    var_ident: &impl Fn(&str) -> Ident,
+) -> proc_macro2::TokenStream {
+    segments
+        .iter()
+        .map(|segment| match segment {
+            Segment::Literal(literal) => quote! { f.write_str(#literal)?; },
+            Segment::Var(var) => {
+                let ident = var_ident(var);
+
+                quote! { write!(f, "{}", #ident)?; }
+            }
+            Segment::Optional(optional) => {
+                let idents = vars(optional)
+                    .into_iter()
+                    .map(var_ident)
+                    .collect::<Vec<_>>();
+                let body = display_body(optional, var_ident);
 
-                match_arms.push(tokens);
+                quote! {
+                    if let (#(Some(#idents),)*) = (#(#idents,)*) {
+                        #body
+                    }
+                }
+            }
+        })
+        .collect()
+}
+
+/// The vars of a template in order, including the ones in optional segments
+fn vars(segments: &[Segment]) -> Vec<&str> {
+    segments
+        .iter()
+        .flat_map(|segment| match segment {
+            Segment::Literal(_) => vec![],
+            Segment::Var(var) => vec![var.as_str()],
+            Segment::Optional(optional) => vars(optional),
+        })
+        .collect()
+}
+
+/// Every occurrence of a var, with the segments it's in and its index in them, and whether it's
+/// in an optional segment
+fn var_positions(segments: &[Segment], optional: bool) -> Vec<(&[Segment], usize, &str, bool)> {
+    segments
+        .iter()
+        .enumerate()
+        .flat_map(|(i, segment)| match segment {
+            Segment::Literal(_) => vec![],
+            Segment::Var(var) => vec![(segments, i, var.as_str(), optional)],
+            Segment::Optional(inner) => var_positions(inner, true),
+        })
+        .collect()
+}
+
+/// Parses a capture into a field with `FromCapture`, after the whitespace options of the field.
+/// An `Option` field is `None` when its group didn't take part in the match, otherwise a capture
+/// that does not parse rejects the pattern
+fn field_value(
+    group: &syn::LitStr,
+    field: &syn::Field,
+    krate: &syn::Path,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let FieldArgs {
+        trim,
+        collapse_whitespace,
+    } = field_args(field)?;
+
+    let trim = trim.then(|| quote! { let capture = capture.trim(); });
+    let collapse_whitespace = collapse_whitespace.then(|| {
+        quote! { let capture = &#krate::capture::collapse_whitespace(capture); }
+    });
+
+    let from_capture = |capture: proc_macro2::TokenStream| {
+        quote! {
+            {
+                let capture: &str = #capture;
+                #trim
+                #collapse_whitespace
+                #krate::capture::FromCapture::from_capture(capture)
+            }
+        }
+    };
+
+    if is_option(&field.ty) {
+        let value = from_capture(quote! { m.as_str() });
+
+        Ok(quote! {
+            match caps.name(#group) {
+                Some(m) => Some(#value?),
+                None => None,
+            }
+        })
+    } else {
+        let value = from_capture(quote! { &caps[#group] });
 
-                regexes.push(quote! { ::regex::Regex::new(#str_value).unwrap(), });
+        Ok(quote! { #value? })
+    }
+}
+
+/// The options of a field, from `#[str_pattern(...)]` on the field itself
+#[derive(Default)]
+struct FieldArgs {
+    /// `trim`, drops the whitespace around a capture
+    trim: bool,
+    /// `collapse_whitespace`, also turns every run of whitespace inside a capture into a single
+    /// space
+    collapse_whitespace: bool,
+}
+
+fn field_args(field: &syn::Field) -> syn::Result<FieldArgs> {
+    let mut args = FieldArgs::default();
+
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("str_pattern"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("trim") {
+                args.trim = true;
+            } else if meta.path.is_ident("collapse_whitespace") {
+                args.collapse_whitespace = true;
+            } else {
+                return Err(meta.error("expected `trim` or `collapse_whitespace`"));
             }
+
+            Ok(())
+        })?;
+    }
+
+    Ok(args)
+}
+
+fn field_string(
+    binding: &Ident,
+    ty: &syn::Type,
+    alloc: &proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let to_string = quote! { #alloc::string::ToString::to_string };
+
+    if is_option(ty) {
+        quote! { #binding.as_ref().map(#to_string) }
+    } else {
+        quote! { Some(#to_string(#binding)) }
+    }
+}
+
+fn is_option(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Option"),
+        _ => false,
+    }
+}
+
+/// A var is in an optional segment exactly when its field is an `Option`, and then it can only be
+/// used once
+fn validate_optional(
+    segments: &[Segment],
+    variant: &syn::Variant,
+    attribute: &Attribute,
+) -> syn::Result<()> {
+    let positions = var_positions(segments, false);
+
+    for (_, _, var, optional) in &positions {
+        let field = variant
+            .fields
+            .iter()
+            .enumerate()
+            .find(|(i, field)| match &field.ident {
+                Some(ident) => ident == var,
+                None => i.to_string() == *var,
+            });
+
+        // Unknown vars are reported by the other checks
+        let Some((_, field)) = field else {
+            continue;
         };
+
+        if *optional != is_option(&field.ty) {
+            let message = if *optional {
+                format!(
+                    "`{}` is in an optional segment, so it must be an `Option`",
+                    var
+                )
+            } else {
+                format!(
+                    "`{}` is an `Option`, so it must be in an optional segment",
+                    var
+                )
+            };
+
+            return Err(syn::Error::new_spanned(attribute, message));
+        }
+
+        if *optional && positions.iter().filter(|(_, _, v, _)| v == var).count() > 1 {
+            return Err(syn::Error::new_spanned(
+                attribute,
+                format!(
+                    "`{}` is in an optional segment, so it can only be used once",
+                    var
+                ),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_fallback(
+    variant: &syn::Variant,
+    attribute_index: usize,
+    has_fallback: bool,
+    attribute: &Attribute,
+) -> syn::Result<()> {
+    if has_fallback {
+        return Err(syn::Error::new_spanned(
+            attribute,
+            "only one variant can be the `fallback`",
+        ));
+    }
+
+    if attribute_index > 0
+        || variant
+            .attrs
+            .iter()
+            .filter(|a| a.path().is_ident("str_pattern"))
+            .count()
+            > 1
+    {
+        return Err(syn::Error::new_spanned(
+            attribute,
+            "the `fallback` variant cannot have other patterns",
+        ));
+    }
+
+    match &variant.fields {
+        syn::Fields::Unnamed(fields) if fields.unnamed.len() == 1 => Ok(()),
+        _ => Err(syn::Error::new_spanned(
+            variant,
+            "the `fallback` variant must have exactly one unnamed field",
+        )),
+    }
+}
+
+/// Lets `.` in a pattern's regex match newlines, for `dotall`
+fn with_dotall(regex: String, dotall: bool) -> String {
+    if dotall {
+        format!("(?s){}", regex)
+    } else {
+        regex
+    }
+}
+
+/// Makes a pattern's regex ignore casing, for `case_insensitive`
+fn with_case(regex: String, case_insensitive: bool) -> String {
+    if case_insensitive {
+        format!("(?i){}", regex)
+    } else {
+        regex
+    }
+}
+
+/// Builds the variant from the named groups of a raw `regex` pattern, which must cover every field
+fn regex_value(
+    variant: &syn::Variant,
+    path: &proc_macro2::TokenStream,
+    regex: &syn::LitStr,
+    krate: &syn::Path,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let compiled = regex::Regex::new(&regex.value())
+        .map_err(|err| syn::Error::new_spanned(regex, format!("invalid regex: {}", err)))?;
+
+    let groups = compiled.capture_names().flatten().collect::<Vec<_>>();
+
+    let group = |name: String| {
+        if groups.contains(&name.as_str()) {
+            Ok(syn::LitStr::new(&name, regex.span()))
+        } else {
+            Err(syn::Error::new_spanned(
+                regex,
+                format!("regex has no `(?<{}>...)` group for field `{}`", name, name),
+            ))
+        }
+    };
+
+    match &variant.fields {
+        syn::Fields::Unit => Ok(quote! { Some(#path) }),
+        syn::Fields::Unnamed(fields) => {
+            let values = fields
+                .unnamed
+                .iter()
+                .enumerate()
+                .map(|(i, field)| field_value(&group(format!("_{}", i))?, field, krate))
+                .collect::<syn::Result<Vec<_>>>()?;
+
+            Ok(quote! { Some(#path(#(#values),*)) })
+        }
+        syn::Fields::Named(fields) => {
+            let field_idents = fields
+                .named
+                .iter()
+                .filter_map(|f| f.ident.as_ref())
+                .collect::<Vec<&Ident>>();
+
+            let values = fields
+                .named
+                .iter()
+                .map(|f| field_value(&group(f.ident.as_ref().unwrap().to_string())?, f, krate))
+                .collect::<syn::Result<Vec<_>>>()?;
+
+            Ok(quote! {
+                Some(#path {
+                    #(#field_idents: #values),*
+                })
+            })
+        }
+    }
+}
+
+/// Two patterns with the same skeleton match the same messages, so the one tried second could
+/// never win. That's only allowed when they belong to different dialects
+fn validate_overlap(patterns: &[Pattern]) -> syn::Result<()> {
+    for (i, pattern) in patterns.iter().enumerate() {
+        let dialect = |p: &Pattern| p.dialect.as_ref().map(|d| quote!(#d).to_string());
+
+        let shadowed_by = patterns[..i].iter().find(|earlier| {
+            earlier.skeleton == pattern.skeleton
+                && match (dialect(earlier), dialect(pattern)) {
+                    (Some(a), Some(b)) => a == b,
+                    _ => true,
+                }
+        });
+
+        if shadowed_by.is_some() {
+            return Err(syn::Error::new_spanned(
+                pattern.attribute,
+                "pattern matches exactly the same messages as an earlier one, so it would never \
+                 be used",
+            ));
+        }
     }
 
-    Ok((
-        match_arms.into_iter().collect::<proc_macro2::TokenStream>(),
-        regexes.into_iter().collect::<proc_macro2::TokenStream>(),
-    ))
+    Ok(())
 }
 
-fn strip_brackets<'a>(m: Match<'a>) -> &'a str {
-    m.as_str().split_at(2).1.split_at(m.as_str().len() - 4).0
+fn validate_captures(
+    captures: &[(String, Capture, proc_macro2::Span)],
+    default: Option<&Capture>,
+    segments: &[Segment],
+) -> syn::Result<HashMap<String, Capture>> {
+    let positions = var_positions(segments, false);
+
+    // Whether every occurrence of a var is wrapped in a delimiter
+    let delimited = |var: &str| {
+        positions
+            .iter()
+            .filter(|(_, _, v, _)| *v == var)
+            .all(|(segments, i, _, _)| delimiter(segments, *i).is_some())
+    };
+
+    let mut result = HashMap::new();
+
+    for (var, capture, span) in captures {
+        if !positions.iter().any(|(_, _, v, _)| v == var) {
+            return Err(syn::Error::new(
+                *span,
+                format!("`{}` is not a var of this template", var),
+            ));
+        }
+
+        if matches!(capture, Capture::Bounded) && !delimited(var) {
+            return Err(syn::Error::new(
+                *span,
+                format!(
+                    "`{}` can only be bounded when it's wrapped in the same character on both \
+                     sides, like '{{{}}}'",
+                    var, var
+                ),
+            ));
+        }
+
+        result.insert(var.clone(), capture.clone());
+    }
+
+    // A bounded default only applies to the vars that are wrapped in a delimiter everywhere, the
+    // others stay greedy
+    if let Some(default) = default {
+        for (_, _, var, _) in &positions {
+            if !matches!(default, Capture::Bounded) || delimited(var) {
+                result
+                    .entry(var.to_string())
+                    .or_insert_with(|| default.clone());
+            }
+        }
+    }
+
+    Ok(result)
 }
 
-fn validate_unit(captures: &Vec<Option<&str>>, attribute: &Attribute) -> syn::Result<()> {
-    if captures.len() != 0 {
+fn validate_unit(captures: &[&str], attribute: &Attribute) -> syn::Result<()> {
+    if !captures.is_empty() {
         return Err(syn::Error::new_spanned(
             attribute,
             format!(
                 "unit variant cannot have template vars. Remove {}",
-                captures
-                    .iter()
-                    .map(|c| c.unwrap())
-                    .collect::<Vec<_>>()
-                    .join(", ")
+                captures.join(", ")
             ),
         ));
     }
@@ -194,11 +2076,15 @@ fn validate_unit(captures: &Vec<Option<&str>>, attribute: &Attribute) -> syn::Re
 }
 
 fn validate_unnamed(
-    captures: &Vec<Option<&str>>,
+    captures: &[&str],
     attribute: &Attribute,
     fields: &FieldsUnnamed,
 ) -> syn::Result<()> {
-    let matched = captures.len();
+    let mut unique = captures.to_vec();
+    unique.sort_unstable();
+    unique.dedup();
+
+    let matched = unique.len();
 
     if matched != fields.unnamed.len() {
         return Err(syn::Error::new_spanned(
@@ -211,10 +2097,7 @@ fn validate_unnamed(
         ));
     }
 
-    if captures
-        .iter()
-        .any(|c| c.is_none() || c.unwrap().is_empty())
-    {
+    if captures.iter().any(|c| c.is_empty()) {
         return Err(syn::Error::new_spanned(
             attribute,
             "template vars cannot be empty",
@@ -223,7 +2106,7 @@ fn validate_unnamed(
 
     let indices_result = captures
         .iter()
-        .map(|c| str::parse::<usize>(c.unwrap()))
+        .map(|c| str::parse::<usize>(c))
         .collect::<Vec<_>>();
 
     for index in indices_result.into_iter() {
@@ -253,7 +2136,7 @@ fn validate_unnamed(
 }
 
 fn validate_named(
-    captures: &Vec<Option<&str>>,
+    captures: &[&str],
     attribute: &Attribute,
     fields: &FieldsNamed,
 ) -> syn::Result<()> {
@@ -262,25 +2145,24 @@ fn validate_named(
         .iter()
         .filter(|f| {
             let ident = f.ident.as_ref().unwrap().to_string();
-            !captures.iter().flatten().any(|c| *c == ident)
+            !captures.iter().any(|c| *c == ident)
         })
         .map(|f| {
             format!(
                 "named variant is missing template variable for field: `{}`",
-                f.ident.as_ref().unwrap().to_string()
+                f.ident.as_ref().unwrap()
             )
         })
         .collect::<Vec<_>>();
 
     let invalid = captures
         .iter()
-        .flatten()
         .filter(|c| {
             let ident = c.to_string();
             !fields
                 .named
                 .iter()
-                .any(|f| f.ident.as_ref().unwrap().to_string() == ident)
+                .any(|f| *f.ident.as_ref().unwrap() == ident)
         })
         .map(|f| format!("unknown field name: `{}`", f))
         .collect::<Vec<_>>();
@@ -0,0 +1,44 @@
+// Derives `no_std` types in a crate without `std`, so generated code that names it doesn't build.
+// Run with `--no-default-features` to link the library without `std` as well
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::string::{String, ToString};
+use surreal_error_parser::StrPattern;
+
+#[derive(StrPattern, Debug, PartialEq)]
+#[str_pattern(no_std, kind = NoStdKind, from_str = ParseNoStd)]
+enum NoStd {
+    #[str_pattern("Conditional clause is not truthy")]
+    Ignore,
+    #[str_pattern("The table '{0}' does not exist")]
+    TbNotFound(String),
+    #[str_pattern("Found {value} for field `{field}`, but expected a {kind}", value = lazy)]
+    FieldCheck {
+        value: String,
+        field: String,
+        kind: String,
+    },
+    #[str_pattern(fallback)]
+    Unknown(String),
+}
+
+#[derive(StrPattern, Debug, PartialEq)]
+#[str_pattern(no_std)]
+#[str_pattern("Retrying in {0}ms")]
+struct Retry(u64);
+
+#[test]
+fn parses_without_std() {
+    assert_eq!(
+        NoStd::from_string("The table 'product' does not exist"),
+        Some(NoStd::TbNotFound("product".to_string()))
+    );
+    assert_eq!(NoStd::parse("nope"), NoStd::Unknown("nope".to_string()));
+    assert_eq!(NoStd::Ignore.kind(), NoStdKind::Ignore);
+    assert!("nope".parse::<NoStd>().is_err());
+    assert_eq!(Retry::from_string("Retrying in 50ms"), Some(Retry(50)));
+    assert_eq!(Retry(50).to_string(), "Retrying in 50ms");
+}
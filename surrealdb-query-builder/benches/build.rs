@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use surrealdb_query_builder::{
+    filters::{FilterValue, Filters},
+    limit::{Limit, Offset},
+    operator::Operator,
+    order_dir::OrderDir,
+    query_options::QueryOptions,
+};
+
+const OPERATORS: [Operator; 6] = [
+    Operator::Eq,
+    Operator::Ne,
+    Operator::Gt,
+    Operator::Ge,
+    Operator::Lt,
+    Operator::Le,
+];
+
+fn filters(count: usize) -> Filters {
+    Filters(
+        (0..count)
+            .map(|i| {
+                let value: FilterValue = match i % 3 {
+                    0 => format!("value {}", i).into(),
+                    1 => (i as i64).into(),
+                    _ => vec!["tag1", "tag2"].into(),
+                };
+
+                let operator = match value {
+                    FilterValue::EscapedList(_) => Operator::ContainsAny,
+                    _ => OPERATORS[i % OPERATORS.len()].clone(),
+                };
+
+                // Repeat keys so the `__N` variable suffixing is exercised too
+                (format!("field_{}", i % 10).into(), (operator, value))
+            })
+            .collect(),
+    )
+}
+
+fn options(filter_count: usize) -> QueryOptions<'static> {
+    QueryOptions {
+        filters: filters(filter_count),
+        expansions: &[],
+        limit: Limit::new(10),
+        offset: Offset::new(0),
+        order_by: Some("id"),
+        order_dir: Some(OrderDir::Asc),
+        extra_binds: HashMap::new(),
+    }
+}
+
+fn build(c: &mut Criterion) {
+    let mut group = c.benchmark_group("build");
+
+    for filter_count in [0, 5, 50] {
+        group.bench_function(format!("{}_filters", filter_count), |b| {
+            b.iter_batched(
+                || options(filter_count),
+                |opts| opts.build(black_box("user"), black_box(&["id", "name"])),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+fn build_nested_expansions(c: &mut Criterion) {
+    c.bench_function("build/nested_expansions", |b| {
+        b.iter(|| {
+            let line_items = QueryOptions {
+                filters: Filters(Box::from([(
+                    "order".into(),
+                    (Operator::Eq, FilterValue::Unsafe("$parent.id".into())),
+                )])),
+                ..QueryOptions::new()
+            }
+            .build("line_item", &["*"]);
+
+            let orders = QueryOptions {
+                filters: Filters(Box::from([(
+                    "user".into(),
+                    (Operator::Eq, FilterValue::Unsafe("$parent.id".into())),
+                )])),
+                expansions: &[("line_items", line_items.0.as_ref())],
+                ..QueryOptions::new()
+            }
+            .build("order", &["*"]);
+
+            QueryOptions {
+                filters: filters(5),
+                expansions: &[
+                    ("purchases", "->purchased.out"),
+                    ("orders", orders.0.as_ref()),
+                ],
+                ..options(0)
+            }
+            .build(black_box("user"), black_box(&["id", "name"]))
+        })
+    });
+}
+
+criterion_group!(benches, build, build_nested_expansions);
+criterion_main!(benches);
@@ -0,0 +1,50 @@
+//! Reads query options as JSON from stdin and prints the query they build and its binds. With
+//! `--check`, the query is also run through the SurrealQL parser
+//!
+//! ```sh
+//! echo '{"table":"user","filters":[{"key":"name","op":"=","value":"tester"}]}' | preview-query
+//! ```
+
+use std::{io::Read, process::ExitCode};
+
+use surrealdb_query_builder::preview::PreviewRequest;
+
+fn main() -> ExitCode {
+    let check = std::env::args().skip(1).any(|arg| arg == "--check");
+
+    let mut input = String::new();
+
+    if let Err(error) = std::io::stdin().read_to_string(&mut input) {
+        eprintln!("error: couldn't read stdin: {}", error);
+        return ExitCode::FAILURE;
+    }
+
+    let request: PreviewRequest = match serde_json::from_str(&input) {
+        Ok(request) => request,
+        Err(error) => {
+            eprintln!("error: invalid query options: {}", error);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let ((query, variables), warnings) = request.build();
+
+    for warning in &warnings {
+        eprintln!("warning: {}", warning);
+    }
+
+    println!("{}", query);
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&variables).expect("binds are plain values")
+    );
+
+    if check {
+        if let Err(error) = surrealdb::sql::parse(&query) {
+            eprintln!("error: {}", error);
+            return ExitCode::FAILURE;
+        }
+    }
+
+    ExitCode::SUCCESS
+}
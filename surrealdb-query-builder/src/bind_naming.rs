@@ -0,0 +1,93 @@
+use std::{collections::HashMap, fmt::Display};
+
+use crate::filters::FilterValue;
+
+/// What happens when a bind's name is already taken, or reserved
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CollisionPolicy {
+    /// Take the next free suffix
+    Suffix,
+    /// Fail the build
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BindCollision(pub Box<str>);
+
+impl Display for BindCollision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "the bind name {:?} is already taken", self.0)
+    }
+}
+
+impl std::error::Error for BindCollision {}
+
+/// How the names of binds are derived from filter keys. The default names a bind after its key,
+/// like `tag_name` for `tag.name`, and the second filter on the same key `tag_name__1`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BindNaming {
+    /// Put in front of every name, like `qb_`, so they can't collide with `LET` params
+    pub prefix: Box<str>,
+    /// Put between a name and its number
+    pub separator: Box<str>,
+    /// Names are cut to this many characters, not counting the numbered suffix
+    pub max_len: Option<usize>,
+    /// Names that are never generated, like the params of the surrounding query
+    pub reserved: Box<[Box<str>]>,
+    pub on_collision: CollisionPolicy,
+}
+
+impl Default for BindNaming {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BindNaming {
+    pub fn new() -> Self {
+        Self {
+            prefix: "".into(),
+            separator: "__".into(),
+            max_len: None,
+            reserved: Box::default(),
+            on_collision: CollisionPolicy::Suffix,
+        }
+    }
+
+    /// The name of the `n`th bind for `ident`, without checking it's free
+    pub fn name(&self, ident: &str, n: usize) -> Box<str> {
+        let mut name = format!("{}{}", self.prefix, ident);
+
+        if let Some(max_len) = self.max_len {
+            name = name.chars().take(max_len).collect();
+        }
+
+        if n > 0 {
+            name = format!("{}{}{}", name, self.separator, n);
+        }
+
+        name.into_boxed_str()
+    }
+
+    /// The name of the `n`th bind for `ident`, or the first free one after it
+    pub(crate) fn assign(
+        &self,
+        ident: &str,
+        mut n: usize,
+        taken: &HashMap<Box<str>, FilterValue>,
+    ) -> Result<Box<str>, BindCollision> {
+        loop {
+            let name = self.name(ident, n);
+
+            if !taken.contains_key(&name) && !self.reserved.contains(&name) {
+                return Ok(name);
+            }
+
+            if self.on_collision == CollisionPolicy::Error {
+                return Err(BindCollision(name));
+            }
+
+            n += 1;
+        }
+    }
+}
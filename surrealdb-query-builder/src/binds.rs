@@ -0,0 +1,62 @@
+use std::{collections::HashMap, fmt::Display};
+
+use rust_decimal::Decimal;
+use surrealdb::sql::{Array, Number, Object, Value};
+
+use crate::filters::{FilterValue, FilterValueKind};
+
+impl From<FilterValueKind> for Value {
+    fn from(value: FilterValueKind) -> Self {
+        match value {
+            FilterValueKind::String(value) => Value::from(String::from(value)),
+            FilterValueKind::Int(value) => Value::Number(Number::Int(value)),
+            // Integers only go up to `i64::MAX`, bigger ones keep their exact value as decimals
+            FilterValueKind::UInt(value) => match i64::try_from(value) {
+                Ok(value) => Value::Number(Number::Int(value)),
+                Err(_) => Value::Number(Number::Decimal(Decimal::from(value))),
+            },
+            FilterValueKind::Float(value) => Value::Number(Number::Float(value)),
+            FilterValueKind::Decimal(value) => Value::Number(Number::Decimal(value)),
+            FilterValueKind::Bool(value) => Value::Bool(value),
+            FilterValueKind::Value(value) => value,
+        }
+    }
+}
+
+/// A filter value that's written into the query rather than bound, like
+/// [`FilterValue::Unsafe`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unbindable(pub FilterValue);
+
+impl Display for Unbindable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} is written into the query, not bound", self.0)
+    }
+}
+
+impl std::error::Error for Unbindable {}
+
+impl TryFrom<FilterValue> for Value {
+    type Error = Unbindable;
+
+    fn try_from(value: FilterValue) -> Result<Self, Self::Error> {
+        match value {
+            FilterValue::Escaped(value) => Ok(value.into()),
+            FilterValue::EscapedList(values) => Ok(Value::Array(Array(
+                values.into_vec().into_iter().map(Value::from).collect(),
+            ))),
+            value => Err(Unbindable(value)),
+        }
+    }
+}
+
+/// Turns the variables of a built query into the SDK's own types, so they're bound exactly as
+/// typed instead of going through serde. Only bindable values ever end up in the variables
+pub fn sql_binds(variables: HashMap<Box<str>, FilterValue>) -> Object {
+    Object(
+        variables
+            .into_iter()
+            .filter_map(|(name, value)| Some((name.into(), Value::try_from(value).ok()?)))
+            .collect(),
+    )
+}
@@ -0,0 +1,97 @@
+use std::{fmt::Display, time::Duration};
+
+use regex::Regex;
+use surrealdb::sql::Value;
+
+use crate::{
+    bind_naming::BindNaming,
+    group::Aggregate,
+    query_options::{QueryOptions, SelectQuery},
+};
+
+/// Rows aggregated per fixed window of a datetime field, like orders per day
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeBuckets<'a> {
+    /// The datetime field floored into buckets
+    pub field: &'a str,
+    pub size: Duration,
+    /// Projected per bucket with their aliases, next to the bucket's start as `bucket`
+    pub aggregates: &'a [(Aggregate<'a>, &'a str)],
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimeBucketError {
+    InvalidField(Box<str>),
+    ZeroSize,
+    /// The alias of an aggregate whose field or alias isn't a plain identifier
+    InvalidAggregate(Box<str>),
+}
+
+impl Display for TimeBucketError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimeBucketError::InvalidField(field) => write!(f, "{:?} is not a field", field),
+            TimeBucketError::ZeroSize => write!(f, "buckets can't be empty"),
+            TimeBucketError::InvalidAggregate(alias) => {
+                write!(f, "aggregate {:?} can't be projected", alias)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TimeBucketError {}
+
+impl<'a> QueryOptions<'a> {
+    /// Builds a `SELECT` grouped by `time::floor(field, $bucket)`, with the size bound as
+    /// `$bucket`, or `$bucket__1` and so on when `extra_binds` already has one. Buckets are ordered oldest first unless `order_by` says otherwise. Pass a
+    /// [`RecordRange`](crate::range::RecordRange) as the target for time-prefixed ids
+    pub fn build_time_buckets(
+        mut self,
+        target: &str,
+        buckets: &TimeBuckets,
+    ) -> Result<SelectQuery, TimeBucketError> {
+        let field_regex = Regex::new(r"^\w+(\.\w+)*$").unwrap();
+
+        if !field_regex.is_match(buckets.field) {
+            return Err(TimeBucketError::InvalidField(buckets.field.into()));
+        }
+
+        if buckets.size.is_zero() {
+            return Err(TimeBucketError::ZeroSize);
+        }
+
+        // Named around the caller's own binds, which could already have a `bucket`
+        let bind = BindNaming::default()
+            .assign("bucket", 0, &self.extra_binds)
+            .expect("suffixing never collides");
+
+        let mut columns = vec![format!(
+            "time::floor({}, ${}) AS bucket",
+            buckets.field, bind
+        )];
+
+        for (aggregate, alias) in buckets.aggregates {
+            columns.push(
+                aggregate
+                    .projection(alias)
+                    .ok_or_else(|| TimeBucketError::InvalidAggregate((*alias).into()))?
+                    .into(),
+            );
+        }
+
+        self.extra_binds
+            .insert(bind, Value::Duration(buckets.size.into()).into());
+        self.order_by = self.order_by.or(Some("bucket"));
+
+        let columns = columns.iter().map(String::as_str).collect::<Vec<_>>();
+
+        Ok(self
+            .build_select(
+                target,
+                &columns,
+                Some("GROUP BY bucket"),
+                &BindNaming::default(),
+            )
+            .expect("suffixing never collides"))
+    }
+}
@@ -0,0 +1,317 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+use surrealdb::{
+    sql::{from_value, FromValueError, Value},
+    Connection, Surreal,
+};
+
+use crate::{
+    classify::{classify, QueryKind},
+    tenant::{Database, Namespace},
+};
+
+/// Every part length-prefixed, so parts can't run into each other
+fn canonical<'b>(parts: impl IntoIterator<Item = &'b [u8]>) -> Vec<u8> {
+    let mut bytes = vec![];
+
+    for part in parts {
+        bytes.extend((part.len() as u64).to_le_bytes());
+        bytes.extend(part);
+    }
+
+    bytes
+}
+
+/// 64-bit FNV-1a, which unlike `DefaultHasher` gives the same hash across builds and Rust
+/// versions
+fn stable_hash(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+
+    hash
+}
+
+fn query_parts<V: Serialize>(query: &str, variables: &HashMap<Box<str>, V>) -> Vec<Vec<u8>> {
+    let mut parts = vec![query.as_bytes().to_vec()];
+
+    for (name, value) in variables.iter().collect::<BTreeMap<_, _>>() {
+        parts.push(name.as_bytes().to_vec());
+        // A bind that can't be serialized can't be sent either, so it never reaches the store
+        parts.push(serde_json::to_vec(value).unwrap_or_default());
+    }
+
+    parts
+}
+
+/// A short, stable hash of a built query together with its binds, for logs and metrics. Binds are
+/// hashed in name order, so the order they were inserted in doesn't matter. Different queries
+/// can share one, so look results up by [`CacheKey`] instead
+pub fn fingerprint<V: Serialize>(query: &str, variables: &HashMap<Box<str>, V>) -> u64 {
+    stable_hash(&CacheKey::query(query, variables).0)
+}
+
+/// A built query with its binds, and the session it runs in when it's from
+/// [`CacheSession::key`]. Holds all of them rather than a hash, so two keys are only equal for
+/// the same query, binds and session
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey(Box<[u8]>);
+
+impl CacheKey {
+    /// The key of a query regardless of the session, for results that are never shared
+    /// between sessions
+    pub fn query<V: Serialize>(query: &str, variables: &HashMap<Box<str>, V>) -> Self {
+        let parts = query_parts(query, variables);
+
+        Self(canonical(parts.iter().map(Vec::as_slice)).into_boxed_slice())
+    }
+
+    /// The key's bytes, the same across builds, for stores outside the process
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+/// Whose results are cached: the same query returns different rows in another namespace or
+/// database, or for another signed in user
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheSession {
+    pub namespace: Namespace,
+    pub database: Database,
+    /// The signed in user, like their record id, or `None` for a system connection
+    pub identity: Option<Box<str>>,
+}
+
+impl CacheSession {
+    pub fn new(namespace: Namespace, database: Database) -> Self {
+        Self {
+            namespace,
+            database,
+            identity: None,
+        }
+    }
+
+    pub fn with_identity(mut self, identity: &str) -> Self {
+        self.identity = Some(identity.into());
+        self
+    }
+
+    /// The store key of a query run in this session
+    pub fn key<V: Serialize>(&self, query: &str, variables: &HashMap<Box<str>, V>) -> CacheKey {
+        let namespace = self.namespace.to_string();
+        let database = self.database.to_string();
+        // Tells a missing identity apart from an empty one
+        let identity = match &self.identity {
+            Some(identity) => [b"1", identity.as_bytes()].concat(),
+            None => b"0".to_vec(),
+        };
+
+        let session = [namespace.as_bytes(), database.as_bytes(), &identity];
+        let parts = query_parts(query, variables);
+
+        let bytes = canonical(session.into_iter().chain(parts.iter().map(Vec::as_slice)));
+
+        CacheKey(bytes.into_boxed_slice())
+    }
+}
+
+/// Where a [`CachingExecutor`] keeps results. Takes `&self` so a store can be shared between
+/// executors, whose keys tell their sessions apart. A store that looks entries up by a hash of
+/// the key has to keep the whole key with the entry and compare it on `get`
+pub trait CacheStore {
+    fn get(&self, key: &CacheKey) -> Option<Value>;
+    /// Stores the results of a query on `table` for `ttl`
+    fn set(&self, key: CacheKey, table: &str, value: Value, ttl: Duration);
+    /// Drops every entry stored for `table`
+    fn invalidate(&self, table: &str);
+}
+
+struct Entry {
+    table: Box<str>,
+    value: Value,
+    expires_at: Instant,
+}
+
+/// An in-process [`CacheStore`]. Expired entries are dropped whenever an entry is stored, so it
+/// only grows with the entries that are still live
+#[derive(Default)]
+pub struct MemoryStore {
+    entries: Mutex<HashMap<CacheKey, Entry>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How many entries are held, including expired ones that weren't dropped yet
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl CacheStore for MemoryStore {
+    fn get(&self, key: &CacheKey) -> Option<Value> {
+        let mut entries = self.entries.lock().unwrap();
+
+        match entries.get(key) {
+            Some(entry) if entry.expires_at > Instant::now() => Some(entry.value.clone()),
+            Some(_) => {
+                entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn set(&self, key: CacheKey, table: &str, value: Value, ttl: Duration) {
+        let mut entries = self.entries.lock().unwrap();
+        let now = Instant::now();
+
+        entries.retain(|_, entry| entry.expires_at > now);
+        entries.insert(
+            key,
+            Entry {
+                table: table.into(),
+                value,
+                expires_at: now + ttl,
+            },
+        );
+    }
+
+    fn invalidate(&self, table: &str) {
+        self.entries
+            .lock()
+            .unwrap()
+            .retain(|_, entry| &*entry.table != table);
+    }
+}
+
+#[derive(Debug)]
+pub enum CacheError {
+    Db(surrealdb::Error),
+    Deserialize(FromValueError),
+}
+
+impl Display for CacheError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CacheError::Db(error) => error.fmt(f),
+            CacheError::Deserialize(error) => write!(f, "{}", error.error),
+        }
+    }
+}
+
+impl std::error::Error for CacheError {}
+
+impl From<surrealdb::Error> for CacheError {
+    fn from(error: surrealdb::Error) -> Self {
+        CacheError::Db(error)
+    }
+}
+
+impl From<FromValueError> for CacheError {
+    fn from(error: FromValueError) -> Self {
+        CacheError::Deserialize(error)
+    }
+}
+
+/// Runs built queries, caching the results of reads for `ttl` and invalidating a table's
+/// entries whenever a write on it runs through the executor
+pub struct CachingExecutor<'a, C: Connection, S: CacheStore> {
+    db: &'a Surreal<C>,
+    session: CacheSession,
+    store: S,
+    ttl: Duration,
+}
+
+impl<'a, C: Connection, S: CacheStore> CachingExecutor<'a, C, S> {
+    /// `session` has to match the namespace, database and user `db` is signed in as, since
+    /// results are only shared between executors of the same session
+    pub fn new(db: &'a Surreal<C>, session: CacheSession, store: S, ttl: Duration) -> Self {
+        Self {
+            db,
+            session,
+            store,
+            ttl,
+        }
+    }
+
+    pub fn session(&self) -> &CacheSession {
+        &self.session
+    }
+
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Runs `query`, which reads or writes `table`, and returns the result of its first
+    /// statement
+    pub async fn execute<T: DeserializeOwned, V: Serialize>(
+        &self,
+        table: &str,
+        query: &(Box<str>, HashMap<Box<str>, V>),
+    ) -> Result<T, CacheError> {
+        let future = self.run(table, query);
+
+        #[cfg(feature = "otel")]
+        let future =
+            tracing::Instrument::instrument(future, crate::otel::span(Some(table), &query.0));
+
+        future.await
+    }
+
+    async fn run<T: DeserializeOwned, V: Serialize>(
+        &self,
+        table: &str,
+        query: &(Box<str>, HashMap<Box<str>, V>),
+    ) -> Result<T, CacheError> {
+        let (query, variables) = query;
+
+        if classify(query) == QueryKind::Write {
+            let value: Value = self
+                .db
+                .query(query.as_ref())
+                .bind(variables)
+                .await?
+                .take(0)?;
+
+            self.store.invalidate(table);
+
+            return Ok(from_value(value)?);
+        }
+
+        let key = self.session.key(query, variables);
+
+        let value = match self.store.get(&key) {
+            Some(value) => value,
+            None => {
+                let value: Value = self
+                    .db
+                    .query(query.as_ref())
+                    .bind(variables)
+                    .await?
+                    .take(0)?;
+
+                self.store.set(key, table, value.clone(), self.ttl);
+
+                value
+            }
+        };
+
+        Ok(from_value(value)?)
+    }
+}
@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+
+use futures::{stream, Stream, TryStreamExt};
+use serde::de::DeserializeOwned;
+use surrealdb::{
+    sql::{from_value, Value},
+    Connection, Surreal,
+};
+
+use crate::{
+    filters::{FilterValue, Filters},
+    limit::Limit,
+    query_options::QueryOptions,
+};
+
+/// Runs a query a chunk at a time, continuing after the last id of every chunk. Made by
+/// [`QueryOptions::chunks`]
+pub struct Chunks<'a, C: Connection> {
+    db: &'a Surreal<C>,
+    first: Box<str>,
+    /// The same query, but only after `$chunk_after`
+    next: Box<str>,
+    variables: HashMap<Box<str>, FilterValue>,
+    chunk_size: usize,
+    started: bool,
+    done: bool,
+}
+
+impl<'a, C: Connection> Chunks<'a, C> {
+    /// Runs the query for the next chunk. Returns `None` once a chunk comes back short, so a
+    /// table of exactly `chunk_size` rows takes one extra, empty query
+    pub async fn next_chunk<T: DeserializeOwned>(&mut self) -> surrealdb::Result<Option<Vec<T>>> {
+        if self.done {
+            return Ok(None);
+        }
+
+        let query = if self.started {
+            &self.next
+        } else {
+            &self.first
+        };
+
+        let rows: Value = self
+            .db
+            .query(query.as_ref())
+            .bind(self.variables.clone())
+            .await?
+            .take(0)?;
+
+        let Value::Array(rows) = rows else {
+            return Ok(None);
+        };
+
+        self.started = true;
+        self.done = rows.len() < self.chunk_size;
+
+        if let Some(Value::Object(last)) = rows.last() {
+            let id = last.get("id").cloned().unwrap_or_default();
+
+            self.variables.insert("chunk_after".into(), id.into());
+        }
+
+        if rows.is_empty() {
+            return Ok(None);
+        }
+
+        let rows = rows
+            .0
+            .into_iter()
+            .map(from_value)
+            .collect::<Result<_, _>>()?;
+
+        Ok(Some(rows))
+    }
+
+    /// The remaining rows one at a time, fetched a chunk at a time
+    pub fn into_stream<T: DeserializeOwned + 'a>(
+        self,
+    ) -> impl Stream<Item = surrealdb::Result<T>> + 'a
+    where
+        C: 'a,
+    {
+        stream::try_unfold(self, |mut chunks| async move {
+            let rows = chunks.next_chunk::<T>().await?;
+
+            surrealdb::Result::Ok(rows.map(|rows| {
+                (
+                    stream::iter(rows.into_iter().map(surrealdb::Result::Ok)),
+                    chunks,
+                )
+            }))
+        })
+        .try_flatten()
+    }
+}
+
+impl<'a> QueryOptions<'a> {
+    /// Iterates the results of `build` in chunks of `chunk_size`, for exports too big to load at
+    /// once. Chunks are ordered by `id` and each one continues after the last id of the one
+    /// before, so they don't overlap or skip rows, even when rows are written in between. Its
+    /// own limit, offset and order are ignored, and `id` is always selected
+    pub fn chunks<'db, C: Connection>(
+        self,
+        db: &'db Surreal<C>,
+        table_name: &str,
+        unsafe_columns: &[&str],
+        chunk_size: usize,
+    ) -> Chunks<'db, C> {
+        let chunk_size = chunk_size.max(1);
+
+        let mut columns = unsafe_columns.to_vec();
+
+        if !columns
+            .iter()
+            .any(|column| matches!(column.trim(), "*" | "id"))
+        {
+            columns.push("id");
+        }
+
+        let mut extra_binds = self.extra_binds;
+        extra_binds.insert("chunk_after".into(), Value::None.into());
+
+        let options =
+            |filters: Filters, extra_binds: HashMap<Box<str>, FilterValue>| QueryOptions {
+                filters,
+                expansions: self.expansions,
+                limit: Limit::new(chunk_size),
+                offset: None,
+                order_by: Some("id"),
+                order_dir: None,
+                extra_binds,
+            };
+
+        let (first, _) =
+            options(self.filters.clone(), extra_binds.clone()).build(table_name, &columns);
+
+        let mut filters = self.filters.0.into_vec();
+        filters.push(
+            Filters::raw("id > $chunk_after", Vec::<(&str, FilterValue)>::new())
+                .expect("there are no binds to check"),
+        );
+
+        let (next, variables) =
+            options(Filters(filters.into_boxed_slice()), extra_binds).build(table_name, &columns);
+
+        Chunks {
+            db,
+            first,
+            next,
+            variables,
+            chunk_size,
+            started: false,
+            done: false,
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use std::collections::HashMap;
+
+use regex::Regex;
+
+/// Whether a query only reads, so it can go to a replica, or has to go to the primary
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QueryKind {
+    Read,
+    Write,
+}
+
+/// Classifies a query by its statements and subqueries. Anything that could change data or the
+/// schema counts as a write, including a field that happens to be named like a statement, since
+/// sending a read to the primary is only slower while the opposite fails
+pub fn classify(query: &str) -> QueryKind {
+    let strings = Regex::new(r#""(\\.|[^"\\])*"|'(\\.|[^'\\])*'|⟨[^⟩]*⟩|`[^`]*`"#).unwrap();
+    let writes = Regex::new(
+        r"(?i)(^|[^\w$:.])(CREATE|UPDATE|UPSERT|DELETE|RELATE|INSERT|DEFINE|REMOVE|KILL|LIVE|BEGIN|COMMIT)\b",
+    )
+    .unwrap();
+
+    if writes.is_match(&strings.replace_all(query, "''")) {
+        QueryKind::Write
+    } else {
+        QueryKind::Read
+    }
+}
+
+/// Gives built queries their [`QueryKind`]
+pub trait Classify {
+    fn kind(&self) -> QueryKind;
+}
+
+impl<T> Classify for (Box<str>, HashMap<Box<str>, T>) {
+    fn kind(&self) -> QueryKind {
+        classify(&self.0)
+    }
+}
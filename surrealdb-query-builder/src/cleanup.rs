@@ -0,0 +1,69 @@
+use std::fmt::Display;
+
+use regex::Regex;
+use surrealdb::{Connection, Surreal};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidTable(pub Box<str>);
+
+impl Display for InvalidTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} is not a table name", self.0)
+    }
+}
+
+impl std::error::Error for InvalidTable {}
+
+fn statements(prefix: &str, tables: &[&str]) -> Result<Vec<String>, InvalidTable> {
+    let regex = Regex::new(r"^\w+$").unwrap();
+
+    tables
+        .iter()
+        .map(|table| {
+            regex
+                .is_match(table)
+                .then(|| format!("{} {}", prefix, table))
+                .ok_or_else(|| InvalidTable((*table).into()))
+        })
+        .collect()
+}
+
+/// Builds a `DELETE FROM` per table, which empties them but keeps their definitions
+pub fn build_truncate(tables: &[&str]) -> Result<Box<str>, InvalidTable> {
+    Ok(statements("DELETE FROM", tables)?
+        .join("; ")
+        .into_boxed_str())
+}
+
+/// Builds a `REMOVE TABLE` per table followed by `schema`, which should re-`DEFINE` them, so
+/// fields and indexes changed by a test don't leak into the next one
+pub fn build_recreate(tables: &[&str], schema: &str) -> Result<Box<str>, InvalidTable> {
+    let mut statements = statements("REMOVE TABLE", tables)?;
+
+    statements.push(schema.to_string());
+
+    Ok(statements.join("; ").into_boxed_str())
+}
+
+/// Empties the tables, for resetting state between integration tests
+pub async fn truncate<C: Connection>(
+    db: &Surreal<C>,
+    tables: &[&str],
+) -> Result<(), Box<dyn std::error::Error>> {
+    db.query(build_truncate(tables)?.as_ref()).await?.check()?;
+
+    Ok(())
+}
+
+/// Removes the tables and runs `schema` to define them again
+pub async fn recreate<C: Connection>(
+    db: &Surreal<C>,
+    tables: &[&str],
+    schema: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    db.query(build_recreate(tables, schema)?.as_ref())
+        .await?
+        .check()?;
+
+    Ok(())
+}
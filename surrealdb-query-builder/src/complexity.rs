@@ -0,0 +1,138 @@
+use std::fmt::Display;
+
+use regex::Regex;
+
+use crate::{
+    filters::FilterValue,
+    query_options::{QueryOptions, SelectQuery},
+};
+
+/// How complex a query built from client input may get
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ComplexityLimits {
+    pub max_filters: usize,
+    pub max_expansions: usize,
+    /// How deeply parentheses may nest in expansions and raw filters, which bounds subqueries
+    pub max_depth: usize,
+    pub allow_parallel: bool,
+}
+
+impl Default for ComplexityLimits {
+    fn default() -> Self {
+        Self {
+            max_filters: 16,
+            max_expansions: 4,
+            max_depth: 4,
+            allow_parallel: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ComplexityError {
+    TooManyFilters { count: usize, max: usize },
+    TooManyExpansions { count: usize, max: usize },
+    TooDeep { depth: usize, max: usize },
+    Parallel,
+}
+
+impl Display for ComplexityError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ComplexityError::TooManyFilters { count, max } => {
+                write!(f, "{} filters exceeds the maximum of {}", count, max)
+            }
+            ComplexityError::TooManyExpansions { count, max } => {
+                write!(f, "{} expansions exceeds the maximum of {}", count, max)
+            }
+            ComplexityError::TooDeep { depth, max } => {
+                write!(f, "nesting depth {} exceeds the maximum of {}", depth, max)
+            }
+            ComplexityError::Parallel => write!(f, "PARALLEL is not allowed"),
+        }
+    }
+}
+
+impl std::error::Error for ComplexityError {}
+
+impl<'a> QueryOptions<'a> {
+    /// Checks the options against `limits` without building anything
+    pub fn check_complexity(&self, limits: &ComplexityLimits) -> Result<(), ComplexityError> {
+        if self.filters.len() > limits.max_filters {
+            return Err(ComplexityError::TooManyFilters {
+                count: self.filters.len(),
+                max: limits.max_filters,
+            });
+        }
+
+        if self.expansions.len() > limits.max_expansions {
+            return Err(ComplexityError::TooManyExpansions {
+                count: self.expansions.len(),
+                max: limits.max_expansions,
+            });
+        }
+
+        let raw_fragments = self
+            .filters
+            .iter()
+            .filter_map(|(_, (_, value))| match value {
+                FilterValue::Raw(raw) => Some(&*raw.fragment),
+                _ => None,
+            });
+
+        let parallel = Regex::new(r"(?i)\bPARALLEL\b").unwrap();
+
+        for fragment in self
+            .expansions
+            .iter()
+            .map(|(_, expansion)| *expansion)
+            .chain(raw_fragments)
+        {
+            if !limits.allow_parallel && parallel.is_match(fragment) {
+                return Err(ComplexityError::Parallel);
+            }
+
+            // Expansions are wrapped in parentheses when built, so they start one level deep
+            let depth = nesting_depth(fragment) + 1;
+
+            if depth > limits.max_depth {
+                return Err(ComplexityError::TooDeep {
+                    depth,
+                    max: limits.max_depth,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Like [`QueryOptions::build`], but refuses options over `limits`
+    pub fn try_build(
+        self,
+        table_name: &str,
+        unsafe_columns: &[&str],
+        limits: &ComplexityLimits,
+    ) -> Result<SelectQuery, ComplexityError> {
+        self.check_complexity(limits)?;
+
+        Ok(self.build(table_name, unsafe_columns))
+    }
+}
+
+fn nesting_depth(fragment: &str) -> usize {
+    let mut depth = 0usize;
+    let mut max = 0;
+
+    for c in fragment.chars() {
+        match c {
+            '(' | '[' | '{' => {
+                depth += 1;
+                max = max.max(depth);
+            }
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+
+    max
+}
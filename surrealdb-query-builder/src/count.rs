@@ -0,0 +1,65 @@
+use std::collections::HashMap;
+
+use serde::de::DeserializeOwned;
+use surrealdb::Response;
+
+use crate::{
+    bind_naming::BindNaming,
+    filters::{FilterValue, Filters},
+    query_options::QueryOptions,
+};
+
+impl<'a> QueryOptions<'a> {
+    /// Builds `SELECT count() AS total ... GROUP ALL` with the same filters as `build`. Limit,
+    /// offset, order and expansions don't change the count, so they're left out
+    pub fn build_count(self, table_name: &str) -> (Box<str>, HashMap<Box<str>, FilterValue>) {
+        let (filters_query, variables) =
+            QueryOptions::build_filters(self.filters, &BindNaming::default(), self.extra_binds)
+                .expect("suffixing never collides");
+
+        let mut query = format!("SELECT count() AS total FROM {}", table_name);
+
+        if !filters_query.is_empty() {
+            query.push(' ');
+            query.push_str(&filters_query);
+        }
+
+        query.push_str(" GROUP ALL");
+
+        (query.into_boxed_str(), variables)
+    }
+
+    /// Builds the query of `build` followed by its `build_count`, so a page and the total it's
+    /// out of take a single round trip. Unpack the response with [`take_with_count`]
+    pub fn build_with_count(
+        self,
+        table_name: &str,
+        unsafe_columns: &[&str],
+    ) -> (Box<str>, HashMap<Box<str>, FilterValue>) {
+        // Both statements bind the same variables, so only one set is returned
+        let (count_query, _) = QueryOptions {
+            filters: Filters(self.filters.0.clone()),
+            extra_binds: self.extra_binds.clone(),
+            ..QueryOptions::new()
+        }
+        .build_count(table_name);
+
+        let (query, variables) = self.build(table_name, unsafe_columns);
+
+        (
+            format!("{}; {}", query, count_query).into_boxed_str(),
+            variables,
+        )
+    }
+}
+
+/// Takes the results and the total of a query built with `build_with_count`
+pub fn take_with_count<T: DeserializeOwned>(
+    response: &mut Response,
+) -> surrealdb::Result<(Vec<T>, usize)> {
+    let results = response.take(0)?;
+    // A count over no records returns no row at all
+    let total: Option<usize> = response.take((1, "total"))?;
+
+    Ok((results, total.unwrap_or(0)))
+}
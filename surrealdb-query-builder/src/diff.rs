@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use surrealdb::Response;
+
+/// One operation of the JSON Patch returned by `RETURN DIFF`. Paths are JSON pointers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum DiffOp {
+    Add {
+        path: Box<str>,
+        value: Value,
+    },
+    Remove {
+        path: Box<str>,
+    },
+    Replace {
+        path: Box<str>,
+        value: Value,
+    },
+    /// A string changed, with `value` as a text diff of the old and new string
+    Change {
+        path: Box<str>,
+        value: Box<str>,
+    },
+    Copy {
+        path: Box<str>,
+        from: Box<str>,
+    },
+    Move {
+        path: Box<str>,
+        from: Box<str>,
+    },
+    Test {
+        path: Box<str>,
+        value: Value,
+    },
+}
+
+/// The changes a write made to one record
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Diff(pub Vec<DiffOp>);
+
+impl Diff {
+    /// Whether the write left the record as it was
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Takes the diffs of the records changed by the statement at `index`, which has to end in
+/// `RETURN DIFF`
+pub fn take_diffs(response: &mut Response, index: usize) -> surrealdb::Result<Vec<Diff>> {
+    response.take(index)
+}
@@ -0,0 +1,257 @@
+use std::{collections::HashMap, fmt::Display, str::FromStr};
+
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+
+use crate::{
+    filters::{Filter, FilterValue, FilterValueKind, Filters},
+    limit::{Limit, Offset},
+    operator::Operator,
+    order_dir::OrderDir,
+    query_options::QueryOptions,
+};
+
+/// The version written in front of every encoded string. Decoding dispatches on it, so strings of
+/// older versions keep decoding after the format changes
+const VERSION: &str = "1";
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EncodingError {
+    /// Only escaped filters of plain values can be encoded, the others would let whoever holds
+    /// the string write into the query
+    Unencodable(Box<str>),
+    UnknownVersion(Box<str>),
+    Malformed,
+}
+
+impl Display for EncodingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EncodingError::Unencodable(key) => {
+                write!(f, "the filter on {:?} can't be encoded", key)
+            }
+            EncodingError::UnknownVersion(version) => {
+                write!(f, "unknown encoding version {:?}", version)
+            }
+            EncodingError::Malformed => write!(f, "malformed query options"),
+        }
+    }
+}
+
+impl std::error::Error for EncodingError {}
+
+/// Query options decoded from a string, which own what [`QueryOptions`] borrows. Expansions and
+/// extra binds are never encoded, they're up to the server
+pub struct DecodedOptions {
+    pub filters: Filters,
+    pub limit: Option<Limit>,
+    pub offset: Option<Offset>,
+    pub order_by: Option<Box<str>>,
+    pub order_dir: Option<OrderDir>,
+}
+
+impl DecodedOptions {
+    pub fn options(&self) -> QueryOptions<'_> {
+        QueryOptions {
+            filters: self.filters.clone(),
+            expansions: &[],
+            limit: self.limit,
+            offset: self.offset,
+            order_by: self.order_by.as_deref(),
+            order_dir: self.order_dir.clone(),
+            extra_binds: HashMap::new(),
+        }
+    }
+}
+
+impl<'a> QueryOptions<'a> {
+    /// Encodes the filters, limit, offset and order into a compact URL-safe string, for links to
+    /// a filtered list and for continuation tokens
+    pub fn encode(&self) -> Result<String, EncodingError> {
+        let filters = self
+            .filters
+            .iter()
+            .map(|(key, (operator, value))| {
+                let value = match value {
+                    FilterValue::Escaped(kind) => encode_kind(kind),
+                    FilterValue::EscapedList(kinds) => kinds
+                        .iter()
+                        .map(encode_kind)
+                        .collect::<Option<Vec<_>>>()
+                        .map(|kinds| json!(["l", kinds])),
+                    _ => None,
+                }
+                .ok_or_else(|| EncodingError::Unencodable(key.clone()))?;
+
+                Ok(json!([key, operator.to_string(), value]))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let order_dir = self.order_dir.as_ref().map(|order_dir| match order_dir {
+            OrderDir::Asc => "asc",
+            OrderDir::Desc => "desc",
+        });
+
+        let value = json!({
+            "f": filters,
+            "l": self.limit,
+            "o": self.offset,
+            "b": self.order_by,
+            "d": order_dir,
+        });
+
+        Ok(format!(
+            "{}.{}",
+            VERSION,
+            encode_base64(value.to_string().as_bytes())
+        ))
+    }
+}
+
+/// Decodes a string made by [`QueryOptions::encode`]
+pub fn decode(encoded: &str) -> Result<DecodedOptions, EncodingError> {
+    let (version, data) = encoded.split_once('.').ok_or(EncodingError::Malformed)?;
+
+    match version {
+        "1" => decode_v1(data).ok_or(EncodingError::Malformed),
+        version => Err(EncodingError::UnknownVersion(version.into())),
+    }
+}
+
+fn decode_v1(data: &str) -> Option<DecodedOptions> {
+    let value: Value = serde_json::from_slice(&decode_base64(data)?).ok()?;
+
+    let filters = value
+        .get("f")?
+        .as_array()?
+        .iter()
+        .map(|filter| -> Option<Filter> {
+            let [key, operator, value] = filter.as_array()?.as_slice() else {
+                return None;
+            };
+
+            let value = match value.as_array()?.as_slice() {
+                [tag, Value::Array(kinds)] if tag == "l" => FilterValue::EscapedList(
+                    kinds
+                        .iter()
+                        .map(decode_kind)
+                        .collect::<Option<Box<[_]>>>()?,
+                ),
+                _ => FilterValue::Escaped(decode_kind(value)?),
+            };
+
+            Some((
+                key.as_str()?.into(),
+                (Operator::from_str(operator.as_str()?).ok()?, value),
+            ))
+        })
+        .collect::<Option<Box<[_]>>>()?;
+
+    let number = |key: &str| match value.get(key)? {
+        Value::Null => Some(None),
+        value => Some(Some(usize::try_from(value.as_u64()?).ok()?)),
+    };
+
+    let limit = match number("l")? {
+        Some(limit) => Some(Limit::new(limit)?),
+        None => None,
+    };
+
+    let offset = match number("o")? {
+        Some(offset) => Some(Offset::new(offset)?),
+        None => None,
+    };
+
+    let order_dir = match value.get("d")? {
+        Value::Null => None,
+        value => Some(match value.as_str()? {
+            "asc" => OrderDir::Asc,
+            "desc" => OrderDir::Desc,
+            _ => return None,
+        }),
+    };
+
+    Some(DecodedOptions {
+        filters: Filters(filters),
+        limit,
+        offset,
+        order_by: match value.get("b")? {
+            Value::Null => None,
+            value => Some(value.as_str()?.into()),
+        },
+        order_dir,
+    })
+}
+
+/// Arbitrary SDK values aren't encoded, they have no stable compact form
+fn encode_kind(kind: &FilterValueKind) -> Option<Value> {
+    Some(match kind {
+        FilterValueKind::String(value) => json!(["s", value]),
+        FilterValueKind::Int(value) => json!(["i", value]),
+        FilterValueKind::UInt(value) => json!(["u", value]),
+        FilterValueKind::Float(value) => json!(["f", value]),
+        FilterValueKind::Decimal(value) => json!(["d", value.to_string()]),
+        FilterValueKind::Bool(value) => json!(["b", value]),
+        FilterValueKind::Value(_) => return None,
+    })
+}
+
+fn decode_kind(value: &Value) -> Option<FilterValueKind> {
+    let [tag, value] = value.as_array()?.as_slice() else {
+        return None;
+    };
+
+    Some(match tag.as_str()? {
+        "s" => FilterValueKind::String(value.as_str()?.into()),
+        "i" => FilterValueKind::Int(value.as_i64()?),
+        "u" => FilterValueKind::UInt(value.as_u64()?),
+        "f" => FilterValueKind::Float(value.as_f64()?),
+        "d" => FilterValueKind::Decimal(Decimal::from_str(value.as_str()?).ok()?),
+        "b" => FilterValueKind::Bool(value.as_bool()?),
+        _ => return None,
+    })
+}
+
+/// URL-safe base64 without padding
+fn encode_base64(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, byte)| n | (*byte as u32) << (16 - 8 * i));
+
+        for i in 0..=chunk.len() {
+            encoded.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+    }
+
+    encoded
+}
+
+fn decode_base64(encoded: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(encoded.len() / 4 * 3);
+
+    for chunk in encoded.as_bytes().chunks(4) {
+        if chunk.len() == 1 {
+            return None;
+        }
+
+        let mut n = 0u32;
+
+        for (i, c) in chunk.iter().enumerate() {
+            let sextet = ALPHABET.iter().position(|a| a == c)? as u32;
+
+            n |= sextet << (18 - 6 * i);
+        }
+
+        for i in 0..chunk.len() - 1 {
+            bytes.push((n >> (16 - 8 * i)) as u8);
+        }
+    }
+
+    Some(bytes)
+}
@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use serde_json::Value;
+use surrealdb::Response;
+
+use crate::{filters::FilterValue, query_options::QueryOptions};
+
+/// The index lookup of an `Iterate Index` step
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct IndexPlan {
+    pub index: String,
+    pub operator: String,
+    pub value: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+pub struct ExplainDetail {
+    pub table: Option<String>,
+    pub plan: Option<IndexPlan>,
+    /// Records fetched, only with `EXPLAIN FULL`
+    pub count: Option<usize>,
+    pub reason: Option<String>,
+}
+
+/// One step of a query plan, like `Iterate Index` or `Fetch`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ExplainStep {
+    pub operation: String,
+    #[serde(default)]
+    pub detail: ExplainDetail,
+}
+
+/// The output of `EXPLAIN` or `EXPLAIN FULL`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(transparent)]
+pub struct Explain(pub Vec<ExplainStep>);
+
+impl Explain {
+    /// The tables iterated without an index, which CI can assert is empty for critical queries
+    pub fn full_table_scans(&self) -> Vec<&str> {
+        self.0
+            .iter()
+            .filter(|step| step.operation == "Iterate Table")
+            .filter_map(|step| step.detail.table.as_deref())
+            .collect()
+    }
+
+    /// The indexes used
+    pub fn indexes(&self) -> Vec<&str> {
+        self.0
+            .iter()
+            .filter_map(|step| Some(step.detail.plan.as_ref()?.index.as_str()))
+            .collect()
+    }
+
+    /// The records fetched, from `EXPLAIN FULL`
+    pub fn fetched(&self) -> Option<usize> {
+        self.0
+            .iter()
+            .filter(|step| step.operation == "Fetch")
+            .find_map(|step| step.detail.count)
+    }
+}
+
+impl<'a> QueryOptions<'a> {
+    /// Builds the query of `build` with `EXPLAIN`, or `EXPLAIN FULL` which also counts the
+    /// records fetched. Take its result with [`take_explain`]
+    pub fn build_explain(
+        self,
+        table_name: &str,
+        unsafe_columns: &[&str],
+        full: bool,
+    ) -> (Box<str>, HashMap<Box<str>, FilterValue>) {
+        let (query, variables) = self.build(table_name, unsafe_columns);
+
+        let explain = if full { "EXPLAIN FULL" } else { "EXPLAIN" };
+
+        (format!("{} {}", query, explain).into_boxed_str(), variables)
+    }
+}
+
+/// Takes the plan of the statement at `index`, which has to end in `EXPLAIN`
+pub fn take_explain(response: &mut Response, index: usize) -> surrealdb::Result<Explain> {
+    Ok(Explain(response.take(index)?))
+}
@@ -0,0 +1,49 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::{filters::FilterValue, query_options::QueryOptions};
+
+pub type ImportQuery = (Box<str>, HashMap<Box<str>, Vec<Value>>);
+
+impl<'a> QueryOptions<'a> {
+    /// Builds the query for dumping the filtered table, ordered by `id`. Ids are exported as their
+    /// key alone, like `1` for `user:1`, so the rows can be imported into a table of another
+    /// name
+    pub fn build_export(self, table_name: &str) -> (Box<str>, HashMap<Box<str>, FilterValue>) {
+        QueryOptions {
+            filters: self.filters,
+            extra_binds: self.extra_binds,
+            order_by: Some("id"),
+            ..QueryOptions::new()
+        }
+        .build(table_name, &["*", "meta::id(id) AS id"])
+    }
+}
+
+/// Writes exported rows as newline-delimited JSON
+pub fn to_ndjson(rows: &[Value]) -> String {
+    rows.iter().map(|row| format!("{}\n", row)).collect()
+}
+
+/// Reads rows written by [`to_ndjson`], skipping blank lines
+pub fn from_ndjson(ndjson: &str) -> serde_json::Result<Vec<Value>> {
+    ndjson
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(serde_json::from_str)
+        .collect()
+}
+
+/// Builds one `INSERT INTO table $rows` per `batch_size` rows, so large imports don't end up in
+/// a single huge statement
+pub fn build_import(table_name: &str, rows: &[Value], batch_size: usize) -> Vec<ImportQuery> {
+    rows.chunks(batch_size.max(1))
+        .map(|batch| {
+            (
+                format!("INSERT INTO {} $rows", table_name).into_boxed_str(),
+                HashMap::from([("rows".into(), batch.to_vec())]),
+            )
+        })
+        .collect()
+}
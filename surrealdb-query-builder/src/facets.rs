@@ -0,0 +1,96 @@
+use std::{collections::HashMap, fmt::Display};
+
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::Value;
+use surrealdb::Response;
+
+use crate::{
+    bind_naming::BindNaming,
+    filters::Filters,
+    query_options::{resolve_key, QueryOptions, SelectQuery},
+};
+
+/// How many records have one value of a facet
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct FacetCount {
+    pub value: Value,
+    pub count: u64,
+}
+
+/// The counts of each facet's values, most common first
+pub type FacetResult = HashMap<Box<str>, Vec<FacetCount>>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidFacet(pub Box<str>);
+
+impl Display for InvalidFacet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} can't be a facet", self.0)
+    }
+}
+
+impl std::error::Error for InvalidFacet {}
+
+impl<'a> QueryOptions<'a> {
+    /// Builds one grouped count per facet, in order, as a single batch. Each count leaves out
+    /// the facet's own filters, so a listing page can offer the other values of a facet that's
+    /// already narrowed down. Order, limit and offset are ignored. Take the counts with
+    /// [`take_facets`]
+    pub fn build_facets(
+        self,
+        table_name: &str,
+        fields: &[&str],
+    ) -> Result<SelectQuery, InvalidFacet> {
+        let regex = Regex::new(r"^\w+(\.\w+)*$").unwrap();
+
+        let mut statements = vec![];
+        let mut variables = HashMap::new();
+
+        for (i, field) in fields.iter().enumerate() {
+            if !regex.is_match(field) {
+                return Err(InvalidFacet((*field).into()));
+            }
+
+            let filters = self
+                .filters
+                .iter()
+                .filter(|(key, _)| resolve_key(key).as_deref() != Some(*field))
+                .cloned()
+                .collect::<Vec<_>>();
+
+            // Every statement names its binds apart, so they can share the batch's variables
+            let naming = BindNaming {
+                prefix: format!("f{}_", i).into(),
+                ..BindNaming::new()
+            };
+
+            let (statement, facet_variables) = QueryOptions {
+                filters: Filters(filters.into_boxed_slice()),
+                extra_binds: self.extra_binds.clone(),
+                ..QueryOptions::new()
+            }
+            .build_select(
+                table_name,
+                &[&format!("{} AS value", field), "count() AS count"],
+                Some("GROUP BY value ORDER BY count DESC"),
+                &naming,
+            )
+            .expect("suffixing never collides");
+
+            statements.push(statement);
+            variables.extend(facet_variables);
+        }
+
+        Ok((statements.join("; ").into_boxed_str(), variables))
+    }
+}
+
+/// Takes the counts of `build_facets`, built with the same `fields`
+pub fn take_facets(response: &mut Response, fields: &[&str]) -> surrealdb::Result<FacetResult> {
+    fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| Ok(((*field).into(), response.take(i)?)))
+        .collect()
+}
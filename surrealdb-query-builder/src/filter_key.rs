@@ -0,0 +1,90 @@
+use regex::Regex;
+
+/// Functions that can wrap the field of a filter key, with the argument each takes after the
+/// field, if any
+const FUNCTIONS: &[(&str, Argument)] = &[
+    ("array::len", Argument::None),
+    ("math::abs", Argument::None),
+    ("math::ceil", Argument::None),
+    ("math::floor", Argument::None),
+    ("math::round", Argument::None),
+    ("string::len", Argument::None),
+    ("string::lowercase", Argument::None),
+    ("string::trim", Argument::None),
+    ("string::uppercase", Argument::None),
+    ("time::day", Argument::None),
+    ("time::floor", Argument::Duration),
+    ("time::group", Argument::TimeUnit),
+    ("time::hour", Argument::None),
+    ("time::month", Argument::None),
+    ("time::round", Argument::Duration),
+    ("time::wday", Argument::None),
+    ("time::yday", Argument::None),
+    ("time::year", Argument::None),
+];
+
+#[derive(Clone, Copy)]
+enum Argument {
+    None,
+    /// A duration literal, like `1d`
+    Duration,
+    /// A quoted unit of `time::group`, like `'day'`
+    TimeUnit,
+}
+
+/// Parses a filter key that wraps its field in one of the whitelisted functions, like
+/// `string::lowercase(email)` or `time::floor(created_at, 1d)`. Returns `None` for anything else,
+/// including functions that aren't whitelisted and invalid arguments
+pub(crate) fn function_key(key: &str) -> Option<Box<str>> {
+    let regex = Regex::new(r"^\s*(\w+::\w+)\(\s*([\w\.]+)\s*(?:,\s*([^()]*?)\s*)?\)\s*$").unwrap();
+
+    let captures = regex.captures(key)?;
+    let function = captures.get(1)?.as_str();
+    let field = captures.get(2)?.as_str();
+    let argument = captures.get(3).map(|m| m.as_str());
+
+    let (_, expected) = FUNCTIONS.iter().find(|(name, _)| *name == function)?;
+
+    let valid = match (expected, argument) {
+        (Argument::None, None) => true,
+        (Argument::Duration, Some(argument)) => Regex::new(r"^(\d+(ns|us|µs|ms|s|m|h|d|w|y))+$")
+            .unwrap()
+            .is_match(argument),
+        (Argument::TimeUnit, Some(argument)) => {
+            Regex::new(r#"^(['"])(year|month|week|day|hour|minute|second)['"]$"#)
+                .unwrap()
+                .is_match(argument)
+        }
+        _ => false,
+    };
+
+    let key = match argument {
+        Some(argument) => format!("{}({}, {})", function, field, argument),
+        None => format!("{}({})", function, field),
+    };
+
+    valid.then(|| key.into_boxed_str())
+}
+
+/// Parses a filter key that does arithmetic on fields and numbers, like `price * quantity` or
+/// `stock - 10`, normalizing the spacing. There are no parentheses, so the usual precedence
+/// applies
+pub(crate) fn arithmetic_key(key: &str) -> Option<Box<str>> {
+    let operand = r"(?:[A-Za-z_][\w\.]*|\d+(?:\.\d+)?)";
+    let regex = Regex::new(&format!(r"^\s*{operand}(?:\s*[-+*/]\s*{operand})+\s*$")).unwrap();
+
+    if !regex.is_match(key) {
+        return None;
+    }
+
+    let token_regex = Regex::new(&format!(r"{operand}|[-+*/]")).unwrap();
+
+    Some(
+        token_regex
+            .find_iter(key)
+            .map(|m| m.as_str())
+            .collect::<Vec<_>>()
+            .join(" ")
+            .into_boxed_str(),
+    )
+}
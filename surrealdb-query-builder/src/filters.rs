@@ -1,12 +1,18 @@
 use std::{
+    collections::HashMap,
     fmt::Display,
     ops::{Deref, DerefMut},
 };
 
+use regex::Regex;
 use rust_decimal::Decimal;
 use serde::Serialize;
+use surrealdb::sql::{Datetime, Number, Strand, Thing, Value};
 
-use crate::operator::Operator;
+use crate::{
+    bind_naming::{BindCollision, BindNaming},
+    operator::Operator,
+};
 
 #[derive(Debug, Clone, PartialEq, Serialize)]
 #[serde(untagged)]
@@ -18,6 +24,8 @@ pub enum FilterValueKind {
     #[serde(serialize_with = "serialize_decimal")]
     Decimal(Decimal),
     Bool(bool),
+    /// Any value the SDK can bind, like objects, geometries or ranges
+    Value(Value),
 }
 
 fn serialize_decimal<S>(d: &Decimal, s: S) -> Result<S::Ok, S::Error>
@@ -27,69 +35,109 @@ where
     surrealdb::sql::Number::Decimal(*d).serialize(s)
 }
 
-impl Into<FilterValueKind> for &str {
-    fn into(self) -> FilterValueKind {
-        FilterValueKind::String(self.into())
+impl From<&str> for FilterValueKind {
+    fn from(value: &str) -> Self {
+        FilterValueKind::String(value.into())
+    }
+}
+
+impl From<String> for FilterValueKind {
+    fn from(value: String) -> Self {
+        FilterValueKind::String(value.into())
+    }
+}
+
+impl From<Box<str>> for FilterValueKind {
+    fn from(value: Box<str>) -> Self {
+        FilterValueKind::String(value)
     }
 }
 
-impl Into<FilterValueKind> for String {
-    fn into(self) -> FilterValueKind {
-        FilterValueKind::String(self.into())
+impl From<i64> for FilterValueKind {
+    fn from(value: i64) -> Self {
+        FilterValueKind::Int(value)
     }
 }
 
-impl Into<FilterValueKind> for Box<str> {
-    fn into(self) -> FilterValueKind {
-        FilterValueKind::String(self)
+impl From<u64> for FilterValueKind {
+    fn from(value: u64) -> Self {
+        FilterValueKind::UInt(value)
     }
 }
 
-impl Into<FilterValueKind> for i64 {
-    fn into(self) -> FilterValueKind {
-        FilterValueKind::Int(self)
+impl From<f64> for FilterValueKind {
+    fn from(value: f64) -> Self {
+        FilterValueKind::Float(value)
     }
 }
 
-impl Into<FilterValueKind> for u64 {
-    fn into(self) -> FilterValueKind {
-        FilterValueKind::UInt(self)
+impl From<i32> for FilterValueKind {
+    fn from(value: i32) -> Self {
+        FilterValueKind::Int(value.into())
     }
 }
 
-impl Into<FilterValueKind> for f64 {
-    fn into(self) -> FilterValueKind {
-        FilterValueKind::Float(self)
+impl From<u32> for FilterValueKind {
+    fn from(value: u32) -> Self {
+        FilterValueKind::UInt(value.into())
     }
 }
 
-impl Into<FilterValueKind> for i32 {
-    fn into(self) -> FilterValueKind {
-        FilterValueKind::Int(self.into())
+impl From<f32> for FilterValueKind {
+    fn from(value: f32) -> Self {
+        FilterValueKind::Float(value.into())
     }
 }
 
-impl Into<FilterValueKind> for u32 {
-    fn into(self) -> FilterValueKind {
-        FilterValueKind::UInt(self.into())
+impl From<Decimal> for FilterValueKind {
+    fn from(value: Decimal) -> Self {
+        FilterValueKind::Decimal(value)
     }
 }
 
-impl Into<FilterValueKind> for f32 {
-    fn into(self) -> FilterValueKind {
-        FilterValueKind::Float(self.into())
+impl From<bool> for FilterValueKind {
+    fn from(value: bool) -> Self {
+        FilterValueKind::Bool(value)
     }
 }
 
-impl Into<FilterValueKind> for Decimal {
-    fn into(self) -> FilterValueKind {
-        FilterValueKind::Decimal(self)
+impl From<Number> for FilterValueKind {
+    fn from(value: Number) -> Self {
+        match value {
+            Number::Int(value) => FilterValueKind::Int(value),
+            Number::Float(value) => FilterValueKind::Float(value),
+            Number::Decimal(value) => FilterValueKind::Decimal(value),
+        }
     }
 }
 
-impl Into<FilterValueKind> for bool {
-    fn into(self) -> FilterValueKind {
-        FilterValueKind::Bool(self)
+impl From<Strand> for FilterValueKind {
+    fn from(value: Strand) -> Self {
+        FilterValueKind::String(value.0.into())
+    }
+}
+
+impl From<Thing> for FilterValueKind {
+    fn from(value: Thing) -> Self {
+        FilterValueKind::Value(Value::Thing(value))
+    }
+}
+
+impl From<Datetime> for FilterValueKind {
+    fn from(value: Datetime) -> Self {
+        FilterValueKind::Value(Value::Datetime(value))
+    }
+}
+
+/// Strings, numbers and booleans become their plain kinds, everything else is wrapped as is
+impl From<Value> for FilterValueKind {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Bool(value) => FilterValueKind::Bool(value),
+            Value::Number(value) => value.into(),
+            Value::Strand(value) => value.into(),
+            value => FilterValueKind::Value(value),
+        }
     }
 }
 
@@ -102,6 +150,7 @@ impl Display for FilterValueKind {
             FilterValueKind::Float(value) => value.fmt(f),
             FilterValueKind::Decimal(value) => value.fmt(f),
             FilterValueKind::Bool(value) => value.fmt(f),
+            FilterValueKind::Value(value) => value.fmt(f),
         }
     }
 }
@@ -112,89 +161,217 @@ pub enum FilterValue {
     Escaped(FilterValueKind),
     Unsafe(FilterValueKind),
     EscapedList(Box<[FilterValueKind]>),
+    /// Compares with the session, like `owner = $auth.id`, without going through `Unsafe`
+    Session(SessionParam),
+    /// A whole condition, see [`Filters::raw`]
+    Raw(RawFilter),
+}
+
+/// A condition written by hand, with its own variables. The key and operator of a filter with a
+/// raw value are ignored. Built with [`Filters::raw`], which checks the names of the binds
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct RawFilter {
+    pub(crate) fragment: Box<str>,
+    pub(crate) binds: Box<[(Box<str>, FilterValue)]>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidBindName(pub Box<str>);
+
+impl Display for InvalidBindName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} is not a variable name", self.0)
+    }
+}
+
+impl std::error::Error for InvalidBindName {}
+
+impl RawFilter {
+    /// Adds the binds to `variables` and returns the condition. Binds are named by `naming`, so
+    /// one whose name is taken gets the next free suffix by default, and is renamed in the
+    /// fragment too
+    pub(crate) fn merge_into(
+        self,
+        variables: &mut HashMap<Box<str>, FilterValue>,
+        naming: &BindNaming,
+    ) -> Result<String, BindCollision> {
+        let variable_regex = Regex::new(r"\$(\w+)").unwrap();
+
+        let mut renames = HashMap::new();
+
+        for (name, value) in self.binds.into_vec() {
+            let unique = naming.assign(&name, 0, variables)?;
+
+            if unique != name {
+                renames.insert(name, unique.clone());
+            }
+
+            variables.insert(unique, value);
+        }
+
+        let fragment = variable_regex.replace_all(&self.fragment, |caps: &regex::Captures| {
+            match renames.get(&caps[1]) {
+                Some(unique) => format!("${}", unique),
+                None => caps[0].to_string(),
+            }
+        });
+
+        Ok(format!("({})", fragment))
+    }
+}
+
+/// A path into one of the parameters the database sets for the session, `$auth`, `$session` or
+/// `$token`, like `$auth.id`. Only valid paths can be constructed, so it's safe to write into a
+/// query
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct SessionParam(Box<str>);
+
+impl SessionParam {
+    pub fn new(path: &str) -> Option<Self> {
+        let regex = Regex::new(r"^\$(auth|session|token)(\.\w+)*$").unwrap();
+
+        regex.is_match(path).then(|| SessionParam(path.into()))
+    }
+
+    /// The param as a projection, like `$auth.id AS owner`
+    pub fn projection(&self, alias: &str) -> Option<Box<str>> {
+        let regex = Regex::new(r"^\w+$").unwrap();
+
+        regex
+            .is_match(alias)
+            .then(|| format!("{} AS {}", self.0, alias).into_boxed_str())
+    }
+}
+
+impl Display for SessionParam {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl From<SessionParam> for FilterValue {
+    fn from(value: SessionParam) -> Self {
+        FilterValue::Session(value)
+    }
+}
+
+impl From<FilterValueKind> for FilterValue {
+    fn from(value: FilterValueKind) -> Self {
+        FilterValue::Escaped(value)
+    }
+}
+
+impl From<&str> for FilterValue {
+    fn from(value: &str) -> Self {
+        FilterValue::Escaped(value.into())
+    }
+}
+
+impl From<String> for FilterValue {
+    fn from(value: String) -> Self {
+        FilterValue::Escaped(value.into())
+    }
+}
+
+impl From<Box<str>> for FilterValue {
+    fn from(value: Box<str>) -> Self {
+        FilterValue::Escaped(value.into())
+    }
 }
 
-impl Into<FilterValue> for FilterValueKind {
-    fn into(self) -> FilterValue {
-        FilterValue::Escaped(self)
+impl From<i64> for FilterValue {
+    fn from(value: i64) -> Self {
+        FilterValue::Escaped(value.into())
     }
 }
 
-impl Into<FilterValue> for &str {
-    fn into(self) -> FilterValue {
-        FilterValue::Escaped(self.into())
+impl From<u64> for FilterValue {
+    fn from(value: u64) -> Self {
+        FilterValue::Escaped(value.into())
     }
 }
 
-impl Into<FilterValue> for String {
-    fn into(self) -> FilterValue {
-        FilterValue::Escaped(self.into())
+impl From<f64> for FilterValue {
+    fn from(value: f64) -> Self {
+        FilterValue::Escaped(value.into())
     }
 }
 
-impl Into<FilterValue> for Box<str> {
-    fn into(self) -> FilterValue {
-        FilterValue::Escaped(self.into())
+impl From<i32> for FilterValue {
+    fn from(value: i32) -> Self {
+        FilterValue::Escaped(value.into())
     }
 }
 
-impl Into<FilterValue> for i64 {
-    fn into(self) -> FilterValue {
-        FilterValue::Escaped(self.into())
+impl From<u32> for FilterValue {
+    fn from(value: u32) -> Self {
+        FilterValue::Escaped(value.into())
     }
 }
 
-impl Into<FilterValue> for u64 {
-    fn into(self) -> FilterValue {
-        FilterValue::Escaped(self.into())
+impl From<f32> for FilterValue {
+    fn from(value: f32) -> Self {
+        FilterValue::Escaped(value.into())
     }
 }
 
-impl Into<FilterValue> for f64 {
-    fn into(self) -> FilterValue {
-        FilterValue::Escaped(self.into())
+impl From<Decimal> for FilterValue {
+    fn from(value: Decimal) -> Self {
+        FilterValue::Escaped(value.into())
     }
 }
 
-impl Into<FilterValue> for i32 {
-    fn into(self) -> FilterValue {
-        FilterValue::Escaped(self.into())
+impl From<bool> for FilterValue {
+    fn from(value: bool) -> Self {
+        FilterValue::Escaped(value.into())
     }
 }
 
-impl Into<FilterValue> for u32 {
-    fn into(self) -> FilterValue {
-        FilterValue::Escaped(self.into())
+impl From<Number> for FilterValue {
+    fn from(value: Number) -> Self {
+        FilterValue::Escaped(value.into())
     }
 }
 
-impl Into<FilterValue> for f32 {
-    fn into(self) -> FilterValue {
-        FilterValue::Escaped(self.into())
+impl From<Strand> for FilterValue {
+    fn from(value: Strand) -> Self {
+        FilterValue::Escaped(value.into())
     }
 }
 
-impl Into<FilterValue> for Decimal {
-    fn into(self) -> FilterValue {
-        FilterValue::Escaped(self.into())
+impl From<Thing> for FilterValue {
+    fn from(value: Thing) -> Self {
+        FilterValue::Escaped(value.into())
     }
 }
 
-impl Into<FilterValue> for bool {
-    fn into(self) -> FilterValue {
-        FilterValue::Escaped(self.into())
+impl From<Datetime> for FilterValue {
+    fn from(value: Datetime) -> Self {
+        FilterValue::Escaped(value.into())
     }
 }
 
-impl<T: Into<FilterValueKind>> Into<FilterValue> for Box<[T]> {
-    fn into(self) -> FilterValue {
-        FilterValue::EscapedList(self.into_vec().into_iter().map(|s| s.into()).collect())
+/// Arrays become lists, for `CONTAINSANY` and `INSIDE`
+impl From<Value> for FilterValue {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Array(values) => {
+                FilterValue::EscapedList(values.0.into_iter().map(FilterValueKind::from).collect())
+            }
+            value => FilterValue::Escaped(value.into()),
+        }
     }
 }
 
-impl<T: Into<FilterValueKind>> Into<FilterValue> for Vec<T> {
-    fn into(self) -> FilterValue {
-        self.into_boxed_slice().into()
+impl<T: Into<FilterValueKind>> From<Box<[T]>> for FilterValue {
+    fn from(value: Box<[T]>) -> Self {
+        FilterValue::EscapedList(value.into_vec().into_iter().map(|s| s.into()).collect())
+    }
+}
+
+impl<T: Into<FilterValueKind>> From<Vec<T>> for FilterValue {
+    fn from(value: Vec<T>) -> Self {
+        value.into_boxed_slice().into()
     }
 }
 
@@ -212,15 +389,56 @@ impl Display for FilterValue {
                     .join(",")
             )
             .fmt(f),
+            FilterValue::Session(param) => param.fmt(f),
+            FilterValue::Raw(raw) => raw.fragment.fmt(f),
         }
     }
 }
 
-#[derive(Default)]
-pub struct Filters(pub Box<[(Box<str>, (Operator, FilterValue))]>);
+pub type Filter = (Box<str>, (Operator, FilterValue));
+
+#[derive(Default, Clone)]
+pub struct Filters(pub Box<[Filter]>);
+
+impl Filters {
+    /// A hand-written condition like `fn::custom_score(id, $weights) > $min`, as an escape hatch
+    /// for what the other filters can't express. Values are still bound, never written into the
+    /// fragment. Names may be written with or without their `$`
+    pub fn raw<T: Into<FilterValue>, S: Into<Box<str>>>(
+        fragment: &str,
+        binds: Vec<(S, T)>,
+    ) -> Result<Filter, InvalidBindName> {
+        let name_regex = Regex::new(r"^\w+$").unwrap();
+
+        let binds = binds
+            .into_iter()
+            .map(|(name, value)| {
+                let name: Box<str> = name.into();
+                let name = name.strip_prefix('$').unwrap_or(&name);
+
+                if !name_regex.is_match(name) {
+                    return Err(InvalidBindName(name.into()));
+                }
+
+                Ok((name.into(), value.into()))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok((
+            "".into(),
+            (
+                Operator::Eq,
+                FilterValue::Raw(RawFilter {
+                    fragment: fragment.into(),
+                    binds,
+                }),
+            ),
+        ))
+    }
+}
 
 impl Deref for Filters {
-    type Target = Box<[(Box<str>, (Operator, FilterValue))]>;
+    type Target = Box<[Filter]>;
 
     fn deref(&self) -> &Self::Target {
         &self.0
@@ -233,43 +451,45 @@ impl DerefMut for Filters {
     }
 }
 
-impl<T: Into<FilterValue>, S: Into<Box<str>>> Into<Filters> for Vec<(S, (Operator, T))> {
-    fn into(self) -> Filters {
+impl<T: Into<FilterValue>, S: Into<Box<str>>> From<Vec<(S, (Operator, T))>> for Filters {
+    fn from(filters: Vec<(S, (Operator, T))>) -> Self {
         Filters(
-            self.into_iter()
+            filters
+                .into_iter()
                 .map(|(key, (operator, value))| (key.into(), (operator, value.into())))
                 .collect(),
         )
     }
 }
 
-impl<T: Into<FilterValue>, S: Into<Box<str>>> Into<Filters> for Box<[(S, (Operator, T))]> {
-    fn into(self) -> Filters {
-        self.into_vec().into()
+impl<T: Into<FilterValue>, S: Into<Box<str>>> From<Box<[(S, (Operator, T))]>> for Filters {
+    fn from(filters: Box<[(S, (Operator, T))]>) -> Self {
+        filters.into_vec().into()
     }
 }
 
-impl<T: Clone + Into<FilterValue>, S: Into<Box<str>> + Clone> Into<Filters>
-    for &[(S, (Operator, T))]
+impl<T: Clone + Into<FilterValue>, S: Into<Box<str>> + Clone> From<&[(S, (Operator, T))]>
+    for Filters
 {
-    fn into(self) -> Filters {
-        let b: Box<[_]> = self.into();
+    fn from(filters: &[(S, (Operator, T))]) -> Self {
+        let b: Box<[_]> = filters.into();
         b.into()
     }
 }
 
-impl<T: Into<FilterValue>, S: Into<String>> Into<Filters> for Vec<(S, T)> {
-    fn into(self) -> Filters {
+impl<T: Into<FilterValue>, S: Into<String>> From<Vec<(S, T)>> for Filters {
+    fn from(filters: Vec<(S, T)>) -> Self {
         Filters(
-            self.into_iter()
+            filters
+                .into_iter()
                 .map(|(key, value)| (key.into().into_boxed_str(), (Operator::Eq, value.into())))
                 .collect(),
         )
     }
 }
 
-impl<T: Into<FilterValue>, S: Into<String>> Into<Filters> for Box<[(S, T)]> {
-    fn into(self) -> Filters {
-        self.into_vec().into()
+impl<T: Into<FilterValue>, S: Into<String>> From<Box<[(S, T)]>> for Filters {
+    fn from(filters: Box<[(S, T)]>) -> Self {
+        filters.into_vec().into()
     }
 }
@@ -0,0 +1,116 @@
+use std::fmt::Display;
+
+use regex::Regex;
+
+use crate::{
+    bind_naming::BindNaming,
+    query_options::{QueryOptions, SelectQuery},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GroupError {
+    /// Not a field or a dotted path into one, like `customer.country`
+    InvalidField(Box<str>),
+    /// [`Group::By`] without any fields
+    NoFields,
+}
+
+impl Display for GroupError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GroupError::InvalidField(field) => write!(f, "{:?} is not a field path", field),
+            GroupError::NoFields => write!(f, "GROUP BY needs at least one field"),
+        }
+    }
+}
+
+impl std::error::Error for GroupError {}
+
+/// How rows are grouped for aggregates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Group<'a> {
+    /// `GROUP ALL`, one row aggregated over every record that passes the filters
+    All,
+    /// `GROUP BY` the fields, one row per distinct combination
+    By(&'a [&'a str]),
+}
+
+impl<'a> QueryOptions<'a> {
+    /// Builds a grouped `SELECT` with the filters of `build`. With [`Group::All`] there's only
+    /// ever one row, so order, limit and offset are left out
+    pub fn build_grouped(
+        mut self,
+        table_name: &str,
+        unsafe_columns: &[&str],
+        group: Group,
+    ) -> Result<SelectQuery, GroupError> {
+        let field_regex = Regex::new(r"^\w+(\.\w+)*$").unwrap();
+
+        let group = match group {
+            Group::All => {
+                self.order_by = None;
+                self.limit = None;
+                self.offset = None;
+
+                "GROUP ALL".to_string()
+            }
+            Group::By([]) => return Err(GroupError::NoFields),
+            Group::By(fields) => {
+                if let Some(field) = fields.iter().find(|field| !field_regex.is_match(field)) {
+                    return Err(GroupError::InvalidField((*field).into()));
+                }
+
+                format!("GROUP BY {}", fields.join(","))
+            }
+        };
+
+        Ok(self
+            .build_select(
+                table_name,
+                unsafe_columns,
+                Some(&group),
+                &BindNaming::default(),
+            )
+            .expect("suffixing never collides"))
+    }
+}
+
+/// An aggregate over the rows of a group, to project in a grouped `SELECT`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregate<'a> {
+    /// `count()`, the rows in the group
+    Count,
+    /// `count(field)`, the rows where the field is truthy
+    CountField(&'a str),
+    /// `array::group(field)`, the distinct values of the field
+    Group(&'a str),
+    Sum(&'a str),
+    Mean(&'a str),
+    Min(&'a str),
+    Max(&'a str),
+}
+
+impl<'a> Aggregate<'a> {
+    /// The aggregate as a projection, like `math::sum(total) AS revenue`. Returns `None` when the
+    /// field or alias isn't a plain (dotted) identifier
+    pub fn projection(&self, alias: &str) -> Option<Box<str>> {
+        let field_regex = Regex::new(r"^\w+(\.\w+)*$").unwrap();
+        let alias_regex = Regex::new(r"^\w+$").unwrap();
+
+        let (function, field) = match *self {
+            Aggregate::Count => ("count", None),
+            Aggregate::CountField(field) => ("count", Some(field)),
+            Aggregate::Group(field) => ("array::group", Some(field)),
+            Aggregate::Sum(field) => ("math::sum", Some(field)),
+            Aggregate::Mean(field) => ("math::mean", Some(field)),
+            Aggregate::Min(field) => ("math::min", Some(field)),
+            Aggregate::Max(field) => ("math::max", Some(field)),
+        };
+
+        if !alias_regex.is_match(alias) || !field.is_none_or(|f| field_regex.is_match(f)) {
+            return None;
+        }
+
+        Some(format!("{}({}) AS {}", function, field.unwrap_or_default(), alias).into_boxed_str())
+    }
+}
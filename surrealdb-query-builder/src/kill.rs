@@ -0,0 +1,57 @@
+use std::{collections::HashMap, fmt::Display, str::FromStr};
+
+use serde::Serialize;
+use surrealdb::sql::Uuid;
+
+/// The id a `LIVE SELECT` returns, checked to be a UUID so it can be bound to `KILL`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(transparent)]
+pub struct LiveQueryId(Uuid);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidLiveQueryId(pub Box<str>);
+
+impl Display for InvalidLiveQueryId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} is not a live query id", self.0)
+    }
+}
+
+impl std::error::Error for InvalidLiveQueryId {}
+
+impl FromStr for LiveQueryId {
+    type Err = InvalidLiveQueryId;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Uuid::try_from(value)
+            .map(LiveQueryId)
+            .map_err(|_| InvalidLiveQueryId(value.into()))
+    }
+}
+
+impl From<Uuid> for LiveQueryId {
+    fn from(value: Uuid) -> Self {
+        LiveQueryId(value)
+    }
+}
+
+impl Display for LiveQueryId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0 .0.fmt(f)
+    }
+}
+
+/// Builds `KILL $id`
+pub struct KillOptions {
+    pub id: LiveQueryId,
+}
+
+impl KillOptions {
+    pub fn new(id: LiveQueryId) -> Self {
+        Self { id }
+    }
+
+    pub fn build(self) -> (Box<str>, HashMap<Box<str>, LiveQueryId>) {
+        ("KILL $id".into(), [("id".into(), self.id)].into())
+    }
+}
@@ -1,27 +1,92 @@
+// Errors carry the SDK's own error unboxed, the way `Response::take` returns it
+#![allow(clippy::result_large_err)]
+
+pub mod bind_naming;
+pub mod binds;
+pub mod buckets;
+pub mod cache;
+pub mod chunks;
+pub mod classify;
+pub mod cleanup;
+pub mod complexity;
+pub mod count;
+pub mod diff;
+pub mod encoding;
+pub mod explain;
+pub mod export;
+pub mod facets;
+mod filter_key;
 pub mod filters;
+pub mod group;
+pub mod kill;
+pub mod limit;
+pub mod memo;
 pub mod operator;
+pub mod order_by;
 pub mod order_dir;
+#[cfg(feature = "otel")]
+pub mod otel;
+pub mod pagination;
+pub mod patch;
+pub mod presets;
+pub mod preview;
 pub mod query_options;
+pub mod range;
+pub mod scope;
+pub mod stock;
+pub mod suggest;
+pub mod tenant;
+pub mod user;
 
 pub type Expansions<'a> = &'a [(&'a str, &'a str)];
 
 #[cfg(test)]
 mod tests {
-    use std::collections::HashMap;
+    use std::{collections::HashMap, ops::Bound, time::Duration};
 
+    use futures::TryStreamExt;
     use rust_decimal::Decimal;
     use serde::Deserialize;
     use surrealdb::{
         engine::local::{Db, Mem},
         opt::Config,
+        sql::{Id, Thing},
         Surreal,
     };
 
     use crate::{
-        filters::{FilterValue, Filters},
-        operator::Operator,
+        bind_naming::{BindCollision, BindNaming, CollisionPolicy},
+        binds::{sql_binds, Unbindable},
+        buckets::{TimeBucketError, TimeBuckets},
+        cache::{fingerprint, CacheSession, CacheStore, CachingExecutor, MemoryStore},
+        classify::{classify, Classify, QueryKind},
+        cleanup::{build_recreate, build_truncate, recreate, truncate, InvalidTable},
+        complexity::{ComplexityError, ComplexityLimits},
+        count::take_with_count,
+        diff::{take_diffs, Diff, DiffOp},
+        encoding::{decode, EncodingError},
+        explain::take_explain,
+        export::{build_import, from_ndjson, to_ndjson},
+        facets::{take_facets, FacetCount, InvalidFacet},
+        filters::{FilterValue, FilterValueKind, Filters, InvalidBindName, SessionParam},
+        group::{Aggregate, Group, GroupError},
+        kill::{InvalidLiveQueryId, KillOptions, LiveQueryId},
+        limit::{Limit, Offset},
+        memo::MemoScope,
+        operator::{Operator, UnknownOperator},
+        order_by::{OrderByError, SortExpr},
         order_dir::OrderDir,
-        query_options::QueryOptions,
+        pagination::{Pagination, PaginationError},
+        patch::{PatchError, PatchOptions},
+        presets::{FilterPresets, UnknownPreset},
+        preview::PreviewRequest,
+        query_options::{ExpansionError, QueryOptions},
+        range::RecordRange,
+        scope::{DefineScopeError, DefineScopeOptions, ScopeAuthError, ScopeCredentials},
+        stock::{ReservationError, StockReservation},
+        suggest::{Suggest, SuggestError},
+        tenant::{Database, InvalidTenantName, Namespace, TenantContext},
+        user::{remove_user, Credential, DefineUserError, DefineUserOptions, UserLevel, UserRole},
     };
 
     async fn set_up_db() -> Surreal<Db> {
@@ -52,10 +117,11 @@ mod tests {
                 ),
             )])),
             expansions: &[],
-            limit: Some(10),
-            offset: Some(0),
+            limit: Limit::new(10),
+            offset: Offset::new(0),
             order_by: Some("id"),
             order_dir: Some(OrderDir::Asc),
+            extra_binds: HashMap::new(),
         };
 
         let query = opts.build("user", &["id", "name"]);
@@ -85,10 +151,11 @@ mod tests {
                 ),
             )])),
             expansions: &[],
-            limit: Some(10),
-            offset: Some(0),
+            limit: Limit::new(10),
+            offset: Offset::new(0),
             order_by: Some("id"),
             order_dir: Some(OrderDir::Asc),
+            extra_binds: HashMap::new(),
         };
 
         let query = opts.build("user", &["id", "name"]);
@@ -112,10 +179,11 @@ mod tests {
                 ("id".into(), (Operator::Ne, "1".into())),
             ])),
             expansions: &[],
-            limit: Some(10),
-            offset: Some(0),
+            limit: Limit::new(10),
+            offset: Offset::new(0),
             order_by: Some("id"),
             order_dir: Some(OrderDir::Asc),
+            extra_binds: HashMap::new(),
         };
 
         let query = opts.build("user", &["id", "name"]);
@@ -144,10 +212,11 @@ mod tests {
         let opts = QueryOptions {
             filters: Filters(Box::new([])),
             expansions: &[],
-            limit: Some(10),
-            offset: Some(0),
+            limit: Limit::new(10),
+            offset: Offset::new(0),
             order_by: Some("id"),
             order_dir: Some(OrderDir::Asc),
+            extra_binds: HashMap::new(),
         };
 
         let query = opts.build("user", &["id", "name"]);
@@ -168,9 +237,10 @@ mod tests {
             filters: Filters(Box::new([])),
             expansions: &[],
             limit: None,
-            offset: Some(0),
+            offset: Offset::new(0),
             order_by: Some("id"),
             order_dir: Some(OrderDir::Asc),
+            extra_binds: HashMap::new(),
         };
 
         let query = opts.build("user", &["id", "name"]);
@@ -190,10 +260,11 @@ mod tests {
         let opts = QueryOptions {
             filters: Filters(Box::new([])),
             expansions: &[],
-            limit: Some(10),
+            limit: Limit::new(10),
             offset: None,
             order_by: Some("id"),
             order_dir: Some(OrderDir::Asc),
+            extra_binds: HashMap::new(),
         };
 
         let query = opts.build("user", &["id", "name"]);
@@ -213,10 +284,11 @@ mod tests {
         let opts = QueryOptions {
             filters: Filters(Box::new([])),
             expansions: &[],
-            limit: Some(10),
-            offset: Some(0),
+            limit: Limit::new(10),
+            offset: Offset::new(0),
             order_by: None,
             order_dir: Some(OrderDir::Asc),
+            extra_binds: HashMap::new(),
         };
 
         let query = opts.build("user", &["id", "name"]);
@@ -236,10 +308,11 @@ mod tests {
         let opts = QueryOptions {
             filters: Filters(Box::new([])),
             expansions: &[],
-            limit: Some(10),
-            offset: Some(0),
+            limit: Limit::new(10),
+            offset: Offset::new(0),
             order_by: Some("id"),
             order_dir: None,
+            extra_binds: HashMap::new(),
         };
 
         let query = opts.build("user", &["id", "name"]);
@@ -259,10 +332,11 @@ mod tests {
         let opts = QueryOptions {
             filters: Filters(Box::new([])),
             expansions: &[],
-            limit: Some(10),
-            offset: Some(0),
+            limit: Limit::new(10),
+            offset: Offset::new(0),
             order_by: Some("id"),
             order_dir: Some(OrderDir::Desc),
+            extra_binds: HashMap::new(),
         };
 
         let query = opts.build("user", &["id", "name"]);
@@ -282,10 +356,11 @@ mod tests {
         let opts = QueryOptions {
             filters: Filters(Box::new([])),
             expansions: &[],
-            limit: Some(10),
-            offset: Some(0),
+            limit: Limit::new(10),
+            offset: Offset::new(0),
             order_by: Some("id"),
             order_dir: Some(OrderDir::Asc),
+            extra_binds: HashMap::new(),
         };
 
         let query = opts.build("user", &["id", "name"]);
@@ -312,10 +387,11 @@ mod tests {
                 ("day_of_birth".into(), (Operator::Le, "10".into())),
             ])),
             expansions: &[],
-            limit: Some(10),
-            offset: Some(0),
+            limit: Limit::new(10),
+            offset: Offset::new(0),
             order_by: Some("id"),
             order_dir: Some(OrderDir::Asc),
+            extra_binds: HashMap::new(),
         };
 
         let query = opts.build("user", &["id", "name"]);
@@ -343,10 +419,11 @@ mod tests {
                 ("is_active".into(), (Operator::Eq, true.into())),
             ])),
             expansions: &[],
-            limit: Some(10),
-            offset: Some(0),
+            limit: Limit::new(10),
+            offset: Offset::new(0),
             order_by: Some("id"),
             order_dir: Some(OrderDir::Asc),
+            extra_binds: HashMap::new(),
         };
 
         let query = opts.build("user", &["id", "name"]);
@@ -374,10 +451,11 @@ mod tests {
         let opts = QueryOptions {
             filters: Filters(Box::new([])),
             expansions: &[("purchases", "->purchased.out")],
-            limit: Some(10),
-            offset: Some(0),
+            limit: Limit::new(10),
+            offset: Offset::new(0),
             order_by: Some("id"),
             order_dir: Some(OrderDir::Asc),
+            extra_binds: HashMap::new(),
         };
 
         let query = opts.build("user", &["id", "name"]);
@@ -404,6 +482,7 @@ mod tests {
             offset: None,
             order_by: None,
             order_dir: None,
+            extra_binds: HashMap::new(),
         }
         .build("orders", &["*"]);
 
@@ -413,10 +492,11 @@ mod tests {
                 ("purchases", "->purchased.out"),
                 ("orders", orders_query.0.as_ref()),
             ],
-            limit: Some(10),
-            offset: Some(0),
+            limit: Limit::new(10),
+            offset: Offset::new(0),
             order_by: Some("id"),
             order_dir: Some(OrderDir::Asc),
+            extra_binds: HashMap::new(),
         };
 
         let query = opts.build("user", &["id", "name"]);
@@ -435,6 +515,2420 @@ mod tests {
             .unwrap();
     }
 
+    #[tokio::test]
+    async fn it_scopes_expansions_to_the_parent() {
+        let orders_query = QueryOptions {
+            filters: Filters(Box::from([(
+                "status".into(),
+                (Operator::Eq, "paid".into()),
+            )])),
+            expansions: &[],
+            limit: None,
+            offset: None,
+            order_by: None,
+            order_dir: None,
+            extra_binds: HashMap::new(),
+        }
+        .build_expansion("orders", &["*"], "user", &BindNaming::default())
+        .unwrap();
+
+        assert_eq!(
+            orders_query.0.as_ref(),
+            "SELECT * FROM orders WHERE status = $status AND user = $parent.id"
+        );
+        assert_eq!(orders_query.1, [("status".into(), "paid".into())].into());
+
+        let opts = QueryOptions {
+            filters: Filters::from(vec![("status", "active")]),
+            expansions: &[("orders", orders_query.0.as_ref())],
+            limit: Limit::new(10),
+            offset: Offset::new(0),
+            order_by: Some("id"),
+            order_dir: Some(OrderDir::Asc),
+            extra_binds: orders_query.1,
+        };
+
+        let query = opts.build("user", &["id", "name"]);
+
+        // The parent's own `status` is named around the expansion's
+        assert_eq!(
+            query.0.as_ref(),
+            "SELECT id,name,(SELECT * FROM orders WHERE status = $status AND user = $parent.id) AS orders FROM user WHERE status = $status__1 ORDER BY id ASC LIMIT 10 START 0"
+        );
+        assert_eq!(query.1.get("status"), Some(&"paid".into()));
+        assert_eq!(query.1.get("status__1"), Some(&"active".into()));
+
+        let db = set_up_db().await;
+
+        db.query(query.0.as_ref()).bind(query.1).await.unwrap();
+
+        assert_eq!(
+            QueryOptions::new().build_expansion("orders", &["*"], "user-id", &BindNaming::new()),
+            Err(ExpansionError::InvalidForeignKey("user-id".into()))
+        );
+    }
+
+    #[tokio::test]
+    async fn it_names_the_binds_of_sibling_expansions_apart() {
+        let expansion = |status: &str, prefix: &str| {
+            QueryOptions {
+                filters: Filters::from(vec![("status", status)]),
+                ..QueryOptions::new()
+            }
+            .build_expansion(
+                "orders",
+                &["*"],
+                "user",
+                &BindNaming {
+                    prefix: prefix.into(),
+                    ..BindNaming::new()
+                },
+            )
+            .unwrap()
+        };
+
+        let (paid, paid_binds) = expansion("paid", "paid_");
+        let (open, open_binds) = expansion("open", "open_");
+
+        let mut extra_binds = paid_binds;
+        extra_binds.extend(open_binds);
+
+        let query = QueryOptions {
+            expansions: &[("paid", paid.as_ref()), ("open", open.as_ref())],
+            extra_binds,
+            ..QueryOptions::new()
+        }
+        .build("user", &["id"]);
+
+        assert_eq!(
+            query.0.as_ref(),
+            "SELECT id,(SELECT * FROM orders WHERE status = $paid_status AND user = $parent.id) AS paid,(SELECT * FROM orders WHERE status = $open_status AND user = $parent.id) AS open FROM user"
+        );
+        assert_eq!(query.1.get("paid_status"), Some(&"paid".into()));
+        assert_eq!(query.1.get("open_status"), Some(&"open".into()));
+
+        let db = set_up_db().await;
+
+        db.query("DEFINE TABLE orders SCHEMALESS")
+            .query("CREATE user:1 SET name = 'tester'")
+            .query("CREATE orders SET user = user:1, status = 'paid'")
+            .await
+            .unwrap()
+            .check()
+            .unwrap();
+
+        let mut response = db.query(query.0.as_ref()).bind(query.1).await.unwrap();
+        let users: Vec<serde_json::Value> = response.take(0).unwrap();
+
+        assert_eq!(users[0]["paid"].as_array().map(Vec::len), Some(1));
+        assert_eq!(users[0]["open"].as_array().map(Vec::len), Some(0));
+    }
+
+    #[tokio::test]
+    async fn it_paginates() {
+        let query = QueryOptions::new()
+            .paginate(
+                Pagination::Page {
+                    page: 3,
+                    per_page: 20,
+                },
+                100,
+            )
+            .unwrap()
+            .build("user", &["id", "name"]);
+
+        assert_eq!(
+            query.0.as_ref(),
+            "SELECT id,name FROM user LIMIT 20 START 40"
+        );
+
+        let db = set_up_db().await;
+
+        db.query(query.0.as_ref()).await.unwrap();
+
+        let pagination: Pagination =
+            serde_json::from_str(r#"{ "limit": 10, "offset": 5 }"#).unwrap();
+
+        assert_eq!(pagination.limit_offset(100), Ok((10, 5)));
+        assert_eq!(pagination.total_pages(21), 3);
+        assert_eq!(
+            serde_json::from_str::<Pagination>(r#"{ "page": 2, "per_page": 10 }"#).unwrap(),
+            Pagination::Page {
+                page: 2,
+                per_page: 10
+            }
+        );
+
+        assert_eq!(
+            Pagination::Page {
+                page: 0,
+                per_page: 10
+            }
+            .limit_offset(100),
+            Err(PaginationError::PageZero)
+        );
+        assert_eq!(
+            Pagination::Offset {
+                limit: 0,
+                offset: 0
+            }
+            .limit_offset(100),
+            Err(PaginationError::EmptyPage)
+        );
+        assert_eq!(
+            Pagination::Offset {
+                limit: 500,
+                offset: 0
+            }
+            .limit_offset(100),
+            Err(PaginationError::TooLarge {
+                per_page: 500,
+                max: 100
+            })
+        );
+        assert_eq!(
+            Pagination::Page {
+                page: usize::MAX,
+                per_page: 10
+            }
+            .limit_offset(100),
+            Err(PaginationError::Overflow)
+        );
+    }
+
+    #[tokio::test]
+    async fn it_counts_in_the_same_round_trip() {
+        let db = set_up_db().await;
+
+        db.query(
+            r#"
+            CREATE user:1 SET name = "a";
+            CREATE user:2 SET name = "b";
+            CREATE user:3 SET name = "b";
+            CREATE user:4 SET name = "b";
+        "#,
+        )
+        .await
+        .unwrap();
+
+        let pagination = Pagination::Page {
+            page: 1,
+            per_page: 2,
+        };
+
+        let query = QueryOptions {
+            filters: Filters(Box::from([("name".into(), (Operator::Eq, "b".into()))])),
+            ..QueryOptions::new()
+        }
+        .paginate(pagination, 100)
+        .unwrap()
+        .build_with_count("user", &["name"]);
+
+        assert_eq!(
+            query.0.as_ref(),
+            "SELECT name FROM user WHERE name = $name LIMIT 2 START 0; SELECT count() AS total FROM user WHERE name = $name GROUP ALL"
+        );
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct TestValue {
+            name: String,
+        }
+
+        let mut response = db.query(query.0.as_ref()).bind(query.1).await.unwrap();
+        let (results, total) = take_with_count::<TestValue>(&mut response).unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(total, 3);
+        assert_eq!(pagination.total_pages(total), 2);
+
+        let query = QueryOptions {
+            filters: Filters(Box::from([("name".into(), (Operator::Eq, "c".into()))])),
+            ..QueryOptions::new()
+        }
+        .build_with_count("user", &["name"]);
+
+        let mut response = db.query(query.0.as_ref()).bind(query.1).await.unwrap();
+        let (results, total) = take_with_count::<TestValue>(&mut response).unwrap();
+
+        assert!(results.is_empty());
+        assert_eq!(total, 0);
+    }
+
+    #[tokio::test]
+    async fn it_builds_json_patches() {
+        let db = set_up_db().await;
+
+        db.query(
+            r#"
+            DEFINE TABLE product;
+
+            CREATE product:1 CONTENT {
+                name: "shirt",
+                stock: 5,
+            };
+        "#,
+        )
+        .await
+        .unwrap();
+
+        let query = PatchOptions::new()
+            .test("/name", "shirt")
+            .replace("/stock", 4)
+            .add("/color", "blue")
+            .remove("/name")
+            .build("product:1")
+            .unwrap();
+
+        assert_eq!(query.0.as_ref(), "UPDATE product:1 PATCH $ops");
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct TestValue {
+            name: Option<String>,
+            stock: i32,
+            color: Option<String>,
+        }
+
+        let mut response = db.query(query.0.as_ref()).bind(query.1).await.unwrap();
+        let result: Option<TestValue> = response.take(0).unwrap();
+
+        assert_eq!(
+            result,
+            Some(TestValue {
+                name: None,
+                stock: 4,
+                color: Some("blue".into()),
+            })
+        );
+
+        // A failed test rejects the whole patch
+        let query = PatchOptions::new()
+            .test("/stock", 5)
+            .replace("/stock", 3)
+            .build("product:1")
+            .unwrap();
+
+        let mut response = db.query(query.0.as_ref()).bind(query.1).await.unwrap();
+
+        assert!(response.take::<Option<TestValue>>(0).is_err());
+
+        assert_eq!(
+            PatchOptions::new().remove("name").build("product:1"),
+            Err(PatchError::InvalidPath("name".into()))
+        );
+        assert_eq!(
+            PatchOptions::new()
+                .remove("/name")
+                .build("product:1; DELETE product"),
+            Err(PatchError::InvalidTarget(
+                "product:1; DELETE product".into()
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn it_parses_returned_diffs() {
+        let db = set_up_db().await;
+
+        db.query(
+            r#"
+            DEFINE TABLE product;
+
+            CREATE product:1 CONTENT {
+                name: "shirt",
+                stock: 5,
+            };
+        "#,
+        )
+        .await
+        .unwrap();
+
+        let query = PatchOptions::new()
+            .replace("/stock", 4)
+            .add("/color", "blue")
+            .return_diff()
+            .build("product:1")
+            .unwrap();
+
+        assert_eq!(query.0.as_ref(), "UPDATE product:1 PATCH $ops RETURN DIFF");
+
+        let mut response = db.query(query.0.as_ref()).bind(query.1).await.unwrap();
+        let diffs = take_diffs(&mut response, 0).unwrap();
+
+        assert_eq!(
+            diffs,
+            vec![Diff(vec![
+                DiffOp::Add {
+                    path: "/color".into(),
+                    value: "blue".into(),
+                },
+                DiffOp::Replace {
+                    path: "/stock".into(),
+                    value: 4.into(),
+                },
+            ])]
+        );
+    }
+
+    #[tokio::test]
+    async fn it_kills_live_queries() {
+        let db = set_up_db().await;
+
+        let mut response = db.query("LIVE SELECT * FROM user").await.unwrap();
+        let id: Option<surrealdb::sql::Uuid> = response.take(0).unwrap();
+        let id = LiveQueryId::from(id.unwrap());
+
+        assert_eq!(id.to_string().parse::<LiveQueryId>(), Ok(id));
+
+        let query = KillOptions::new(id).build();
+
+        assert_eq!(query.0.as_ref(), "KILL $id");
+
+        db.query(query.0.as_ref())
+            .bind(query.1)
+            .await
+            .unwrap()
+            .check()
+            .unwrap();
+
+        assert_eq!(
+            "1; REMOVE TABLE user".parse::<LiveQueryId>(),
+            Err(InvalidLiveQueryId("1; REMOVE TABLE user".into()))
+        );
+    }
+
+    #[tokio::test]
+    async fn it_defines_users() {
+        let query = DefineUserOptions {
+            name: "orders_service",
+            level: UserLevel::Database,
+            credential: Credential::Password("it's \"secret\"".into()),
+            roles: &[UserRole::Editor, UserRole::Viewer],
+        }
+        .build()
+        .unwrap();
+
+        assert_eq!(
+            query.as_ref(),
+            r#"DEFINE USER orders_service ON DATABASE PASSWORD "it's \"secret\"" ROLES EDITOR, VIEWER"#
+        );
+
+        let db = set_up_db().await;
+
+        db.query(query.as_ref()).await.unwrap().check().unwrap();
+
+        let query = remove_user("orders_service", UserLevel::Database).unwrap();
+
+        assert_eq!(query.as_ref(), "REMOVE USER orders_service ON DATABASE");
+
+        db.query(query.as_ref()).await.unwrap().check().unwrap();
+
+        assert_eq!(
+            DefineUserOptions {
+                name: "admin ON ROOT PASSWORD 'x' ROLES OWNER; --",
+                level: UserLevel::Database,
+                credential: Credential::Passhash("hash".into()),
+                roles: &[UserRole::Viewer],
+            }
+            .build(),
+            Err(DefineUserError::InvalidName(
+                "admin ON ROOT PASSWORD 'x' ROLES OWNER; --".into()
+            ))
+        );
+        assert_eq!(
+            DefineUserOptions {
+                name: "viewer",
+                level: UserLevel::Root,
+                credential: Credential::Passhash("hash".into()),
+                roles: &[],
+            }
+            .build(),
+            Err(DefineUserError::NoRoles)
+        );
+    }
+
+    #[tokio::test]
+    async fn it_signs_up_and_in_to_scopes() {
+        let query = DefineScopeOptions {
+            name: "account",
+            table: "customer",
+            identifier_field: "email",
+            password_field: "pass",
+            session: "24h",
+        }
+        .build()
+        .unwrap();
+
+        assert_eq!(
+            query.as_ref(),
+            "DEFINE SCOPE account SESSION 24h SIGNUP (CREATE customer SET email = $identifier, pass = crypto::argon2::generate($password)) SIGNIN (SELECT * FROM customer WHERE email = $identifier AND crypto::argon2::compare(pass, $password))"
+        );
+
+        let db = set_up_db().await;
+
+        db.query("DEFINE TABLE customer SCHEMALESS")
+            .query(query.as_ref())
+            .await
+            .unwrap()
+            .check()
+            .unwrap();
+
+        let credentials = ScopeCredentials {
+            identifier: "tester@example.com",
+            password: "hunter2",
+        };
+
+        db.signup(credentials.clone().scope("test", "test", "account"))
+            .await
+            .unwrap();
+        db.signin(credentials.scope("test", "test", "account"))
+            .await
+            .unwrap();
+
+        let error = db
+            .signin(
+                ScopeCredentials {
+                    identifier: "tester@example.com",
+                    password: "wrong",
+                }
+                .scope("test", "test", "account"),
+            )
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            ScopeAuthError::from_surrealdb(&error),
+            Some(ScopeAuthError::InvalidCredentials)
+        );
+
+        let error = db
+            .signin(
+                ScopeCredentials {
+                    identifier: "tester@example.com",
+                    password: "hunter2",
+                }
+                .scope("test", "test", "missing"),
+            )
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            ScopeAuthError::from_surrealdb(&error),
+            Some(ScopeAuthError::ScopeNotFound(None))
+        );
+
+        assert_eq!(
+            DefineScopeOptions {
+                name: "account",
+                table: "customer; REMOVE TABLE customer",
+                identifier_field: "email",
+                password_field: "pass",
+                session: "24h",
+            }
+            .build(),
+            Err(DefineScopeError::InvalidIdent(
+                "customer; REMOVE TABLE customer".into()
+            ))
+        );
+        assert_eq!(
+            DefineScopeOptions {
+                name: "account",
+                table: "customer",
+                identifier_field: "email",
+                password_field: "pass",
+                session: "24h SIGNIN (true)",
+            }
+            .build(),
+            Err(DefineScopeError::InvalidDuration(
+                "24h SIGNIN (true)".into()
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn it_filters_by_session_params() {
+        let owner = SessionParam::new("$auth.id").unwrap();
+
+        let opts = QueryOptions {
+            filters: Filters(Box::from([
+                ("owner".into(), (Operator::Eq, owner.clone().into())),
+                ("name".into(), (Operator::Eq, "tester".into())),
+            ])),
+            ..QueryOptions::new()
+        };
+
+        let query = opts.build(
+            "user",
+            &["id", owner.projection("current_user").unwrap().as_ref()],
+        );
+
+        assert_eq!(
+            query.0.as_ref(),
+            "SELECT id,$auth.id AS current_user FROM user WHERE name = $name AND owner = $auth.id"
+        );
+        assert_eq!(query.1, [("name".into(), "tester".into())].into());
+
+        let db = set_up_db().await;
+
+        db.query(query.0.as_ref()).bind(query.1).await.unwrap();
+
+        assert_eq!(SessionParam::new("$auth.id; DELETE user"), None);
+        assert_eq!(SessionParam::new("$name"), None);
+        assert_eq!(owner.projection("a; DELETE user"), None);
+    }
+
+    #[tokio::test]
+    async fn it_wraps_filter_keys_in_whitelisted_functions() {
+        let opts = QueryOptions {
+            filters: Filters(Box::from([
+                (
+                    "string::lowercase(email)".into(),
+                    (Operator::Eq, "tester@example.com".into()),
+                ),
+                (
+                    "time::floor(created_at, 1d)".into(),
+                    (Operator::Eq, "2024-03-15T00:00:00Z".into()),
+                ),
+                (
+                    "time::group( created_at,'day' )".into(),
+                    (Operator::Ne, "2024-03-16T00:00:00Z".into()),
+                ),
+            ])),
+            ..QueryOptions::new()
+        };
+
+        let query = opts.build("user", &["id", "name"]);
+
+        assert_eq!(
+            query.0.as_ref(),
+            "SELECT id,name FROM user WHERE string::lowercase(email) = $string_lowercase_email AND time::floor(created_at, 1d) = $time_floor_created_at_1d AND time::group(created_at, 'day') != $time_group_created_at_day"
+        );
+        assert_eq!(
+            query.1,
+            [
+                ("string_lowercase_email".into(), "tester@example.com".into()),
+                (
+                    "time_floor_created_at_1d".into(),
+                    "2024-03-15T00:00:00Z".into()
+                ),
+                (
+                    "time_group_created_at_day".into(),
+                    "2024-03-16T00:00:00Z".into()
+                ),
+            ]
+            .into()
+        );
+
+        let db = set_up_db().await;
+
+        db.query(query.0.as_ref()).bind(query.1).await.unwrap();
+
+        // Functions off the whitelist and invalid arguments fall back to the plain key sanitizing
+        let opts = QueryOptions {
+            filters: Filters(Box::from([
+                ("crypto::md5(email)".into(), (Operator::Eq, "a".into())),
+                (
+                    "time::floor(created_at, 1d); DELETE user)".into(),
+                    (Operator::Eq, "b".into()),
+                ),
+            ])),
+            ..QueryOptions::new()
+        };
+
+        let query = opts.build("user", &["id"]);
+
+        assert_eq!(
+            query.0.as_ref(),
+            "SELECT id FROM user WHERE crypto = $crypto AND time = $time"
+        );
+    }
+
+    #[tokio::test]
+    async fn it_allows_arithmetic_in_filter_keys() {
+        let opts = QueryOptions {
+            filters: Filters(Box::from([
+                ("price*quantity".into(), (Operator::Ge, 100.into())),
+                ("stock - 10".into(), (Operator::Gt, 0.into())),
+                ("price * 1.5".into(), (Operator::Lt, 30.into())),
+            ])),
+            ..QueryOptions::new()
+        };
+
+        let query = opts.build("orders", &["*"]);
+
+        assert_eq!(
+            query.0.as_ref(),
+            "SELECT * FROM orders WHERE price * 1.5 < $price_times_1_5 AND price * quantity >= $price_times_quantity AND stock - 10 > $stock_minus_10"
+        );
+        assert_eq!(
+            query.1,
+            [
+                ("price_times_quantity".into(), 100.into()),
+                ("stock_minus_10".into(), 0.into()),
+                ("price_times_1_5".into(), 30.into()),
+            ]
+            .into()
+        );
+
+        let db = set_up_db().await;
+
+        db.query(query.0.as_ref()).bind(query.1).await.unwrap();
+
+        let opts = QueryOptions {
+            filters: Filters(Box::from([(
+                "price * (SELECT * FROM user)".into(),
+                (Operator::Ge, 100.into()),
+            )])),
+            ..QueryOptions::new()
+        };
+
+        let query = opts.build("orders", &["*"]);
+
+        assert_eq!(
+            query.0.as_ref(),
+            "SELECT * FROM orders WHERE price >= $price"
+        );
+    }
+
+    #[tokio::test]
+    async fn it_accepts_raw_filters_with_their_own_binds() {
+        let opts = QueryOptions {
+            filters: Filters(Box::from([
+                ("name".into(), (Operator::Eq, "tester".into())),
+                Filters::raw(
+                    "string::len(name) >= $name AND string::len(name) <= $max",
+                    vec![("name", 3), ("max", 10)],
+                )
+                .unwrap(),
+                Filters::raw("$name != $max", vec![("$name", "a"), ("$max", "b")]).unwrap(),
+            ])),
+            ..QueryOptions::new()
+        };
+
+        let query = opts.build("user", &["id", "name"]);
+
+        assert_eq!(
+            query.0.as_ref(),
+            "SELECT id,name FROM user WHERE ($name__2 != $max__1) AND (string::len(name) >= $name__1 AND string::len(name) <= $max) AND name = $name"
+        );
+        assert_eq!(
+            query.1,
+            [
+                ("name".into(), "tester".into()),
+                ("name__1".into(), 3.into()),
+                ("max".into(), 10.into()),
+                ("name__2".into(), "a".into()),
+                ("max__1".into(), "b".into()),
+            ]
+            .into()
+        );
+
+        let db = set_up_db().await;
+
+        db.query(query.0.as_ref()).bind(query.1).await.unwrap();
+
+        assert_eq!(
+            Filters::raw("$id; DELETE user", vec![("id; DELETE user", "a")]).err(),
+            Some(InvalidBindName("id; DELETE user".into()))
+        );
+    }
+
+    #[tokio::test]
+    async fn it_expands_filter_presets() {
+        let presets = FilterPresets::new()
+            .register("active", vec![("is_active", true)])
+            .register(
+                "adults",
+                vec![("age", (Operator::Ge, 18)), ("age", (Operator::Lt, 130))],
+            );
+
+        #[derive(Deserialize)]
+        struct Request {
+            presets: Vec<String>,
+        }
+
+        let request: Request =
+            serde_json::from_str(r#"{ "presets": ["active", "adults"] }"#).unwrap();
+
+        let query = QueryOptions {
+            filters: vec![("name", "tester")].into(),
+            ..QueryOptions::new()
+        }
+        .with_presets(&presets, &request.presets)
+        .unwrap()
+        .build("user", &["id", "name"]);
+
+        assert_eq!(
+            query.0.as_ref(),
+            "SELECT id,name FROM user WHERE age < $age__1 AND age >= $age AND is_active = $is_active AND name = $name"
+        );
+
+        let db = set_up_db().await;
+
+        db.query(query.0.as_ref()).bind(query.1).await.unwrap();
+
+        assert_eq!(
+            presets.expand(&["active", "everything"]).err(),
+            Some(UnknownPreset("everything".into()))
+        );
+    }
+
+    #[tokio::test]
+    async fn it_encodes_query_options() {
+        let opts = QueryOptions {
+            filters: Filters(Box::from([
+                ("name".into(), (Operator::Eq, "tester / ü".into())),
+                ("age".into(), (Operator::Gt, 20.into())),
+                ("price".into(), (Operator::Le, Decimal::new(1999, 2).into())),
+                ("is_active".into(), (Operator::Eq, true.into())),
+                (
+                    "tags".into(),
+                    (Operator::ContainsAny, vec!["tag1", "tag2"].into()),
+                ),
+            ])),
+            expansions: &[],
+            limit: Limit::new(10),
+            offset: Offset::new(20),
+            order_by: Some("name"),
+            order_dir: Some(OrderDir::Desc),
+            extra_binds: HashMap::new(),
+        };
+
+        let encoded = opts.encode().unwrap();
+
+        assert!(encoded.starts_with("1."));
+        assert!(encoded
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c)));
+
+        let decoded = decode(&encoded).unwrap();
+
+        assert_eq!(
+            decoded.options().build("user", &["*"]),
+            opts.build("user", &["*"])
+        );
+
+        assert_eq!(
+            decode("2.e30").err(),
+            Some(EncodingError::UnknownVersion("2".into()))
+        );
+        assert_eq!(decode("1.e30").err(), Some(EncodingError::Malformed));
+
+        let unsafe_opts = QueryOptions {
+            filters: Filters(Box::from([(
+                "name".into(),
+                (Operator::Eq, FilterValue::Unsafe("$auth.name".into())),
+            )])),
+            ..QueryOptions::new()
+        };
+
+        assert_eq!(
+            unsafe_opts.encode(),
+            Err(EncodingError::Unencodable("name".into()))
+        );
+    }
+
+    #[tokio::test]
+    async fn it_enforces_complexity_limits() {
+        let limits = ComplexityLimits {
+            max_filters: 2,
+            max_expansions: 1,
+            max_depth: 2,
+            allow_parallel: false,
+        };
+
+        let filters = Filters::from(vec![("name", "a"), ("name", "b"), ("name", "c")]);
+
+        assert_eq!(
+            QueryOptions {
+                filters,
+                ..QueryOptions::new()
+            }
+            .try_build("user", &["*"], &limits)
+            .err(),
+            Some(ComplexityError::TooManyFilters { count: 3, max: 2 })
+        );
+
+        assert_eq!(
+            QueryOptions {
+                expansions: &[("a", "SELECT * FROM user"), ("b", "SELECT * FROM user")],
+                ..QueryOptions::new()
+            }
+            .try_build("user", &["*"], &limits)
+            .err(),
+            Some(ComplexityError::TooManyExpansions { count: 2, max: 1 })
+        );
+
+        assert_eq!(
+            QueryOptions {
+                expansions: &[("a", "SELECT * FROM (SELECT * FROM (SELECT * FROM user))")],
+                ..QueryOptions::new()
+            }
+            .try_build("user", &["*"], &limits)
+            .err(),
+            Some(ComplexityError::TooDeep { depth: 3, max: 2 })
+        );
+
+        assert_eq!(
+            QueryOptions {
+                expansions: &[("a", "SELECT * FROM user parallel")],
+                ..QueryOptions::new()
+            }
+            .try_build("user", &["*"], &limits)
+            .err(),
+            Some(ComplexityError::Parallel)
+        );
+
+        let query = QueryOptions {
+            filters: Filters::from(vec![("name", "tester")]),
+            expansions: &[("friends", "SELECT * FROM (SELECT * FROM user)")],
+            ..QueryOptions::new()
+        }
+        .try_build("user", &["*"], &limits)
+        .unwrap();
+
+        assert_eq!(
+            query.0.as_ref(),
+            "SELECT *,(SELECT * FROM (SELECT * FROM user)) AS friends FROM user WHERE name = $name"
+        );
+
+        let db = set_up_db().await;
+
+        db.query(query.0.as_ref()).bind(query.1).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn it_builds_grouped_queries() {
+        let query = QueryOptions {
+            filters: Filters::from(vec![("name", "tester")]),
+            limit: Limit::new(10),
+            order_by: Some("name"),
+            ..QueryOptions::new()
+        }
+        .build_grouped("user", &["count() AS total"], Group::All)
+        .unwrap();
+
+        assert_eq!(
+            query.0.as_ref(),
+            "SELECT count() AS total FROM user WHERE name = $name GROUP ALL"
+        );
+
+        let db = set_up_db().await;
+
+        db.query("CREATE user SET name = 'tester'; CREATE user SET name = 'tester'")
+            .await
+            .unwrap();
+
+        let total: Option<usize> = db
+            .query(query.0.as_ref())
+            .bind(query.1)
+            .await
+            .unwrap()
+            .take((0, "total"))
+            .unwrap();
+
+        assert_eq!(total, Some(2));
+
+        let query = QueryOptions {
+            order_by: Some("name"),
+            limit: Limit::new(10),
+            ..QueryOptions::new()
+        }
+        .build_grouped("user", &["name", "count() AS total"], Group::By(&["name"]))
+        .unwrap();
+
+        assert_eq!(
+            query.0.as_ref(),
+            "SELECT name,count() AS total FROM user GROUP BY name ORDER BY name LIMIT 10"
+        );
+
+        db.query(query.0.as_ref()).bind(query.1).await.unwrap();
+
+        assert_eq!(
+            QueryOptions::new().build_grouped("user", &["name"], Group::By(&["name", "tag-id"])),
+            Err(GroupError::InvalidField("tag-id".into()))
+        );
+        assert_eq!(
+            QueryOptions::new().build_grouped("user", &["name"], Group::By(&[])),
+            Err(GroupError::NoFields)
+        );
+    }
+
+    #[tokio::test]
+    async fn it_builds_aggregate_projections() {
+        let columns = [
+            "status".into(),
+            Aggregate::Count.projection("orders").unwrap(),
+            Aggregate::CountField("paid").projection("paid").unwrap(),
+            Aggregate::Group("customer")
+                .projection("customers")
+                .unwrap(),
+            Aggregate::Sum("total").projection("revenue").unwrap(),
+            Aggregate::Mean("total").projection("average").unwrap(),
+            Aggregate::Min("total").projection("smallest").unwrap(),
+            Aggregate::Max("total").projection("largest").unwrap(),
+        ];
+        let columns = columns.iter().map(AsRef::as_ref).collect::<Vec<_>>();
+
+        let query = QueryOptions {
+            order_by: Some("status"),
+            ..QueryOptions::new()
+        }
+        .build_grouped("purchase", &columns, Group::By(&["status"]))
+        .unwrap();
+
+        assert_eq!(
+            query.0.as_ref(),
+            "SELECT status,count() AS orders,count(paid) AS paid,array::group(customer) AS customers,math::sum(total) AS revenue,math::mean(total) AS average,math::min(total) AS smallest,math::max(total) AS largest FROM purchase GROUP BY status ORDER BY status"
+        );
+
+        assert_eq!(
+            Aggregate::Sum("total) AS x, (1").projection("revenue"),
+            None
+        );
+        assert_eq!(Aggregate::Count.projection("total, name"), None);
+
+        let db = set_up_db().await;
+
+        db.query("DEFINE TABLE purchase SCHEMALESS")
+            .query("CREATE purchase SET status = 'open', paid = false, customer = 'a', total = 10")
+            .query("CREATE purchase SET status = 'open', paid = true, customer = 'b', total = 20")
+            .query("CREATE purchase SET status = 'done', paid = true, customer = 'a', total = 5")
+            .await
+            .unwrap();
+
+        let revenue: Vec<i64> = db
+            .query(query.0.as_ref())
+            .bind(query.1)
+            .await
+            .unwrap()
+            .take((0, "revenue"))
+            .unwrap();
+
+        assert_eq!(revenue, vec![5, 30]);
+    }
+
+    #[tokio::test]
+    async fn it_iterates_in_chunks() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct TestValue {
+            name: String,
+        }
+
+        let db = set_up_db().await;
+
+        for i in 0..5 {
+            db.query(format!("CREATE user:{} SET name = 'tester'", i))
+                .await
+                .unwrap();
+        }
+
+        db.query("CREATE user SET name = 'other'").await.unwrap();
+
+        let mut chunks = QueryOptions {
+            filters: Filters::from(vec![("name", "tester")]),
+            limit: Limit::new(1),
+            ..QueryOptions::new()
+        }
+        .chunks(&db, "user", &["*"], 2);
+
+        let mut sizes = vec![];
+
+        while let Some(chunk) = chunks.next_chunk::<TestValue>().await.unwrap() {
+            sizes.push(chunk.len());
+        }
+
+        assert_eq!(sizes, vec![2, 2, 1]);
+        assert!(chunks.next_chunk::<TestValue>().await.unwrap().is_none());
+
+        let mut chunks = QueryOptions {
+            order_by: Some("name"),
+            ..QueryOptions::new()
+        }
+        .chunks(&db, "user", &["name"], 2);
+
+        let first = chunks.next_chunk::<TestValue>().await.unwrap().unwrap();
+
+        // Rows written before the cursor don't shift the chunks after it
+        db.query("CREATE type::thing('user', -1) SET name = 'tester'")
+            .await
+            .unwrap()
+            .check()
+            .unwrap();
+
+        let mut count = first.len();
+
+        while let Some(chunk) = chunks.next_chunk::<TestValue>().await.unwrap() {
+            count += chunk.len();
+        }
+
+        assert_eq!(count, 6);
+    }
+
+    #[tokio::test]
+    async fn it_streams_in_chunks() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct TestValue {
+            name: String,
+        }
+
+        let db = set_up_db().await;
+
+        for i in 0..5 {
+            db.query(format!("CREATE user:{} SET name = 'tester{}'", i, i))
+                .await
+                .unwrap();
+        }
+
+        let names = QueryOptions::new()
+            .chunks(&db, "user", &["*"], 2)
+            .into_stream::<TestValue>()
+            .map_ok(|value| value.name)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+
+        assert_eq!(
+            names,
+            vec!["tester0", "tester1", "tester2", "tester3", "tester4"]
+        );
+    }
+
+    #[tokio::test]
+    async fn it_exports_and_imports_tables() {
+        let query = QueryOptions {
+            filters: Filters::from(vec![("name", "tester")]),
+            order_by: Some("name"),
+            ..QueryOptions::new()
+        }
+        .build_export("user");
+
+        assert_eq!(
+            query.0.as_ref(),
+            "SELECT *,meta::id(id) AS id FROM user WHERE name = $name ORDER BY id"
+        );
+
+        let db = set_up_db().await;
+
+        db.query("CREATE user:1 SET name = 'tester'")
+            .query("CREATE user:abc SET name = 'tester'")
+            .query("CREATE user:2 SET name = 'other'")
+            .query("DEFINE TABLE user_copy SCHEMAFULL")
+            .query("DEFINE FIELD name ON TABLE user_copy TYPE string")
+            .await
+            .unwrap();
+
+        let rows: Vec<serde_json::Value> = db
+            .query(query.0.as_ref())
+            .bind(query.1)
+            .await
+            .unwrap()
+            .take(0)
+            .unwrap();
+
+        let ndjson = to_ndjson(&rows);
+
+        assert_eq!(ndjson.lines().count(), 2);
+
+        let rows = from_ndjson(&format!("{}\n", ndjson)).unwrap();
+        let queries = build_import("user_copy", &rows, 1);
+
+        assert_eq!(queries.len(), 2);
+        assert_eq!(queries[0].0.as_ref(), "INSERT INTO user_copy $rows");
+
+        for query in queries {
+            db.query(query.0.as_ref()).bind(query.1).await.unwrap();
+        }
+
+        let mut ids: Vec<String> = db
+            .query("SELECT VALUE type::string(id) FROM user_copy")
+            .await
+            .unwrap()
+            .take(0)
+            .unwrap();
+
+        ids.sort();
+
+        assert_eq!(ids, vec!["user_copy:1", "user_copy:abc"]);
+    }
+
+    #[tokio::test]
+    async fn it_cleans_up_tables() {
+        assert_eq!(
+            build_truncate(&["user", "post"]).unwrap().as_ref(),
+            "DELETE FROM user; DELETE FROM post"
+        );
+        assert_eq!(
+            build_recreate(&["user"], "DEFINE TABLE user SCHEMAFULL")
+                .unwrap()
+                .as_ref(),
+            "REMOVE TABLE user; DEFINE TABLE user SCHEMAFULL"
+        );
+        assert_eq!(
+            build_truncate(&["user; REMOVE DATABASE test"]),
+            Err(InvalidTable("user; REMOVE DATABASE test".into()))
+        );
+
+        let db = set_up_db().await;
+
+        db.query("CREATE user SET name = 'tester'").await.unwrap();
+
+        truncate(&db, &["user"]).await.unwrap();
+
+        let count: Vec<usize> = db
+            .query("SELECT VALUE count() FROM user GROUP ALL")
+            .await
+            .unwrap()
+            .take(0)
+            .unwrap();
+
+        assert!(count.is_empty());
+
+        db.query("DEFINE FIELD age ON TABLE user TYPE int")
+            .await
+            .unwrap();
+
+        recreate(
+            &db,
+            &["user"],
+            "DEFINE TABLE user SCHEMAFULL; DEFINE FIELD name ON TABLE user TYPE string",
+        )
+        .await
+        .unwrap();
+
+        // `age` is gone with the old definition, so a record without it is valid again
+        db.query("CREATE user SET name = 'tester'")
+            .await
+            .unwrap()
+            .check()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn it_switches_tenants() {
+        assert_eq!(
+            "acme; REMOVE NAMESPACE test".parse::<Namespace>(),
+            Err(InvalidTenantName("acme; REMOVE NAMESPACE test".into()))
+        );
+
+        let tenant = TenantContext::new("test".parse().unwrap(), "test".parse().unwrap())
+            .with_scope("name", "tester")
+            .unwrap();
+
+        let query = QueryOptions {
+            filters: Filters::from(vec![("name", "other")]),
+            ..QueryOptions::new()
+        }
+        .scoped(&tenant)
+        .build("user", &["*"]);
+
+        assert_eq!(
+            query.0.as_ref(),
+            "SELECT * FROM user WHERE name = $name AND name = $name__1"
+        );
+        assert_eq!(query.1.get("name"), Some(&"tester".into()));
+
+        let db = Surreal::new::<Mem>(Config::default()).await.unwrap();
+
+        tenant.use_on(&db).await.unwrap();
+
+        db.query("CREATE user SET name = 'tester'").await.unwrap();
+
+        let other: Database = "other".parse().unwrap();
+
+        TenantContext::new("test".parse().unwrap(), other)
+            .use_on(&db)
+            .await
+            .unwrap();
+
+        let names: Vec<String> = db
+            .query("SELECT VALUE name FROM user")
+            .await
+            .unwrap()
+            .take(0)
+            .unwrap();
+
+        assert!(names.is_empty());
+
+        for field in ["tenant-id", "", "$$"] {
+            assert_eq!(
+                tenant.clone().with_scope(field, "tester"),
+                Err(InvalidTenantName(field.into()))
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn it_classifies_queries() {
+        let query = QueryOptions {
+            filters: Filters::from(vec![("name", "DELETE user")]),
+            expansions: &[("posts", "SELECT * FROM post WHERE author = $parent.id")],
+            ..QueryOptions::new()
+        }
+        .build("user", &["*"]);
+
+        assert_eq!(query.kind(), QueryKind::Read);
+
+        let query = PatchOptions::new()
+            .replace("/name", "tester")
+            .build("user:1")
+            .unwrap();
+
+        assert_eq!(query.kind(), QueryKind::Write);
+
+        assert_eq!(
+            classify("SELECT *, (CREATE log SET at = time::now()) FROM user"),
+            QueryKind::Write
+        );
+        assert_eq!(
+            classify("SELECT * FROM user WHERE name = 'update' AND $delete"),
+            QueryKind::Read
+        );
+        assert_eq!(
+            classify("select * from user; delete user"),
+            QueryKind::Write
+        );
+    }
+
+    #[tokio::test]
+    async fn it_caches_reads_until_a_write() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct TestValue {
+            name: String,
+        }
+
+        let build = || {
+            QueryOptions {
+                filters: Filters::from(vec![("name", "tester")]),
+                ..QueryOptions::new()
+            }
+            .build("user", &["name"])
+        };
+
+        assert_eq!(
+            fingerprint(&build().0, &build().1),
+            fingerprint(&build().0, &build().1)
+        );
+        // Stable across builds, so keys can outlive the process
+        assert_eq!(
+            fingerprint(&build().0, &build().1),
+            fingerprint(
+                "SELECT name FROM user WHERE name = $name",
+                &HashMap::from([("name".into(), FilterValue::from("tester"))])
+            )
+        );
+        assert_eq!(
+            fingerprint::<FilterValue>("SELECT * FROM user", &HashMap::new()),
+            0xb4d9_8a1f_5e55_e61e
+        );
+
+        let session = CacheSession::new("test".parse().unwrap(), "test".parse().unwrap());
+        let key = |session: &CacheSession| session.key(&build().0, &build().1);
+
+        assert_ne!(key(&session), key(&session.clone().with_identity("user:1")));
+        assert_ne!(
+            key(&session),
+            key(&CacheSession::new(
+                "test".parse().unwrap(),
+                "other".parse().unwrap()
+            ))
+        );
+        assert_ne!(key(&session.clone().with_identity("")), key(&session));
+
+        let store = MemoryStore::new();
+
+        store.set(
+            key(&session),
+            "user",
+            surrealdb::sql::Value::None,
+            Duration::ZERO,
+        );
+        store.set(
+            key(&session.clone().with_identity("user:1")),
+            "user",
+            surrealdb::sql::Value::None,
+            Duration::from_secs(60),
+        );
+
+        // Storing an entry drops the expired ones, even if they're never read again
+        assert_eq!(store.len(), 1);
+
+        let db = set_up_db().await;
+        let executor = CachingExecutor::new(
+            &db,
+            session.clone(),
+            MemoryStore::new(),
+            Duration::from_secs(60),
+        );
+
+        db.query("CREATE user SET name = 'tester'").await.unwrap();
+
+        let users: Vec<TestValue> = executor.execute("user", &build()).await.unwrap();
+
+        assert_eq!(users.len(), 1);
+
+        // Written around the executor, so the cached result is stale
+        db.query("CREATE user SET name = 'tester'").await.unwrap();
+
+        let users: Vec<TestValue> = executor.execute("user", &build()).await.unwrap();
+
+        assert_eq!(users.len(), 1);
+
+        let write = PatchOptions::new()
+            .replace("/name", "tester")
+            .build("user")
+            .unwrap();
+
+        executor
+            .execute::<Vec<TestValue>, _>("user", &write)
+            .await
+            .unwrap();
+
+        assert!(executor.store().get(&key(&session)).is_none());
+
+        let users: Vec<TestValue> = executor.execute("user", &build()).await.unwrap();
+
+        assert_eq!(users.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn it_memoizes_identical_reads() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct TestValue {
+            name: String,
+        }
+
+        let build = || {
+            QueryOptions {
+                filters: Filters::from(vec![("name", "tester")]),
+                ..QueryOptions::new()
+            }
+            .build("user", &["name"])
+        };
+
+        let db = set_up_db().await;
+        let scope = MemoScope::new(&db);
+
+        db.query("CREATE user SET name = 'tester'").await.unwrap();
+
+        let (query, same_query) = (build(), build());
+        let (first, second) = futures::join!(
+            scope.execute::<Vec<TestValue>, _>(&query),
+            scope.execute::<Vec<TestValue>, _>(&same_query)
+        );
+
+        assert_eq!(first.unwrap(), second.unwrap());
+
+        db.query("CREATE user SET name = 'tester'").await.unwrap();
+
+        let users: Vec<TestValue> = scope.execute(&build()).await.unwrap();
+
+        assert_eq!(users.len(), 1);
+
+        let write = PatchOptions::new()
+            .replace("/name", "tester")
+            .build("user")
+            .unwrap();
+
+        scope.execute::<Vec<TestValue>, _>(&write).await.unwrap();
+
+        let users: Vec<TestValue> = scope.execute(&build()).await.unwrap();
+
+        assert_eq!(users.len(), 2);
+    }
+
+    #[cfg(feature = "otel")]
+    #[tokio::test]
+    async fn it_sanitizes_statements_for_spans() {
+        use crate::otel::{operation, sanitize_statement};
+
+        let query =
+            "SELECT * FROM user:1 WHERE name = 'tester' AND $name__1 AND age > 20.5 LIMIT 10";
+
+        assert_eq!(
+            sanitize_statement(query),
+            "SELECT * FROM user:? WHERE name = ? AND $name__1 AND age > ? LIMIT ?"
+        );
+        assert_eq!(operation(query), "SELECT");
+        assert_eq!(operation("  update user PATCH $ops"), "UPDATE");
+
+        for (query, sanitized) in [
+            (
+                "SELECT * FROM user:⟨jane@example.com⟩",
+                "SELECT * FROM user:?",
+            ),
+            ("SELECT * FROM user:`x`", "SELECT * FROM user:?"),
+            ("SELECT * FROM user:jane", "SELECT * FROM user:?"),
+            ("SELECT * FROM user:⟨jane's⟩", "SELECT * FROM user:?"),
+            ("SELECT * FROM `user`:jane", "SELECT * FROM `user`:?"),
+            (
+                "SELECT string::lowercase(email) FROM user WHERE time::floor(created_at, 1d) = $at",
+                "SELECT string::lowercase(email) FROM user WHERE time::floor(created_at, 1d) = $at",
+            ),
+        ] {
+            assert_eq!(sanitize_statement(query), sanitized);
+        }
+    }
+
+    #[tokio::test]
+    async fn it_names_binds_with_a_strategy() {
+        let filters = || {
+            Filters::from(vec![
+                ("created_at", (Operator::Gt, 1)),
+                ("created_at", (Operator::Lt, 5)),
+                ("created_by", (Operator::Eq, 2)),
+            ])
+        };
+
+        let naming = BindNaming {
+            prefix: "qb_".into(),
+            separator: "_".into(),
+            max_len: Some(10),
+            reserved: Box::from(["qb_created_1".into()]),
+            ..BindNaming::new()
+        };
+
+        let query = QueryOptions {
+            filters: filters(),
+            ..QueryOptions::new()
+        }
+        .build_named("user", &["*"], &naming)
+        .unwrap();
+
+        // `created_at` and `created_by` both cut to `qb_created`, and the reserved name is skipped
+        assert_eq!(
+            query.0.as_ref(),
+            "SELECT * FROM user WHERE created_at < $qb_created_2 AND created_at > $qb_created AND created_by = $qb_created_3"
+        );
+
+        let naming = BindNaming {
+            on_collision: CollisionPolicy::Error,
+            ..naming
+        };
+
+        assert_eq!(
+            QueryOptions {
+                filters: filters(),
+                ..QueryOptions::new()
+            }
+            .build_named("user", &["*"], &naming),
+            Err(BindCollision("qb_created_1".into()))
+        );
+
+        let db = set_up_db().await;
+
+        db.query(query.0.as_ref()).bind(query.1).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn it_filters_by_sdk_values() {
+        let meta = surrealdb::sql::value("{ kind: 'shop', tags: ['a', 'b'] }").unwrap();
+
+        let opts = QueryOptions {
+            filters: Filters::from(vec![(
+                "meta",
+                FilterValue::Escaped(FilterValueKind::Value(meta)),
+            )]),
+            ..QueryOptions::new()
+        };
+
+        assert_eq!(
+            opts.encode(),
+            Err(EncodingError::Unencodable("meta".into()))
+        );
+
+        let query = opts.build("place", &["meta"]);
+
+        assert_eq!(
+            query.0.as_ref(),
+            "SELECT meta FROM place WHERE meta = $meta"
+        );
+
+        let db = set_up_db().await;
+
+        db.query("DEFINE TABLE place SCHEMALESS")
+            .query("CREATE place SET meta = { kind: 'shop', tags: ['a', 'b'] }")
+            .query("CREATE place SET meta = { kind: 'cafe' }")
+            .await
+            .unwrap();
+
+        let metas: surrealdb::sql::Value = db
+            .query(query.0.as_ref())
+            .bind(query.1)
+            .await
+            .unwrap()
+            .take(0)
+            .unwrap();
+
+        assert_eq!(
+            metas,
+            surrealdb::sql::value("[{ meta: { kind: 'shop', tags: ['a', 'b'] } }]").unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn it_converts_sdk_values_into_filters() {
+        use surrealdb::sql::{value, Number, Strand, Thing, Value};
+
+        assert_eq!(
+            FilterValueKind::from(Value::from("tester")),
+            FilterValueKind::String("tester".into())
+        );
+        assert_eq!(
+            FilterValueKind::from(Number::Decimal(Decimal::new(15, 1))),
+            FilterValueKind::Decimal(Decimal::new(15, 1))
+        );
+        assert_eq!(
+            FilterValue::from(value("[1, 'a', true]").unwrap()),
+            FilterValue::EscapedList(Box::from([
+                FilterValueKind::Int(1),
+                FilterValueKind::String("a".into()),
+                FilterValueKind::Bool(true),
+            ]))
+        );
+
+        let db = set_up_db().await;
+
+        db.query("CREATE user:1 SET name = 'tester'")
+            .query("CREATE user:2 SET name = 'other'")
+            .await
+            .unwrap();
+
+        let ids: Vec<Thing> = db
+            .query("SELECT VALUE id FROM user WHERE name = 'tester'")
+            .await
+            .unwrap()
+            .take(0)
+            .unwrap();
+
+        let names: Vec<Strand> = db
+            .query("SELECT VALUE name FROM user WHERE name = 'tester'")
+            .await
+            .unwrap()
+            .take(0)
+            .unwrap();
+
+        let query = QueryOptions {
+            filters: Filters(Box::from([
+                ("id".into(), (Operator::Eq, ids[0].clone().into())),
+                ("name".into(), (Operator::Eq, names[0].clone().into())),
+            ])),
+            ..QueryOptions::new()
+        }
+        .build("user", &["name"]);
+
+        let found: Vec<String> = db
+            .query(query.0.as_ref())
+            .bind(query.1)
+            .await
+            .unwrap()
+            .take((0, "name"))
+            .unwrap();
+
+        assert_eq!(found, vec!["tester"]);
+    }
+
+    #[tokio::test]
+    async fn it_converts_binds_into_sdk_values() {
+        use surrealdb::sql::{Number, Value};
+
+        assert_eq!(
+            Value::from(FilterValueKind::UInt(u64::MAX)),
+            Value::Number(Number::Decimal(Decimal::from(u64::MAX)))
+        );
+        assert_eq!(
+            Value::try_from(FilterValue::Unsafe("$auth.id".into())),
+            Err(Unbindable(FilterValue::Unsafe("$auth.id".into())))
+        );
+
+        let query = QueryOptions {
+            filters: Filters(Box::from([
+                ("price".into(), (Operator::Eq, Decimal::new(1999, 2).into())),
+                ("age".into(), (Operator::Eq, 20u64.into())),
+                (
+                    "tags".into(),
+                    (Operator::ContainsAny, vec!["a", "b"].into()),
+                ),
+            ])),
+            ..QueryOptions::new()
+        }
+        .build("user", &["*"]);
+
+        let binds = sql_binds(query.1);
+
+        assert_eq!(
+            binds.get("price"),
+            Some(&Value::Number(Number::Decimal(Decimal::new(1999, 2))))
+        );
+        assert_eq!(binds.get("age"), Some(&Value::Number(Number::Int(20))));
+
+        let db = set_up_db().await;
+
+        let types: Vec<bool> = db
+            .query(
+                "RETURN [type::is::decimal($price), type::is::int($age), type::is::array($tags)]",
+            )
+            .bind(binds)
+            .await
+            .unwrap()
+            .take(0)
+            .unwrap();
+
+        assert_eq!(types, vec![true, true, true]);
+    }
+
+    #[tokio::test]
+    async fn it_merges_extra_binds() {
+        let query = QueryOptions {
+            filters: Filters(Box::from([
+                ("name".into(), (Operator::Eq, "tester".into())),
+                Filters::raw("name != $excluded OR $min > 0", vec![("min", 1)]).unwrap(),
+            ])),
+            expansions: &[(
+                "posts",
+                "SELECT * FROM post WHERE author = $parent.id LIMIT $min",
+            )],
+            extra_binds: HashMap::from([
+                ("name".into(), "taken".into()),
+                ("min".into(), 3.into()),
+                ("excluded".into(), "other".into()),
+            ]),
+            ..QueryOptions::new()
+        }
+        .build("user", &["*"]);
+
+        // The extra binds keep their names, the generated ones are named around them
+        assert_eq!(
+            query.0.as_ref(),
+            "SELECT *,(SELECT * FROM post WHERE author = $parent.id LIMIT $min) AS posts FROM user WHERE (name != $excluded OR $min__1 > 0) AND name = $name__1"
+        );
+        assert_eq!(query.1.get("name"), Some(&"taken".into()));
+        assert_eq!(query.1.get("name__1"), Some(&"tester".into()));
+        assert_eq!(query.1.get("min"), Some(&3.into()));
+        assert_eq!(query.1.get("min__1"), Some(&1.into()));
+
+        let strict = BindNaming {
+            on_collision: CollisionPolicy::Error,
+            ..BindNaming::new()
+        };
+
+        assert_eq!(
+            QueryOptions {
+                filters: Filters::from(vec![("name", "tester")]),
+                extra_binds: HashMap::from([("name".into(), "taken".into())]),
+                ..QueryOptions::new()
+            }
+            .build_named("user", &["*"], &strict),
+            Err(BindCollision("name".into()))
+        );
+
+        let db = set_up_db().await;
+
+        db.query("DEFINE TABLE post SCHEMALESS").await.unwrap();
+        db.query(query.0.as_ref()).bind(query.1).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn it_orders_by_paths_and_expansions() {
+        let db = set_up_db().await;
+
+        db.query("DEFINE TABLE post SCHEMALESS")
+            .query("CREATE user:1 SET name = 'b'; CREATE user:2 SET name = 'a'")
+            .query("CREATE post SET author = user:1, meta = { rank: 1 }")
+            .query("CREATE post SET author = user:2, meta = { rank: 2 }")
+            .query("CREATE post SET author = user:2, meta = { rank: 3 }")
+            .await
+            .unwrap();
+
+        let opts = QueryOptions {
+            order_by: Some("meta.rank"),
+            order_dir: Some(OrderDir::Desc),
+            ..QueryOptions::new()
+        };
+
+        assert_eq!(opts.check_order_by(&["*"]), Ok(()));
+        assert_eq!(
+            opts.check_order_by(&["author"]),
+            Err(OrderByError::NotSelected("meta.rank".into()))
+        );
+
+        let query = opts.build("post", &["*"]);
+
+        assert_eq!(
+            query.0.as_ref(),
+            "SELECT * FROM post ORDER BY meta.rank DESC"
+        );
+
+        let metas: Vec<HashMap<String, i64>> = db
+            .query(query.0.as_ref())
+            .await
+            .unwrap()
+            .take((0, "meta"))
+            .unwrap();
+        let ranks = metas.iter().map(|meta| meta["rank"]).collect::<Vec<_>>();
+
+        assert_eq!(ranks, vec![3, 2, 1]);
+
+        let opts = QueryOptions {
+            expansions: &[("posts", "count(<-author<-post)")],
+            order_by: Some("post_count"),
+            ..QueryOptions::new()
+        };
+
+        assert_eq!(
+            opts.check_order_by(&["name"]),
+            Err(OrderByError::NotSelected("post_count".into()))
+        );
+
+        let opts = QueryOptions {
+            expansions: &[(
+                "post_count",
+                "array::len(SELECT * FROM post WHERE author = $parent.id)",
+            )],
+            order_by: Some("post_count"),
+            order_dir: Some(OrderDir::Desc),
+            ..QueryOptions::new()
+        };
+
+        assert_eq!(opts.check_order_by(&["name"]), Ok(()));
+        assert_eq!(
+            QueryOptions {
+                order_by: Some("name; REMOVE TABLE user"),
+                ..QueryOptions::new()
+            }
+            .check_order_by(&["*"]),
+            Err(OrderByError::InvalidPath("name; REMOVE TABLE user".into()))
+        );
+
+        let query = opts.build("user", &["name"]);
+
+        let names: Vec<String> = db
+            .query(query.0.as_ref())
+            .await
+            .unwrap()
+            .take((0, "name"))
+            .unwrap();
+
+        assert_eq!(names, vec!["a", "b"]);
+    }
+
+    #[tokio::test]
+    async fn it_validates_limit_and_offset() {
+        assert_eq!(Limit::new(0), None);
+        assert_eq!(Limit::new(10).map(Limit::get), Some(10));
+        assert_eq!(Offset::new(Offset::MAX + 1), None);
+
+        assert_eq!(serde_json::from_str::<Limit>("10").ok(), Limit::new(10));
+        assert!(serde_json::from_str::<Limit>("0").is_err());
+        assert!(serde_json::from_str::<Limit>("-1").is_err());
+        assert!(serde_json::from_str::<Limit>("1.5").is_err());
+        assert!(serde_json::from_str::<Offset>("-1").is_err());
+        assert!(serde_json::from_str::<Offset>("18446744073709551616").is_err());
+        assert!(serde_json::from_str::<Offset>("9223372036854775808").is_err());
+        assert_eq!(serde_json::from_str::<Offset>("0").ok(), Offset::new(0));
+    }
+
+    #[tokio::test]
+    async fn it_previews_json_options() {
+        let request: PreviewRequest = serde_json::from_str(
+            r#"{
+                "table": "user",
+                "columns": ["name"],
+                "filters": [
+                    { "key": "name", "op": "=", "value": "tester" },
+                    { "key": "tags", "op": "CONTAINSANY", "value": ["a", 1] },
+                    { "key": "meta", "op": "=", "value": { "a": 1 } }
+                ],
+                "limit": 10,
+                "order_by": "age",
+                "order_dir": "desc"
+            }"#,
+        )
+        .unwrap();
+
+        let ((query, variables), warnings) = request.build();
+
+        assert_eq!(
+            query.as_ref(),
+            "SELECT name FROM user WHERE name = $name AND tags CONTAINSANY $tags ORDER BY age DESC LIMIT 10"
+        );
+        assert_eq!(variables.len(), 2);
+        assert_eq!(
+            warnings,
+            vec![
+                "the filter on \"meta\" has no usable value",
+                "\"age\" is neither selected nor an expansion"
+            ]
+        );
+
+        assert!(
+            serde_json::from_str::<PreviewRequest>(r#"{ "table": "user", "limit": -1 }"#).is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn it_explains_query_plans() {
+        let db = set_up_db().await;
+
+        db.query("DEFINE INDEX user_name ON user FIELDS name")
+            .query("CREATE user SET name = 'tester'")
+            .await
+            .unwrap();
+
+        let query = QueryOptions {
+            filters: Filters::from(vec![("name", "tester")]),
+            ..QueryOptions::new()
+        }
+        .build_explain("user", &["*"], true);
+
+        assert_eq!(
+            query.0.as_ref(),
+            "SELECT * FROM user WHERE name = $name EXPLAIN FULL"
+        );
+
+        let mut response = db.query(query.0.as_ref()).bind(query.1).await.unwrap();
+        let explain = take_explain(&mut response, 0).unwrap();
+
+        assert!(explain.full_table_scans().is_empty());
+        assert_eq!(explain.indexes(), vec!["user_name"]);
+        assert_eq!(explain.fetched(), Some(1));
+
+        let query = QueryOptions::new().build_explain("user", &["*"], false);
+
+        let mut response = db.query(query.0.as_ref()).await.unwrap();
+        let explain = take_explain(&mut response, 0).unwrap();
+
+        assert_eq!(explain.full_table_scans(), vec!["user"]);
+        assert_eq!(explain.fetched(), None);
+    }
+
+    #[tokio::test]
+    async fn it_builds_record_range_queries() {
+        let db = set_up_db().await;
+
+        db.query("DEFINE TABLE order SCHEMALESS")
+            .query("CREATE order:⟨2024-01-03⟩, order:⟨2024-02-10⟩, order:⟨2024-03-01⟩")
+            .await
+            .unwrap();
+
+        let range = RecordRange::new("order", Id::from("2024-01")..Id::from("2024-03")).unwrap();
+
+        assert_eq!(range.to_string(), "order:⟨2024-01⟩..⟨2024-03⟩");
+
+        let query = QueryOptions {
+            order_by: Some("id"),
+            ..QueryOptions::new()
+        }
+        .build_range(&range, &["*"]);
+
+        assert_eq!(
+            query.0.as_ref(),
+            "SELECT * FROM order:⟨2024-01⟩..⟨2024-03⟩ ORDER BY id"
+        );
+
+        let ids: Vec<String> = db
+            .query(format!("SELECT VALUE meta::id(id) FROM ({})", query.0))
+            .bind(query.1)
+            .await
+            .unwrap()
+            .take(0)
+            .unwrap();
+
+        assert_eq!(ids, vec!["2024-01-03", "2024-02-10"]);
+
+        let range = RecordRange::new(
+            "order",
+            (
+                Bound::Excluded(Id::from("2024-01-03")),
+                Bound::Included(Id::from("2024-03-01")),
+            ),
+        )
+        .unwrap();
+
+        assert_eq!(range.to_string(), "order:⟨2024-01-03⟩>..=⟨2024-03-01⟩");
+
+        let ids: Vec<String> = db
+            .query(format!("SELECT VALUE meta::id(id) FROM {}", range))
+            .await
+            .unwrap()
+            .take(0)
+            .unwrap();
+
+        assert_eq!(ids.len(), 2);
+
+        let range = RecordRange::new("order", Id::from("a⟩; DELETE order; --")..).unwrap();
+
+        assert_eq!(range.to_string(), "order:⟨a\\⟩; DELETE order; --⟩..");
+
+        assert_eq!(
+            RecordRange::new("order; DELETE order", ..),
+            Err(InvalidTable("order; DELETE order".into()))
+        );
+    }
+
+    #[tokio::test]
+    async fn it_builds_time_bucket_queries() {
+        let db = set_up_db().await;
+
+        db.query("DEFINE TABLE order SCHEMALESS")
+            .query("CREATE order SET created_at = d'2024-01-01T09:00:00Z', total = 10, status = 'paid'")
+            .query("CREATE order SET created_at = d'2024-01-01T17:00:00Z', total = 5, status = 'paid'")
+            .query("CREATE order SET created_at = d'2024-01-02T12:00:00Z', total = 7, status = 'paid'")
+            .query("CREATE order SET created_at = d'2024-01-02T13:00:00Z', total = 99, status = 'void'")
+            .await
+            .unwrap();
+
+        let buckets = TimeBuckets {
+            field: "created_at",
+            size: Duration::from_secs(24 * 60 * 60),
+            aggregates: &[
+                (Aggregate::Count, "orders"),
+                (Aggregate::Sum("total"), "revenue"),
+            ],
+        };
+
+        let query = QueryOptions {
+            filters: Filters::from(vec![("status", "paid")]),
+            ..QueryOptions::new()
+        }
+        .build_time_buckets("order", &buckets)
+        .unwrap();
+
+        assert_eq!(
+            query.0.as_ref(),
+            "SELECT time::floor(created_at, $bucket) AS bucket,count() AS orders,math::sum(total) AS revenue FROM order WHERE status = $status GROUP BY bucket ORDER BY bucket"
+        );
+
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Bucket {
+            orders: u64,
+            revenue: u64,
+        }
+
+        let rows: Vec<Bucket> = db
+            .query(query.0.as_ref())
+            .bind(query.1)
+            .await
+            .unwrap()
+            .take(0)
+            .unwrap();
+
+        assert_eq!(
+            rows,
+            vec![
+                Bucket {
+                    orders: 2,
+                    revenue: 15
+                },
+                Bucket {
+                    orders: 1,
+                    revenue: 7
+                }
+            ]
+        );
+
+        assert_eq!(
+            QueryOptions::new().build_time_buckets(
+                "order",
+                &TimeBuckets {
+                    size: Duration::ZERO,
+                    ..buckets
+                }
+            ),
+            Err(TimeBucketError::ZeroSize)
+        );
+        assert_eq!(
+            QueryOptions::new().build_time_buckets(
+                "order",
+                &TimeBuckets {
+                    field: "created_at, $x",
+                    ..buckets
+                }
+            ),
+            Err(TimeBucketError::InvalidField("created_at, $x".into()))
+        );
+
+        let query = QueryOptions {
+            extra_binds: HashMap::from([("bucket".into(), "mine".into())]),
+            ..QueryOptions::new()
+        }
+        .build_time_buckets("order", &buckets)
+        .unwrap();
+
+        assert!(query
+            .0
+            .starts_with("SELECT time::floor(created_at, $bucket__1) AS bucket,"));
+        assert_eq!(query.1["bucket"], "mine".into());
+    }
+
+    #[tokio::test]
+    async fn it_builds_facet_counts() {
+        let db = set_up_db().await;
+
+        db.query("DEFINE TABLE product SCHEMALESS")
+            .query("CREATE product SET color = 'red', size = 'm'")
+            .query("CREATE product SET color = 'red', size = 'l'")
+            .query("CREATE product SET color = 'red', size = 'l'")
+            .query("CREATE product SET color = 'blue', size = 'm'")
+            .await
+            .unwrap();
+
+        let fields = ["color", "size"];
+
+        let query = QueryOptions {
+            filters: Filters::from(vec![("color", "red")]),
+            ..QueryOptions::new()
+        }
+        .build_facets("product", &fields)
+        .unwrap();
+
+        assert_eq!(
+            query.0.as_ref(),
+            "SELECT color AS value,count() AS count FROM product GROUP BY value ORDER BY count DESC; SELECT size AS value,count() AS count FROM product WHERE color = $f1_color GROUP BY value ORDER BY count DESC"
+        );
+
+        let mut response = db.query(query.0.as_ref()).bind(query.1).await.unwrap();
+        let facets = take_facets(&mut response, &fields).unwrap();
+
+        assert_eq!(
+            facets["color"],
+            vec![
+                FacetCount {
+                    value: "red".into(),
+                    count: 3
+                },
+                FacetCount {
+                    value: "blue".into(),
+                    count: 1
+                }
+            ]
+        );
+        assert_eq!(
+            facets["size"],
+            vec![
+                FacetCount {
+                    value: "l".into(),
+                    count: 2
+                },
+                FacetCount {
+                    value: "m".into(),
+                    count: 1
+                }
+            ]
+        );
+
+        // A key that only resolves to the facet's field is still the facet's own filter
+        let query = QueryOptions {
+            filters: Filters::from(vec![(" color ", "red"), ("size", "l")]),
+            ..QueryOptions::new()
+        }
+        .build_facets("product", &fields)
+        .unwrap();
+
+        assert_eq!(
+            query.0.as_ref(),
+            "SELECT color AS value,count() AS count FROM product WHERE size = $f0_size GROUP BY value ORDER BY count DESC; SELECT size AS value,count() AS count FROM product WHERE color = $f1_color GROUP BY value ORDER BY count DESC"
+        );
+
+        let mut response = db.query(query.0.as_ref()).bind(query.1).await.unwrap();
+        let facets = take_facets(&mut response, &fields).unwrap();
+
+        assert_eq!(
+            facets["color"],
+            vec![FacetCount {
+                value: "red".into(),
+                count: 2
+            }]
+        );
+        assert_eq!(
+            facets["size"],
+            vec![
+                FacetCount {
+                    value: "l".into(),
+                    count: 2
+                },
+                FacetCount {
+                    value: "m".into(),
+                    count: 1
+                }
+            ]
+        );
+
+        assert_eq!(
+            QueryOptions::new().build_facets("product", &["color; DELETE product"]),
+            Err(InvalidFacet("color; DELETE product".into()))
+        );
+    }
+
+    #[tokio::test]
+    async fn it_reserves_stock() {
+        let db = set_up_db().await;
+
+        db.query("DEFINE TABLE product SCHEMALESS")
+            .query("CREATE product:a SET stock = 5")
+            .query("CREATE product:b SET stock = 1")
+            .await
+            .unwrap();
+
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Product {
+            stock: u64,
+        }
+
+        let a = Thing::from(("product", "a"));
+        let b = Thing::from(("product", "b"));
+
+        let reservation = StockReservation::new().add(a.clone(), 2);
+
+        assert_eq!(
+            reservation.build().unwrap().0.as_ref(),
+            "UPDATE $record0 SET stock -= $quantity0 WHERE stock >= $quantity0 RETURN AFTER"
+        );
+
+        let reserved: Vec<Product> = reservation.reserve(&db).await.unwrap();
+
+        assert_eq!(reserved, vec![Product { stock: 3 }]);
+
+        let short = StockReservation::new().add(b.clone(), 2);
+
+        assert!(matches!(
+            short.reserve::<_, Product>(&db).await,
+            Err(ReservationError::OutOfStock { record, quantity: 2 }) if record == b
+        ));
+
+        let reserved: Vec<Product> = StockReservation::new()
+            .add(a.clone(), 1)
+            .add(b.clone(), 1)
+            .reserve(&db)
+            .await
+            .unwrap();
+
+        assert_eq!(reserved, vec![Product { stock: 2 }, Product { stock: 0 }]);
+
+        let short = StockReservation::new().add(a.clone(), 1).add(b.clone(), 1);
+
+        assert!(matches!(
+            short.reserve::<_, Product>(&db).await,
+            Err(ReservationError::OutOfStock { record, quantity: 1 }) if record == b
+        ));
+
+        // The whole reservation was rolled back
+        let stock: Option<u64> = db
+            .query("SELECT VALUE stock FROM ONLY product:a")
+            .await
+            .unwrap()
+            .take(0)
+            .unwrap();
+
+        assert_eq!(stock, Some(2));
+
+        assert!(matches!(
+            StockReservation::new().build(),
+            Err(ReservationError::Empty)
+        ));
+        assert!(matches!(
+            StockReservation::new()
+                .field("stock = 0, x")
+                .add(a, 1)
+                .build(),
+            Err(ReservationError::InvalidField(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn it_builds_suggestions() {
+        let db = set_up_db().await;
+
+        let suggest = Suggest::new("product", "name");
+
+        db.query("DEFINE TABLE product SCHEMALESS")
+            .query(suggest.build_definitions().unwrap().as_ref())
+            .query("CREATE product SET name = 'Laptop stand', active = true")
+            .query("CREATE product SET name = 'Gaming laptop', active = true")
+            .query("CREATE product SET name = 'Lamp', active = true")
+            .query("CREATE product SET name = 'Old laptop', active = false")
+            .await
+            .unwrap()
+            .check()
+            .unwrap();
+
+        let query = QueryOptions {
+            filters: Filters::from(vec![("active", true)]),
+            ..QueryOptions::new()
+        }
+        .build_suggest(&suggest, "lapt", &["name"])
+        .unwrap();
+
+        assert_eq!(
+            query.0.as_ref(),
+            "SELECT name,search::score(1) AS score FROM product WHERE (name @1@ $input) AND active = $active ORDER BY score DESC LIMIT 10"
+        );
+
+        let mut names: Vec<String> = db
+            .query(format!("SELECT VALUE name FROM ({})", query.0))
+            .bind(query.1)
+            .await
+            .unwrap()
+            .take(0)
+            .unwrap();
+
+        names.sort();
+
+        assert_eq!(names, vec!["Gaming laptop", "Laptop stand"]);
+
+        assert_eq!(
+            Suggest::new("product; REMOVE TABLE product", "name").build_definitions(),
+            Err(SuggestError::InvalidTable(
+                "product; REMOVE TABLE product".into()
+            ))
+        );
+        assert_eq!(
+            QueryOptions::new().build_suggest(
+                &Suggest {
+                    min_gram: 3,
+                    max_gram: 2,
+                    ..suggest
+                },
+                "la",
+                &["name"]
+            ),
+            Err(SuggestError::InvalidGrams { min: 3, max: 2 })
+        );
+    }
+
+    #[tokio::test]
+    async fn it_orders_by_computed_expressions() {
+        let db = set_up_db().await;
+
+        db.query("DEFINE TABLE product SCHEMALESS")
+            .query("CREATE product:a SET price = 10, currency = 'EUR'")
+            .query("CREATE product:b SET price = 9, currency = 'USD'")
+            .query("CREATE product:c SET price = 1000, currency = 'JPY'")
+            .await
+            .unwrap();
+
+        let rates = [
+            ("EUR", Decimal::new(11, 1).into()),
+            ("USD", 1.into()),
+            ("JPY", Decimal::new(7, 3).into()),
+        ];
+
+        let query = QueryOptions {
+            order_dir: Some(OrderDir::Desc),
+            ..QueryOptions::new()
+        }
+        .build_sorted(
+            "product",
+            &["id"],
+            &SortExpr::Converted {
+                field: "price",
+                key: "currency",
+                rates: &rates,
+            },
+            "normalized_price",
+        )
+        .unwrap();
+
+        assert_eq!(
+            query.0.as_ref(),
+            "SELECT id,price * $sort_rates[currency] AS normalized_price FROM product ORDER BY normalized_price DESC"
+        );
+
+        let ids: Vec<String> = db
+            .query(format!("SELECT VALUE meta::id(id) FROM ({})", query.0))
+            .bind(query.1)
+            .await
+            .unwrap()
+            .take(0)
+            .unwrap();
+
+        assert_eq!(ids, vec!["a", "b", "c"]);
+
+        let query = QueryOptions {
+            filters: Filters::from(vec![("currency", "EUR")]),
+            ..QueryOptions::new()
+        }
+        .build_sorted(
+            "product",
+            &["*"],
+            &SortExpr::Scaled {
+                field: "price",
+                rate: 2.into(),
+            },
+            "doubled",
+        )
+        .unwrap();
+
+        assert_eq!(
+            query.0.as_ref(),
+            "SELECT *,price * $sort_rate AS doubled FROM product WHERE currency = $currency ORDER BY doubled"
+        );
+        assert_eq!(query.1["sort_rate"], 2.into());
+
+        let query = QueryOptions {
+            extra_binds: HashMap::from([("sort_rate".into(), "mine".into())]),
+            ..QueryOptions::new()
+        }
+        .build_sorted(
+            "product",
+            &["*"],
+            &SortExpr::Scaled {
+                field: "price",
+                rate: 2.into(),
+            },
+            "doubled",
+        )
+        .unwrap();
+
+        assert_eq!(
+            query.0.as_ref(),
+            "SELECT *,price * $sort_rate__1 AS doubled FROM product ORDER BY doubled"
+        );
+        assert_eq!(query.1["sort_rate"], "mine".into());
+        assert_eq!(query.1["sort_rate__1"], 2.into());
+
+        assert_eq!(
+            QueryOptions::new().build_sorted(
+                "product",
+                &["*"],
+                &SortExpr::Converted {
+                    field: "price",
+                    key: "currency]; DELETE product; --",
+                    rates: &rates,
+                },
+                "normalized_price",
+            ),
+            Err(OrderByError::InvalidPath(
+                "currency]; DELETE product; --".into()
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn it_parses_operators_the_way_serde_does() {
+        for operator in [
+            Operator::Eq,
+            Operator::Ne,
+            Operator::Gt,
+            Operator::Ge,
+            Operator::Lt,
+            Operator::Le,
+            Operator::ContainsAny,
+            Operator::Inside,
+        ] {
+            let written = operator.to_string();
+            let deserialized: Operator = serde_json::from_value(written.clone().into()).unwrap();
+
+            assert_eq!(written.parse::<Operator>().unwrap().to_string(), written);
+            assert_eq!(deserialized.to_string(), written);
+        }
+
+        assert_eq!(
+            "LIKE".parse::<Operator>().err(),
+            Some(UnknownOperator("LIKE".into()))
+        );
+        assert!(serde_json::from_value::<Operator>("LIKE".into()).is_err());
+    }
+
     #[tokio::test]
     async fn it_sanitizes_filter_keys() {
         let opts = QueryOptions {
@@ -444,10 +2938,11 @@ mod tests {
                 (Operator::Eq, "whatever".into()),
             )])),
             expansions: &[],
-            limit: Some(10),
-            offset: Some(0),
+            limit: Limit::new(10),
+            offset: Offset::new(0),
             order_by: Some("id"),
             order_dir: Some(OrderDir::Asc),
+            extra_binds: HashMap::new(),
         };
 
         let query = opts.build("user", &["id", "name"]);
@@ -469,10 +2964,11 @@ mod tests {
                 (Operator::Eq, "whatever".into()),
             )])),
             expansions: &[],
-            limit: Some(10),
-            offset: Some(0),
+            limit: Limit::new(10),
+            offset: Offset::new(0),
             order_by: Some("id"),
             order_dir: Some(OrderDir::Asc),
+            extra_binds: HashMap::new(),
         };
 
         let query = opts.build("user", &["id", "tag"]);
@@ -496,10 +2992,11 @@ mod tests {
                 "purchased_items = \"hello\"; DELETE user:hello; SELECT * FROM user WHERE name = \"hello\"",
                 "->purchased.out",
             )],
-            limit: Some(10),
-            offset: Some(0),
+            limit: Limit::new(10),
+            offset: Offset::new(0),
             order_by: Some("id"),
             order_dir: Some(OrderDir::Asc),
+            extra_binds: HashMap::new(),
         };
 
         let query = opts.build("user", &["id", "name"]);
@@ -526,6 +3023,7 @@ mod tests {
             offset: None,
             order_by: None,
             order_dir: None,
+            extra_binds: HashMap::new(),
         };
 
         let query = opts.build("user", &["id", "name"]);
@@ -580,6 +3078,7 @@ mod tests {
             offset: None,
             order_by: None,
             order_dir: None,
+            extra_binds: HashMap::new(),
         };
 
         let query = opts.build("user", &["id", "name"]);
@@ -612,6 +3111,7 @@ mod tests {
             offset: None,
             order_by: None,
             order_dir: None,
+            extra_binds: HashMap::new(),
         };
 
         let query = opts.build("user", &["id", "name"]);
@@ -647,6 +3147,7 @@ mod tests {
             offset: None,
             order_by: None,
             order_dir: None,
+            extra_binds: HashMap::new(),
         };
 
         let query = opts.build("test", &["*"]);
@@ -743,6 +3244,7 @@ mod tests {
             offset: None,
             order_by: None,
             order_dir: None,
+            extra_binds: HashMap::new(),
         };
 
         let query = opts.build("decimal_test", &["price"]);
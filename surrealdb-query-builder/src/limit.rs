@@ -0,0 +1,91 @@
+use std::{fmt::Display, num::NonZeroUsize};
+
+use serde::{de::Unexpected, Deserialize, Serialize};
+
+/// A `LIMIT`, which is at least 1 since a limit of 0 would never return anything
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(transparent)]
+pub struct Limit(NonZeroUsize);
+
+/// A `START`, at most [`Offset::MAX`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(transparent)]
+pub struct Offset(usize);
+
+impl Limit {
+    /// Returns `None` for 0
+    pub const fn new(limit: usize) -> Option<Self> {
+        match NonZeroUsize::new(limit) {
+            Some(limit) => Some(Limit(limit)),
+            None => None,
+        }
+    }
+
+    pub const fn get(self) -> usize {
+        self.0.get()
+    }
+}
+
+impl Offset {
+    /// The database reads `START` as a signed 64-bit integer
+    pub const MAX: usize = i64::MAX as usize;
+
+    /// Returns `None` above [`Offset::MAX`]
+    pub const fn new(offset: usize) -> Option<Self> {
+        if offset <= Self::MAX {
+            Some(Offset(offset))
+        } else {
+            None
+        }
+    }
+
+    pub const fn get(self) -> usize {
+        self.0
+    }
+}
+
+impl Display for Limit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl Display for Offset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Negative, fractional and overflowing input is an error rather than being wrapped or cast
+impl<'de> Deserialize<'de> for Limit {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let limit = u64::deserialize(deserializer)?;
+
+        usize::try_from(limit)
+            .ok()
+            .and_then(Limit::new)
+            .ok_or_else(|| {
+                serde::de::Error::invalid_value(
+                    Unexpected::Unsigned(limit),
+                    &"a limit of at least 1",
+                )
+            })
+    }
+}
+
+/// Negative, fractional and overflowing input is an error rather than being wrapped or cast
+impl<'de> Deserialize<'de> for Offset {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let offset = u64::deserialize(deserializer)?;
+
+        usize::try_from(offset)
+            .ok()
+            .and_then(Offset::new)
+            .ok_or_else(|| {
+                serde::de::Error::invalid_value(
+                    Unexpected::Unsigned(offset),
+                    &"an offset of at most i64::MAX",
+                )
+            })
+    }
+}
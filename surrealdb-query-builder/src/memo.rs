@@ -0,0 +1,96 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use futures::lock::Mutex as AsyncMutex;
+use serde::{de::DeserializeOwned, Serialize};
+use surrealdb::{
+    sql::{from_value, Value},
+    Connection, Surreal,
+};
+
+use crate::{
+    cache::{CacheError, CacheKey},
+    classify::{classify, QueryKind},
+};
+
+type Slot = Arc<AsyncMutex<Option<Value>>>;
+
+/// Runs every distinct read once for as long as it lives, meant to be created per request. A
+/// read that's already running is waited on instead of sent again
+pub struct MemoScope<'a, C: Connection> {
+    db: &'a Surreal<C>,
+    slots: Mutex<HashMap<CacheKey, Slot>>,
+}
+
+impl<'a, C: Connection> MemoScope<'a, C> {
+    pub fn new(db: &'a Surreal<C>) -> Self {
+        Self {
+            db,
+            slots: Mutex::default(),
+        }
+    }
+
+    /// Runs `query` and returns the result of its first statement. Writes always run, and
+    /// forget everything read so far since it may have changed
+    pub async fn execute<T: DeserializeOwned, V: Serialize>(
+        &self,
+        query: &(Box<str>, HashMap<Box<str>, V>),
+    ) -> Result<T, CacheError> {
+        let future = self.run(query);
+
+        #[cfg(feature = "otel")]
+        let future = tracing::Instrument::instrument(future, crate::otel::span(None, &query.0));
+
+        future.await
+    }
+
+    async fn run<T: DeserializeOwned, V: Serialize>(
+        &self,
+        query: &(Box<str>, HashMap<Box<str>, V>),
+    ) -> Result<T, CacheError> {
+        let (query, variables) = query;
+
+        if classify(query) == QueryKind::Write {
+            let value: Value = self
+                .db
+                .query(query.as_ref())
+                .bind(variables)
+                .await?
+                .take(0)?;
+
+            self.slots.lock().unwrap().clear();
+
+            return Ok(from_value(value)?);
+        }
+
+        let slot = self
+            .slots
+            .lock()
+            .unwrap()
+            .entry(CacheKey::query(query, variables))
+            .or_default()
+            .clone();
+
+        let mut slot = slot.lock().await;
+
+        let value = match &*slot {
+            Some(value) => value.clone(),
+            None => {
+                let value: Value = self
+                    .db
+                    .query(query.as_ref())
+                    .bind(variables)
+                    .await?
+                    .take(0)?;
+
+                *slot = Some(value.clone());
+
+                value
+            }
+        };
+
+        Ok(from_value(value)?)
+    }
+}
@@ -1,4 +1,4 @@
-use std::fmt::Display;
+use std::{fmt::Display, str::FromStr};
 
 use serde::Deserialize;
 
@@ -29,28 +29,50 @@ impl Display for Operator {
     }
 }
 
-struct OperatorVisitor;
-
-impl<'de> serde::de::Visitor<'de> for OperatorVisitor {
-    type Value = Operator;
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownOperator(pub Box<str>);
 
-    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-        formatter.write_str("Operator")
+impl Display for UnknownOperator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} is not an operator", self.0)
     }
+}
 
-    fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
-        match v {
+impl std::error::Error for UnknownOperator {}
+
+/// Parses the operator as it's written in a query, the inverse of its `Display`
+impl FromStr for Operator {
+    type Err = UnknownOperator;
+
+    fn from_str(operator: &str) -> Result<Self, Self::Err> {
+        match operator {
             "=" => Ok(Operator::Eq),
             "!=" => Ok(Operator::Ne),
             ">" => Ok(Operator::Gt),
             ">=" => Ok(Operator::Ge),
             "<" => Ok(Operator::Lt),
             "<=" => Ok(Operator::Le),
-            _ => Err(E::invalid_value(serde::de::Unexpected::Str(v), &self)),
+            "CONTAINSANY" => Ok(Operator::ContainsAny),
+            "INSIDE" => Ok(Operator::Inside),
+            _ => Err(UnknownOperator(operator.into())),
         }
     }
 }
 
+struct OperatorVisitor;
+
+impl<'de> serde::de::Visitor<'de> for OperatorVisitor {
+    type Value = Operator;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("Operator")
+    }
+
+    fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Operator::from_str(v).map_err(|_| E::invalid_value(serde::de::Unexpected::Str(v), &self))
+    }
+}
+
 impl<'de> Deserialize<'de> for Operator {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
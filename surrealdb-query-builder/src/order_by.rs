@@ -0,0 +1,169 @@
+use std::fmt::Display;
+
+use regex::Regex;
+
+use surrealdb::sql::{Object, Value};
+
+use crate::{
+    bind_naming::BindNaming,
+    filters::FilterValueKind,
+    query_options::{sanitize, QueryOptions, SelectQuery},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OrderByError {
+    /// Not a field or a dotted path into one, like `tag.name`
+    InvalidPath(Box<str>),
+    /// Neither among the columns nor an expansion alias, which the database refuses to order by
+    NotSelected(Box<str>),
+}
+
+impl Display for OrderByError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OrderByError::InvalidPath(path) => write!(f, "{:?} is not a field path", path),
+            OrderByError::NotSelected(path) => {
+                write!(f, "{:?} is neither selected nor an expansion", path)
+            }
+        }
+    }
+}
+
+impl std::error::Error for OrderByError {}
+
+/// A computed sort key, limited to expressions that can't be abused, with its factors bound
+#[derive(Debug, Clone, PartialEq)]
+pub enum SortExpr<'a> {
+    /// `field * $sort_rate`
+    Scaled {
+        field: &'a str,
+        rate: FilterValueKind,
+    },
+    /// `field * $sort_rates[key]`, like prices converted from each record's `currency` with a
+    /// rate per currency code. Records without a rate sort as if they had no value
+    Converted {
+        field: &'a str,
+        key: &'a str,
+        rates: &'a [(&'a str, FilterValueKind)],
+    },
+}
+
+/// The leading field path of `value`, like `tag.name` for `tag.name DESC`
+pub(crate) fn order_path(value: &str) -> Option<&str> {
+    let regex = Regex::new(r"^\w+(\.\w+)*").unwrap();
+
+    Some(regex.find(value)?.as_str())
+}
+
+impl<'a> QueryOptions<'a> {
+    /// Checks that `order_by` is a field path the query can be ordered by: one among the columns
+    /// (or under `*`), or an expansion alias
+    pub fn check_order_by(&self, unsafe_columns: &[&str]) -> Result<(), OrderByError> {
+        let Some(order_by) = self.order_by else {
+            return Ok(());
+        };
+
+        let path_regex = Regex::new(r"^\w+(\.\w+)*$").unwrap();
+        let alias_regex = Regex::new(r"(?i)\sAS\s+(\w+)$").unwrap();
+
+        if !path_regex.is_match(order_by) {
+            return Err(OrderByError::InvalidPath(order_by.into()));
+        }
+
+        let head = order_by.split('.').next().unwrap_or_default();
+
+        let selected = unsafe_columns.iter().any(|column| {
+            let column = column.trim();
+
+            match alias_regex.captures(column) {
+                Some(captures) => &captures[1] == head,
+                None => {
+                    column == "*" || column == order_by || column.split('.').next() == Some(head)
+                }
+            }
+        });
+
+        let expanded = self
+            .expansions
+            .iter()
+            .any(|(key, _)| sanitize(key) == Some(head));
+
+        if selected || expanded {
+            Ok(())
+        } else {
+            Err(OrderByError::NotSelected(order_by.into()))
+        }
+    }
+}
+
+impl<'a> QueryOptions<'a> {
+    /// Builds the query of `build` ordered by `expr`, which is projected as `alias` since only
+    /// selected fields can be ordered by. `order_dir` applies as usual. The rates are bound as
+    /// `$sort_rate` or `$sort_rates`, suffixed when `extra_binds` already has one
+    pub fn build_sorted(
+        mut self,
+        table_name: &str,
+        unsafe_columns: &[&str],
+        expr: &SortExpr,
+        alias: &'a str,
+    ) -> Result<SelectQuery, OrderByError> {
+        let path_regex = Regex::new(r"^\w+(\.\w+)*$").unwrap();
+        let alias_regex = Regex::new(r"^\w+$").unwrap();
+
+        let (field, key) = match *expr {
+            SortExpr::Scaled { field, .. } => (field, None),
+            SortExpr::Converted { field, key, .. } => (field, Some(key)),
+        };
+
+        if let Some(invalid) = [field]
+            .into_iter()
+            .chain(key)
+            .find(|path| !path_regex.is_match(path))
+        {
+            return Err(OrderByError::InvalidPath(invalid.into()));
+        }
+
+        if !alias_regex.is_match(alias) {
+            return Err(OrderByError::InvalidPath(alias.into()));
+        }
+
+        // Named around the caller's own binds, which could already have a `sort_rate`
+        let bind = |ident: &str, extra_binds: &_| {
+            BindNaming::default()
+                .assign(ident, 0, extra_binds)
+                .expect("suffixing never collides")
+        };
+
+        let projection = match expr {
+            SortExpr::Scaled { field, rate } => {
+                let name = bind("sort_rate", &self.extra_binds);
+                let projection = format!("{} * ${} AS {}", field, name, alias);
+
+                self.extra_binds.insert(name, rate.clone().into());
+
+                projection
+            }
+            SortExpr::Converted { field, key, rates } => {
+                let rates = rates
+                    .iter()
+                    .map(|(code, rate)| (code.to_string(), Value::from(rate.clone())))
+                    .collect();
+
+                let name = bind("sort_rates", &self.extra_binds);
+                let projection = format!("{} * ${}[{}] AS {}", field, name, key, alias);
+
+                self.extra_binds
+                    .insert(name, Value::Object(Object(rates)).into());
+
+                projection
+            }
+        };
+
+        self.order_by = Some(alias);
+
+        let mut columns = unsafe_columns.to_vec();
+        columns.push(&projection);
+
+        Ok(self.build(table_name, &columns))
+    }
+}
@@ -0,0 +1,55 @@
+use regex::Regex;
+use tracing::{field, Span};
+
+/// Replaces the literals left in a built query with `?`, so statements can be recorded without
+/// leaking values. That includes the id of a record id, like `user:?` for `user:⟨jane@example.com⟩`,
+/// but not its table. Bound values are never in the query to begin with
+pub fn sanitize_statement(query: &str) -> String {
+    let record_ids = Regex::new(
+        r"(\w+|`(?:\\.|[^`\\])*`|⟨(?:\\.|[^⟩\\])*⟩):(?:⟨(?:\\.|[^⟩\\])*⟩|`(?:\\.|[^`\\])*`|\w+)",
+    )
+    .unwrap();
+    let strings = Regex::new(r#""(\\.|[^"\\])*"|'(\\.|[^'\\])*'"#).unwrap();
+    let numbers = Regex::new(r"\b\d+(\.\d+)?\b").unwrap();
+
+    // Record ids go first, a quote inside `⟨…⟩` would otherwise start a string
+    let query = record_ids.replace_all(query, "$1:?");
+    let query = strings.replace_all(&query, "?");
+
+    numbers.replace_all(&query, "?").into_owned()
+}
+
+/// The statement's keyword, like `SELECT`
+pub fn operation(query: &str) -> String {
+    query
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_uppercase()
+}
+
+/// A span with the OpenTelemetry database attributes, named `<operation> <table>` as the
+/// semantic conventions suggest
+pub(crate) fn span(table: Option<&str>, query: &str) -> Span {
+    let operation = operation(query);
+    let name = match table {
+        Some(table) => format!("{} {}", operation, table),
+        None => operation.clone(),
+    };
+
+    let span = tracing::info_span!(
+        "surrealdb.query",
+        otel.name = name,
+        otel.kind = "client",
+        db.system = "surrealdb",
+        db.statement = sanitize_statement(query),
+        db.operation = operation,
+        db.sql.table = field::Empty,
+    );
+
+    if let Some(table) = table {
+        span.record("db.sql.table", table);
+    }
+
+    span
+}
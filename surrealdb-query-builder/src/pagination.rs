@@ -0,0 +1,115 @@
+use std::fmt::Display;
+
+use serde::Deserialize;
+
+use crate::{
+    limit::{Limit, Offset},
+    query_options::QueryOptions,
+};
+
+/// Which slice of the results to return, either as `LIMIT`/`START` or as pages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum Pagination {
+    Page {
+        /// Starts at 1
+        page: usize,
+        per_page: usize,
+    },
+    Offset {
+        limit: usize,
+        #[serde(default)]
+        offset: usize,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PaginationError {
+    /// Pages are counted from 1
+    PageZero,
+    /// A page or limit of 0 would never return anything
+    EmptyPage,
+    /// More results per page than the caller allows
+    TooLarge { per_page: usize, max: usize },
+    /// The page is so far out that its offset doesn't fit in a `usize`
+    Overflow,
+}
+
+impl Display for PaginationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PaginationError::PageZero => write!(f, "pages start at 1"),
+            PaginationError::EmptyPage => write!(f, "at least one result per page is required"),
+            PaginationError::TooLarge { per_page, max } => {
+                write!(
+                    f,
+                    "{} results per page exceeds the maximum of {}",
+                    per_page, max
+                )
+            }
+            PaginationError::Overflow => write!(f, "the page is out of range"),
+        }
+    }
+}
+
+impl std::error::Error for PaginationError {}
+
+impl Pagination {
+    /// The `LIMIT` and `START` of the page, after checking that at most `max_per_page` results
+    /// are requested
+    pub fn limit_offset(&self, max_per_page: usize) -> Result<(usize, usize), PaginationError> {
+        let (limit, offset) = match *self {
+            Pagination::Page { page: 0, .. } => return Err(PaginationError::PageZero),
+            Pagination::Page { page, per_page } => (
+                per_page,
+                (page - 1)
+                    .checked_mul(per_page)
+                    .ok_or(PaginationError::Overflow)?,
+            ),
+            Pagination::Offset { limit, offset } => (limit, offset),
+        };
+
+        if limit == 0 {
+            return Err(PaginationError::EmptyPage);
+        }
+
+        if limit > max_per_page {
+            return Err(PaginationError::TooLarge {
+                per_page: limit,
+                max: max_per_page,
+            });
+        }
+
+        Ok((limit, offset))
+    }
+
+    /// The number of pages needed for `total` results, e.g. from a `count()` of the same filters
+    pub fn total_pages(&self, total: usize) -> usize {
+        let per_page = match *self {
+            Pagination::Page { per_page, .. } => per_page,
+            Pagination::Offset { limit, .. } => limit,
+        };
+
+        match per_page {
+            0 => 0,
+            per_page => total.div_ceil(per_page),
+        }
+    }
+}
+
+impl<'a> QueryOptions<'a> {
+    /// Sets `limit` and `offset` from a validated [`Pagination`]
+    pub fn paginate(
+        mut self,
+        pagination: Pagination,
+        max_per_page: usize,
+    ) -> Result<Self, PaginationError> {
+        let (limit, offset) = pagination.limit_offset(max_per_page)?;
+
+        // Both are checked by `limit_offset`, the limit to be at least 1
+        self.limit = Limit::new(limit);
+        self.offset = Some(Offset::new(offset).ok_or(PaginationError::Overflow)?);
+
+        Ok(self)
+    }
+}
@@ -0,0 +1,141 @@
+use std::{collections::HashMap, fmt::Display};
+
+use regex::Regex;
+use serde::Serialize;
+use serde_json::Value;
+
+/// One operation of a JSON Patch. Paths are JSON pointers, like `/tags/0`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOp {
+    Add {
+        path: Box<str>,
+        value: Value,
+    },
+    Remove {
+        path: Box<str>,
+    },
+    Replace {
+        path: Box<str>,
+        value: Value,
+    },
+    /// Fails the whole patch when the value at `path` isn't `value`
+    Test {
+        path: Box<str>,
+        value: Value,
+    },
+}
+
+impl PatchOp {
+    fn path(&self) -> &str {
+        match self {
+            PatchOp::Add { path, .. }
+            | PatchOp::Remove { path }
+            | PatchOp::Replace { path, .. }
+            | PatchOp::Test { path, .. } => path,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatchError {
+    /// The target is neither a table nor a record id
+    InvalidTarget(Box<str>),
+    /// A path that isn't a JSON pointer
+    InvalidPath(Box<str>),
+}
+
+impl Display for PatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PatchError::InvalidTarget(target) => write!(f, "invalid patch target {:?}", target),
+            PatchError::InvalidPath(path) => write!(f, "invalid JSON pointer {:?}", path),
+        }
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+pub type PatchQuery = (Box<str>, HashMap<Box<str>, Vec<PatchOp>>);
+
+/// Builds `UPDATE ... PATCH $ops`, with the operations bound instead of written into the query
+#[derive(Debug, Clone, Default)]
+pub struct PatchOptions {
+    pub ops: Vec<PatchOp>,
+    /// Returns the changes made to each record instead of the records, see [`crate::diff`]
+    pub return_diff: bool,
+}
+
+impl PatchOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(mut self, path: &str, value: impl Into<Value>) -> Self {
+        self.ops.push(PatchOp::Add {
+            path: path.into(),
+            value: value.into(),
+        });
+
+        self
+    }
+
+    pub fn remove(mut self, path: &str) -> Self {
+        self.ops.push(PatchOp::Remove { path: path.into() });
+
+        self
+    }
+
+    pub fn replace(mut self, path: &str, value: impl Into<Value>) -> Self {
+        self.ops.push(PatchOp::Replace {
+            path: path.into(),
+            value: value.into(),
+        });
+
+        self
+    }
+
+    pub fn test(mut self, path: &str, value: impl Into<Value>) -> Self {
+        self.ops.push(PatchOp::Test {
+            path: path.into(),
+            value: value.into(),
+        });
+
+        self
+    }
+
+    pub fn return_diff(mut self) -> Self {
+        self.return_diff = true;
+
+        self
+    }
+
+    /// `target` is a table or a record id, like `product:1`
+    pub fn build(self, target: &str) -> Result<PatchQuery, PatchError> {
+        let target_regex = Regex::new(r"^\w+(:\w+)?$").unwrap();
+
+        if !target_regex.is_match(target) {
+            return Err(PatchError::InvalidTarget(target.into()));
+        }
+
+        if let Some(op) = self.ops.iter().find(|op| !is_json_pointer(op.path())) {
+            return Err(PatchError::InvalidPath(op.path().into()));
+        }
+
+        let mut query = format!("UPDATE {} PATCH $ops", target);
+
+        if self.return_diff {
+            query.push_str(" RETURN DIFF");
+        }
+
+        Ok((query.into_boxed_str(), [("ops".into(), self.ops)].into()))
+    }
+}
+
+/// An empty pointer refers to the whole document, any other one starts with `/`, and `~` is only
+/// used to escape `~0` and `~1`
+fn is_json_pointer(path: &str) -> bool {
+    let pointer_regex = Regex::new(r"^(/([^~/]|~[01])*)*$").unwrap();
+
+    pointer_regex.is_match(path)
+}
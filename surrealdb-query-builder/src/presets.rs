@@ -0,0 +1,67 @@
+use std::{collections::HashMap, fmt::Display};
+
+use crate::{filters::Filters, query_options::QueryOptions};
+
+/// Filter sets registered on the server under a name, like `active_products`. Clients refer to
+/// them by name, so they can't forge or loosen the conditions themselves
+#[derive(Default, Clone)]
+pub struct FilterPresets {
+    presets: HashMap<Box<str>, Filters>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownPreset(pub Box<str>);
+
+impl Display for UnknownPreset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "there is no filter preset named {:?}", self.0)
+    }
+}
+
+impl std::error::Error for UnknownPreset {}
+
+impl FilterPresets {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `filters` under `name`, replacing any preset of the same name
+    pub fn register(mut self, name: &str, filters: impl Into<Filters>) -> Self {
+        self.presets.insert(name.into(), filters.into());
+
+        self
+    }
+
+    /// The filters of all the presets, in order
+    pub fn expand<S: AsRef<str>>(&self, names: &[S]) -> Result<Filters, UnknownPreset> {
+        let mut filters = vec![];
+
+        for name in names {
+            let preset = self
+                .presets
+                .get(name.as_ref())
+                .ok_or_else(|| UnknownPreset(name.as_ref().into()))?;
+
+            filters.extend(preset.iter().cloned());
+        }
+
+        Ok(Filters(filters.into_boxed_slice()))
+    }
+}
+
+impl<'a> QueryOptions<'a> {
+    /// Adds the filters of the named presets to the ones already set
+    pub fn with_presets<S: AsRef<str>>(
+        mut self,
+        presets: &FilterPresets,
+        names: &[S],
+    ) -> Result<Self, UnknownPreset> {
+        let mut filters = std::mem::take(&mut self.filters).0.into_vec();
+
+        filters.extend(presets.expand(names)?.0.into_vec());
+
+        self.filters = Filters(filters.into_boxed_slice());
+
+        Ok(self)
+    }
+}
@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+    filters::{FilterValue, FilterValueKind, Filters},
+    limit::{Limit, Offset},
+    operator::Operator,
+    order_dir::OrderDir,
+    query_options::{QueryOptions, SelectQuery},
+};
+
+/// A filter as a client would send it, like `{ "key": "name", "op": "=", "value": "tester" }`
+#[derive(Deserialize)]
+pub struct PreviewFilter {
+    pub key: Box<str>,
+    pub op: Operator,
+    pub value: Value,
+}
+
+/// Query options as JSON, for previewing what a payload builds without writing Rust
+#[derive(Deserialize)]
+pub struct PreviewRequest {
+    pub table: Box<str>,
+    #[serde(default = "all_columns")]
+    pub columns: Vec<Box<str>>,
+    #[serde(default)]
+    pub filters: Vec<PreviewFilter>,
+    /// Pairs of alias and subquery
+    #[serde(default)]
+    pub expansions: Vec<(Box<str>, Box<str>)>,
+    pub limit: Option<Limit>,
+    pub offset: Option<Offset>,
+    pub order_by: Option<Box<str>>,
+    pub order_dir: Option<OrderDir>,
+}
+
+fn all_columns() -> Vec<Box<str>> {
+    vec!["*".into()]
+}
+
+/// Only plain JSON values can be filtered by, objects and `null` can't
+fn filter_value(value: Value) -> Option<FilterValue> {
+    let kind = |value: Value| -> Option<FilterValueKind> {
+        Some(match value {
+            Value::String(value) => value.into(),
+            Value::Bool(value) => value.into(),
+            Value::Number(number) => match (number.as_i64(), number.as_u64()) {
+                (Some(value), _) => value.into(),
+                (None, Some(value)) => value.into(),
+                _ => number.as_f64()?.into(),
+            },
+            _ => return None,
+        })
+    };
+
+    match value {
+        Value::Array(values) => Some(FilterValue::EscapedList(
+            values.into_iter().map(kind).collect::<Option<_>>()?,
+        )),
+        value => Some(FilterValue::Escaped(kind(value)?)),
+    }
+}
+
+impl PreviewRequest {
+    /// Builds the query, with a warning for everything that was left out or will fail
+    pub fn build(self) -> (SelectQuery, Vec<String>) {
+        let mut warnings = vec![];
+
+        let filters = self
+            .filters
+            .into_iter()
+            .filter_map(|filter| match filter_value(filter.value) {
+                Some(value) => Some((filter.key, (filter.op, value))),
+                None => {
+                    warnings.push(format!(
+                        "the filter on {:?} has no usable value",
+                        filter.key
+                    ));
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let expansions = self
+            .expansions
+            .iter()
+            .map(|(alias, subquery)| (alias.as_ref(), subquery.as_ref()))
+            .collect::<Vec<_>>();
+        let columns = self.columns.iter().map(AsRef::as_ref).collect::<Vec<_>>();
+
+        let options = QueryOptions {
+            filters: Filters(filters.into_boxed_slice()),
+            expansions: &expansions,
+            limit: self.limit,
+            offset: self.offset,
+            order_by: self.order_by.as_deref(),
+            order_dir: self.order_dir,
+            extra_binds: HashMap::new(),
+        };
+
+        if let Err(error) = options.check_order_by(&columns) {
+            warnings.push(error.to_string());
+        }
+
+        (options.build(&self.table, &columns), warnings)
+    }
+}
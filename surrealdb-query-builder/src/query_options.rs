@@ -1,21 +1,62 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt::Display};
 
 use regex::Regex;
 
 use crate::{
+    bind_naming::{BindCollision, BindNaming},
+    filter_key::{arithmetic_key, function_key},
     filters::{FilterValue, Filters},
+    limit::{Limit, Offset},
     operator::Operator,
+    order_by::order_path,
     order_dir::OrderDir,
     Expansions,
 };
 
+pub type SelectQuery = (Box<str>, HashMap<Box<str>, FilterValue>);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExpansionError {
+    InvalidForeignKey(Box<str>),
+    Collision(BindCollision),
+}
+
+impl Display for ExpansionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExpansionError::InvalidForeignKey(key) => write!(f, "{:?} is not a field path", key),
+            ExpansionError::Collision(collision) => collision.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ExpansionError {}
+
+impl From<BindCollision> for ExpansionError {
+    fn from(collision: BindCollision) -> Self {
+        ExpansionError::Collision(collision)
+    }
+}
+
+/// The `WHERE` clause and the variables it binds
+pub(crate) type WhereClause = (Box<str>, HashMap<Box<str>, FilterValue>);
+
 pub struct QueryOptions<'a> {
     pub filters: Filters,
     pub expansions: Expansions<'a>,
-    pub limit: Option<usize>,
-    pub offset: Option<usize>,
+    pub limit: Option<Limit>,
+    pub offset: Option<Offset>,
     pub order_by: Option<&'a str>,
     pub order_dir: Option<OrderDir>,
+    /// Bound as they are, for params referenced by expansions and raw filters. Generated binds
+    /// are named around them
+    pub extra_binds: HashMap<Box<str>, FilterValue>,
+}
+
+impl<'a> Default for QueryOptions<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<'a> QueryOptions<'a> {
@@ -27,55 +68,61 @@ impl<'a> QueryOptions<'a> {
             offset: None,
             order_by: None,
             order_dir: None,
+            extra_binds: HashMap::new(),
         }
     }
 
     fn flatten_grouped_filters(
         grouped_filters: HashMap<Box<str>, Vec<(Operator, FilterValue)>>,
-    ) -> HashMap<Box<str>, (Box<str>, Operator, FilterValue)> {
-        let mut result = HashMap::new();
-
-        for (key, values) in grouped_filters.into_iter() {
-            let mut i = 0;
-
-            for (operator, value) in values.into_iter() {
-                let enumerated_key = if i == 0 {
-                    key.clone()
-                } else {
-                    format!("{}__{}", &key, i).into_boxed_str()
-                };
-
-                result.insert(enumerated_key, (key.clone(), operator, value));
+    ) -> Vec<(Box<str>, usize, Operator, FilterValue)> {
+        let mut result = grouped_filters
+            .into_iter()
+            .flat_map(|(key, values)| {
+                values
+                    .into_iter()
+                    .enumerate()
+                    .map(move |(i, (operator, value))| (key.clone(), i, operator, value))
+            })
+            .collect::<Vec<_>>();
 
-                i += 1;
-            }
-        }
+        // Names are handed out in this order, so it has to be stable for them to be
+        result.sort_by(|a, b| (&a.0, a.1).cmp(&(&b.0, b.1)));
 
         result
     }
 
-    fn build_filters(filters: Filters) -> (Box<str>, HashMap<Box<str>, FilterValue>) {
+    /// Builds the `WHERE` clause, adding its binds to `variables` without overwriting any
+    pub(crate) fn build_filters(
+        filters: Filters,
+        naming: &BindNaming,
+        mut variables: HashMap<Box<str>, FilterValue>,
+    ) -> Result<WhereClause, BindCollision> {
         if filters.is_empty() {
-            return ("".into(), HashMap::new());
+            return Ok(("".into(), variables));
         }
 
-        let grouped_filters: HashMap<Box<str>, Vec<(Operator, FilterValue)>> = filters
+        // Raw filters have no key to group by, and their binds are merged in last
+        let (raw_filters, filters): (Vec<_>, Vec<_>) = filters
             .0
             .into_vec()
             .into_iter()
-            .filter_map(|(unsafe_key, (operator, value))| {
-                let key = sanitize(&unsafe_key)?;
+            .partition(|(_, (_, value))| matches!(value, FilterValue::Raw(_)));
 
-                Some((key.to_string().into_boxed_str(), (operator, value)))
+        let grouped_filters: HashMap<Box<str>, Vec<(Operator, FilterValue)>> = filters
+            .into_iter()
+            .filter_map(|(unsafe_key, (operator, value))| {
+                Some((resolve_key(&unsafe_key)?, (operator, value)))
             })
             .fold(HashMap::new(), |mut acc, (key, (operator, value))| {
                 match value {
-                    FilterValue::Escaped(_) | FilterValue::Unsafe(_) => match acc.get_mut(&key) {
-                        Some(values) => values.push((operator, value)),
-                        None => {
-                            acc.insert(key, vec![(operator, value)]);
+                    FilterValue::Escaped(_) | FilterValue::Unsafe(_) | FilterValue::Session(_) => {
+                        match acc.get_mut(&key) {
+                            Some(values) => values.push((operator, value)),
+                            None => {
+                                acc.insert(key, vec![(operator, value)]);
+                            }
                         }
-                    },
+                    }
                     FilterValue::EscapedList(_) => {
                         match operator {
                             // Ignore any operator that's not an array operator when we have an array of values
@@ -88,6 +135,7 @@ impl<'a> QueryOptions<'a> {
                             _ => {}
                         };
                     }
+                    FilterValue::Raw(_) => {}
                 };
 
                 acc
@@ -95,52 +143,49 @@ impl<'a> QueryOptions<'a> {
 
         let filters = QueryOptions::flatten_grouped_filters(grouped_filters);
 
-        let mut filters_query_vec = filters
-            .iter()
-            .filter_map(|(enumerated_key, (key, operator, value))| {
-                let variable_ident = to_variable_ident(enumerated_key);
+        let mut filters_query_vec = vec![];
 
-                match value {
-                    FilterValue::Escaped(_) => Some(format!(
-                        "{} {} {}",
-                        key,
-                        operator,
-                        format!("${}", variable_ident)
-                    )),
-                    FilterValue::Unsafe(value) => Some(format!("{} {} {}", key, operator, value)),
-                    FilterValue::EscapedList(_) => {
-                        // Ignore any operator that's not an array operator when we have an array of values
-                        match operator {
-                            Operator::ContainsAny | Operator::Inside => {
-                                Some(format!("{} {} ${}", key, operator, variable_ident))
-                            }
-                            _ => return None,
-                        }
+        for (key, i, operator, value) in filters {
+            match value {
+                FilterValue::Escaped(_) => {
+                    let name = naming.assign(&to_variable_ident(&key), i, &variables)?;
+
+                    filters_query_vec.push(format!("{} {} ${}", key, operator, name));
+                    variables.insert(name, value);
+                }
+                FilterValue::Unsafe(value) => {
+                    filters_query_vec.push(format!("{} {} {}", key, operator, value))
+                }
+                FilterValue::Session(param) => {
+                    filters_query_vec.push(format!("{} {} {}", key, operator, param))
+                }
+                FilterValue::EscapedList(_) => {
+                    // Ignore any operator that's not an array operator when we have an array of values
+                    if let Operator::ContainsAny | Operator::Inside = operator {
+                        let name = naming.assign(&to_variable_ident(&key), i, &variables)?;
+
+                        filters_query_vec.push(format!("{} {} ${}", key, operator, name));
+                        variables.insert(name, value);
                     }
                 }
-            })
-            .collect::<Vec<_>>();
+                FilterValue::Raw(_) => {}
+            }
+        }
+
+        for (_, (_, value)) in raw_filters {
+            if let FilterValue::Raw(raw) = value {
+                filters_query_vec.push(raw.merge_into(&mut variables, naming)?);
+            }
+        }
 
         filters_query_vec.sort_unstable();
 
         let filters_query = filters_query_vec.join(" AND ");
 
-        let variables = filters
-            .into_iter()
-            .filter_map(|(enumerated_key, (_, _, value))| {
-                let key = to_variable_ident(&enumerated_key);
-
-                match value {
-                    FilterValue::Escaped(_) | FilterValue::EscapedList(_) => Some((key, value)),
-                    FilterValue::Unsafe(_) => None,
-                }
-            })
-            .collect();
-
-        (
+        Ok((
             format!("WHERE {}", filters_query).into_boxed_str(),
             variables,
-        )
+        ))
     }
 
     pub fn build(
@@ -148,9 +193,32 @@ impl<'a> QueryOptions<'a> {
         table_name: &str,
         unsafe_columns: &[&str],
     ) -> (Box<str>, HashMap<Box<str>, FilterValue>) {
+        self.build_named(table_name, unsafe_columns, &BindNaming::default())
+            .expect("suffixing never collides")
+    }
+
+    /// Like [`QueryOptions::build`], with binds named by `naming`
+    pub fn build_named(
+        self,
+        table_name: &str,
+        unsafe_columns: &[&str],
+        naming: &BindNaming,
+    ) -> Result<SelectQuery, BindCollision> {
+        self.build_select(table_name, unsafe_columns, None, naming)
+    }
+
+    /// Builds the `SELECT`, with an optional `GROUP` clause that goes between `WHERE` and
+    /// `ORDER BY`
+    pub(crate) fn build_select(
+        self,
+        table_name: &str,
+        unsafe_columns: &[&str],
+        group: Option<&str>,
+        naming: &BindNaming,
+    ) -> Result<SelectQuery, BindCollision> {
         let expansions = self
             .expansions
-            .into_iter()
+            .iter()
             .filter_map(|(unsafe_key, expansion)| {
                 let key = sanitize(unsafe_key)?;
 
@@ -172,13 +240,18 @@ impl<'a> QueryOptions<'a> {
             table_name
         );
 
-        let (filters_query, variables) = QueryOptions::build_filters(self.filters);
+        let (filters_query, variables) =
+            QueryOptions::build_filters(self.filters, naming, self.extra_binds)?;
 
         if !filters_query.is_empty() {
             push_query_str(&mut query, &filters_query);
         }
 
-        if let Some(Some(order_by)) = self.order_by.map(|ob| sanitize(ob)) {
+        if let Some(group) = group {
+            push_query_str(&mut query, group);
+        }
+
+        if let Some(Some(order_by)) = self.order_by.map(order_path) {
             push_query_str(&mut query, &format!("ORDER BY {}", order_by));
 
             if let Some(order_dir) = self.order_dir {
@@ -197,7 +270,37 @@ impl<'a> QueryOptions<'a> {
             push_query_str(&mut query, format!("START {}", offset).as_str());
         }
 
-        (query.into_boxed_str(), variables)
+        Ok((query.into_boxed_str(), variables))
+    }
+
+    /// Builds a query to be used as an expansion, correlated with the record it's expanded on by
+    /// `foreign_key = $parent.id`. Its binds go in the parent's `extra_binds`, so the parent's
+    /// own binds are named around them. Expansions of the same parent need a `naming` each,
+    /// like a prefix per expansion, or their binds overwrite each other there
+    pub fn build_expansion(
+        mut self,
+        table_name: &str,
+        unsafe_columns: &[&str],
+        foreign_key: &str,
+        naming: &BindNaming,
+    ) -> Result<SelectQuery, ExpansionError> {
+        let regex = Regex::new(r"^\w+(\.\w+)*$").unwrap();
+
+        // A mangled key would correlate on the wrong field, or return every row
+        if !regex.is_match(foreign_key) {
+            return Err(ExpansionError::InvalidForeignKey(foreign_key.into()));
+        }
+
+        let mut filters = std::mem::take(&mut self.filters).0.into_vec();
+
+        filters.push((
+            foreign_key.into(),
+            (Operator::Eq, FilterValue::Unsafe("$parent.id".into())),
+        ));
+
+        self.filters = Filters(filters.into_boxed_slice());
+
+        Ok(self.build_named(table_name, unsafe_columns, naming)?)
     }
 }
 
@@ -206,7 +309,16 @@ fn push_query_str(query: &mut String, value: &str) {
     query.push_str(value);
 }
 
-fn sanitize(value: &str) -> Option<&str> {
+/// Resolves a filter key to the one the query filters on: a whitelisted function or arithmetic,
+/// normalized, or else the sanitized field, like `email` for ` email `
+pub(crate) fn resolve_key(unsafe_key: &str) -> Option<Box<str>> {
+    match function_key(unsafe_key).or_else(|| arithmetic_key(unsafe_key)) {
+        Some(key) => Some(key),
+        None => sanitize(unsafe_key).map(Into::into),
+    }
+}
+
+pub(crate) fn sanitize(value: &str) -> Option<&str> {
     let regex = Regex::new(r"[\w\.]+").unwrap();
 
     let value = regex.captures(value)?.get(0)?.as_str();
@@ -214,6 +326,18 @@ fn sanitize(value: &str) -> Option<&str> {
     Some(value)
 }
 
+/// Turns a filter key into a variable name, like `tag.name` into `tag_name`,
+/// `string::lowercase(email)` into `string_lowercase_email` and `price * quantity` into
+/// `price_times_quantity`
 fn to_variable_ident(value: &str) -> Box<str> {
-    value.replace('.', "_").into_boxed_str()
+    let regex = Regex::new(r"\W+").unwrap();
+
+    let value = value
+        .trim_end_matches(|c: char| !c.is_alphanumeric() && c != '_')
+        .replace(" + ", "_plus_")
+        .replace(" - ", "_minus_")
+        .replace(" * ", "_times_")
+        .replace(" / ", "_div_");
+
+    regex.replace_all(&value, "_").into_owned().into_boxed_str()
 }
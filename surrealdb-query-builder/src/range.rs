@@ -0,0 +1,67 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    ops::{Bound, RangeBounds},
+};
+
+use regex::Regex;
+use surrealdb::sql::Id;
+
+use crate::{cleanup::InvalidTable, filters::FilterValue, query_options::QueryOptions};
+
+/// A range of record ids of one table, like `order:⟨2024-01⟩..⟨2024-02⟩`, which SurrealDB scans
+/// by key instead of filtering the whole table
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordRange {
+    table: Box<str>,
+    start: Bound<Id>,
+    end: Bound<Id>,
+}
+
+impl RecordRange {
+    /// Takes any range of ids, like `Id::from("a")..=Id::from("z")` or a `(Bound, Bound)` for
+    /// an excluded start
+    pub fn new(table: &str, range: impl RangeBounds<Id>) -> Result<Self, InvalidTable> {
+        let regex = Regex::new(r"^\w+$").unwrap();
+
+        if !regex.is_match(table) {
+            return Err(InvalidTable(table.into()));
+        }
+
+        Ok(Self {
+            table: table.into(),
+            start: range.start_bound().cloned(),
+            end: range.end_bound().cloned(),
+        })
+    }
+}
+
+// Ids can't be parameters inside a range, so they're escaped by the SDK's `Id` instead
+impl Display for RecordRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:", self.table)?;
+
+        match &self.start {
+            Bound::Included(id) => write!(f, "{}..", id)?,
+            Bound::Excluded(id) => write!(f, "{}>..", id)?,
+            Bound::Unbounded => write!(f, "..")?,
+        }
+
+        match &self.end {
+            Bound::Included(id) => write!(f, "={}", id),
+            Bound::Excluded(id) => write!(f, "{}", id),
+            Bound::Unbounded => Ok(()),
+        }
+    }
+}
+
+impl<'a> QueryOptions<'a> {
+    /// Builds the query of `build` over the records in `range` instead of a whole table
+    pub fn build_range(
+        self,
+        range: &RecordRange,
+        unsafe_columns: &[&str],
+    ) -> (Box<str>, HashMap<Box<str>, FilterValue>) {
+        self.build(&range.to_string(), unsafe_columns)
+    }
+}
@@ -0,0 +1,141 @@
+use std::fmt::Display;
+
+use regex::Regex;
+use serde::Serialize;
+use surreal_error_parser::QueryError;
+use surrealdb::opt::auth::Scope;
+
+/// Builds a `DEFINE SCOPE` whose signup creates a record with an identifier and an argon2 hash
+/// of the password, and whose signin looks it up by both. The credentials are the `$identifier`
+/// and `$password` of [`ScopeCredentials`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DefineScopeOptions<'a> {
+    pub name: &'a str,
+    pub table: &'a str,
+    /// The field the identifier is stored in, like `email`
+    pub identifier_field: &'a str,
+    /// The field the password hash is stored in
+    pub password_field: &'a str,
+    /// How long a session lasts, like `24h`
+    pub session: &'a str,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DefineScopeError {
+    InvalidIdent(Box<str>),
+    InvalidDuration(Box<str>),
+}
+
+impl Display for DefineScopeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DefineScopeError::InvalidIdent(ident) => write!(f, "invalid identifier {:?}", ident),
+            DefineScopeError::InvalidDuration(duration) => {
+                write!(f, "invalid duration {:?}", duration)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DefineScopeError {}
+
+impl<'a> DefineScopeOptions<'a> {
+    pub fn build(self) -> Result<Box<str>, DefineScopeError> {
+        let ident_regex = Regex::new(r"^\w+$").unwrap();
+        let duration_regex = Regex::new(r"^(\d+(ns|us|µs|ms|s|m|h|d|w|y))+$").unwrap();
+
+        for ident in [
+            self.name,
+            self.table,
+            self.identifier_field,
+            self.password_field,
+        ] {
+            if !ident_regex.is_match(ident) {
+                return Err(DefineScopeError::InvalidIdent(ident.into()));
+            }
+        }
+
+        if !duration_regex.is_match(self.session) {
+            return Err(DefineScopeError::InvalidDuration(self.session.into()));
+        }
+
+        let DefineScopeOptions {
+            name,
+            table,
+            identifier_field,
+            password_field,
+            session,
+        } = self;
+
+        Ok(format!(
+            "DEFINE SCOPE {name} SESSION {session} \
+             SIGNUP (CREATE {table} SET {identifier_field} = $identifier, \
+             {password_field} = crypto::argon2::generate($password)) \
+             SIGNIN (SELECT * FROM {table} WHERE {identifier_field} = $identifier \
+             AND crypto::argon2::compare({password_field}, $password))"
+        )
+        .into_boxed_str())
+    }
+}
+
+/// The params of a signup or signin to a scope defined with [`DefineScopeOptions`]. They're sent
+/// as variables, never written into a query
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ScopeCredentials<'a> {
+    pub identifier: &'a str,
+    pub password: &'a str,
+}
+
+impl<'a> ScopeCredentials<'a> {
+    /// The credentials for `Surreal::signup` and `Surreal::signin`
+    pub fn scope(
+        self,
+        namespace: &'a str,
+        database: &'a str,
+        scope: &'a str,
+    ) -> Scope<'a, ScopeCredentials<'a>> {
+        Scope {
+            namespace,
+            database,
+            scope,
+            params: self,
+        }
+    }
+}
+
+/// Why a signup or signin to a scope failed
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScopeAuthError {
+    /// The signin query found no record, or the password didn't match
+    InvalidCredentials,
+    /// The signup query failed, usually because the identifier is taken
+    SignupFailed,
+    /// The scope, when the error names it
+    ScopeNotFound(Option<String>),
+    Other(QueryError),
+}
+
+impl From<QueryError> for ScopeAuthError {
+    fn from(error: QueryError) -> Self {
+        match error {
+            // Embedded engines report a signin that found no record as such
+            QueryError::InvalidAuth | QueryError::SigninQueryFailed | QueryError::NoRecordFound => {
+                ScopeAuthError::InvalidCredentials
+            }
+            QueryError::InvalidSignup | QueryError::SignupQueryFailed => {
+                ScopeAuthError::SignupFailed
+            }
+            QueryError::ScNotFound { value } => ScopeAuthError::ScopeNotFound(Some(value)),
+            QueryError::NoScopeFound => ScopeAuthError::ScopeNotFound(None),
+            error => ScopeAuthError::Other(error),
+        }
+    }
+}
+
+impl ScopeAuthError {
+    /// Classifies the error of a signup or signin, or returns `None` when the client library
+    /// raised it, like for a lost connection
+    pub fn from_surrealdb(error: &surrealdb::Error) -> Option<Self> {
+        QueryError::try_from(error).ok().map(ScopeAuthError::from)
+    }
+}
@@ -0,0 +1,186 @@
+use std::{collections::HashMap, fmt::Display};
+
+use regex::Regex;
+use serde::de::DeserializeOwned;
+use surrealdb::{sql::Thing, Connection, Surreal};
+
+use crate::filters::FilterValue;
+
+#[derive(Debug)]
+pub enum ReservationError {
+    InvalidField(Box<str>),
+    /// There's nothing to reserve
+    Empty,
+    /// The record has less than `quantity` left, or doesn't exist. Nothing was reserved
+    OutOfStock {
+        record: Thing,
+        quantity: u64,
+    },
+    Db(surrealdb::Error),
+}
+
+impl Display for ReservationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReservationError::InvalidField(field) => write!(f, "{:?} is not a field", field),
+            ReservationError::Empty => write!(f, "nothing to reserve"),
+            ReservationError::OutOfStock { record, quantity } => {
+                write!(f, "{} has less than {} in stock", record, quantity)
+            }
+            ReservationError::Db(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ReservationError {}
+
+impl From<surrealdb::Error> for ReservationError {
+    fn from(error: surrealdb::Error) -> Self {
+        ReservationError::Db(error)
+    }
+}
+
+pub type ReservationQuery = (Box<str>, HashMap<Box<str>, FilterValue>);
+
+/// Takes quantities off the stock of records, but only if every one of them has enough left
+#[derive(Debug, Clone)]
+pub struct StockReservation {
+    /// The numeric field holding what's left, `stock` by default
+    pub field: Box<str>,
+    pub lines: Vec<(Thing, u64)>,
+}
+
+impl Default for StockReservation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StockReservation {
+    pub fn new() -> Self {
+        Self {
+            field: "stock".into(),
+            lines: vec![],
+        }
+    }
+
+    pub fn field(mut self, field: &str) -> Self {
+        self.field = field.into();
+
+        self
+    }
+
+    pub fn add(mut self, record: Thing, quantity: u64) -> Self {
+        self.lines.push((record, quantity));
+
+        self
+    }
+
+    /// Builds `UPDATE $record0 SET stock -= $quantity0 WHERE stock >= $quantity0 RETURN AFTER`
+    /// for a single record. More records are updated in a transaction that throws, and so
+    /// rolls back, as soon as one of them is short. Run it with [`StockReservation::reserve`]
+    pub fn build(&self) -> Result<ReservationQuery, ReservationError> {
+        let field_regex = Regex::new(r"^\w+(\.\w+)*$").unwrap();
+
+        if !field_regex.is_match(&self.field) {
+            return Err(ReservationError::InvalidField(self.field.clone()));
+        }
+
+        if self.lines.is_empty() {
+            return Err(ReservationError::Empty);
+        }
+
+        let mut variables = HashMap::new();
+
+        let updates = self
+            .lines
+            .iter()
+            .enumerate()
+            .map(|(i, (record, quantity))| {
+                variables.insert(format!("record{}", i).into(), record.clone().into());
+                variables.insert(format!("quantity{}", i).into(), (*quantity).into());
+
+                format!(
+                    "UPDATE $record{i} SET {field} -= $quantity{i} WHERE {field} >= $quantity{i} RETURN AFTER",
+                    i = i,
+                    field = self.field
+                )
+            })
+            .collect::<Vec<_>>();
+
+        if let [update] = updates.as_slice() {
+            return Ok((update.as_str().into(), variables));
+        }
+
+        // Each record takes three statements, the last of which returns it
+        let statements = updates
+            .iter()
+            .enumerate()
+            .map(|(i, update)| {
+                format!(
+                    "LET $reserved{i} = {update}; IF !$reserved{i} {{ THROW 'out of stock' }}; $reserved{i};",
+                    i = i,
+                    update = update
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        Ok((
+            format!("BEGIN TRANSACTION; {} COMMIT TRANSACTION", statements).into_boxed_str(),
+            variables,
+        ))
+    }
+
+    /// Reserves every line or none of them, returning the updated records in order
+    pub async fn reserve<C: Connection, T: DeserializeOwned>(
+        &self,
+        db: &Surreal<C>,
+    ) -> Result<Vec<T>, ReservationError> {
+        let (query, variables) = self.build()?;
+
+        let mut response = db.query(query.as_ref()).bind(variables).await?;
+
+        if let [(record, quantity)] = self.lines.as_slice() {
+            let mut records: Vec<T> = response.take(0)?;
+
+            return match records.pop() {
+                Some(record) => Ok(vec![record]),
+                None => Err(ReservationError::OutOfStock {
+                    record: record.clone(),
+                    quantity: *quantity,
+                }),
+            };
+        }
+
+        let mut errors = response.take_errors();
+
+        let thrown = errors.iter().find_map(|(index, error)| {
+            matches!(error, surrealdb::Error::Db(surrealdb::error::Db::Thrown(_)))
+                .then_some(index / 3)
+        });
+
+        if let Some(line) = thrown {
+            let (record, quantity) = &self.lines[line];
+
+            return Err(ReservationError::OutOfStock {
+                record: record.clone(),
+                quantity: *quantity,
+            });
+        }
+
+        if let Some(index) = errors.keys().min().copied() {
+            return Err(errors.remove(&index).unwrap().into());
+        }
+
+        let mut records = vec![];
+
+        for i in 0..self.lines.len() {
+            let reserved: Option<T> = response.take(i * 3 + 2)?;
+
+            records.extend(reserved);
+        }
+
+        Ok(records)
+    }
+}
@@ -0,0 +1,138 @@
+use std::fmt::Display;
+
+use regex::Regex;
+
+use crate::{
+    filters::Filters,
+    limit::Limit,
+    order_dir::OrderDir,
+    query_options::{QueryOptions, SelectQuery},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SuggestError {
+    InvalidTable(Box<str>),
+    InvalidField(Box<str>),
+    /// Grams have to be at least one character, and `min_gram` at most `max_gram`
+    InvalidGrams {
+        min: u8,
+        max: u8,
+    },
+}
+
+impl Display for SuggestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SuggestError::InvalidTable(table) => write!(f, "{:?} is not a table name", table),
+            SuggestError::InvalidField(field) => write!(f, "{:?} is not a field", field),
+            SuggestError::InvalidGrams { min, max } => {
+                write!(f, "invalid edge n-gram lengths {}..={}", min, max)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SuggestError {}
+
+/// Search-as-you-type on a text field, which is indexed by the prefixes of its words so a
+/// partial word like `lap` already matches `laptop`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Suggest<'a> {
+    pub table: &'a str,
+    pub field: &'a str,
+    /// The shortest prefix indexed, shorter input matches nothing
+    pub min_gram: u8,
+    /// Longer words are only matched by their first `max_gram` characters
+    pub max_gram: u8,
+}
+
+impl<'a> Suggest<'a> {
+    pub fn new(table: &'a str, field: &'a str) -> Self {
+        Self {
+            table,
+            field,
+            min_gram: 2,
+            max_gram: 10,
+        }
+    }
+
+    /// The name of both the analyzer and the index, like `product_name_suggest`
+    pub fn name(&self) -> Box<str> {
+        format!("{}_{}_suggest", self.table, self.field.replace('.', "_")).into_boxed_str()
+    }
+
+    fn validate(&self) -> Result<(), SuggestError> {
+        let table_regex = Regex::new(r"^\w+$").unwrap();
+        let field_regex = Regex::new(r"^\w+(\.\w+)*$").unwrap();
+
+        if !table_regex.is_match(self.table) {
+            return Err(SuggestError::InvalidTable(self.table.into()));
+        }
+
+        if !field_regex.is_match(self.field) {
+            return Err(SuggestError::InvalidField(self.field.into()));
+        }
+
+        if self.min_gram == 0 || self.min_gram > self.max_gram {
+            return Err(SuggestError::InvalidGrams {
+                min: self.min_gram,
+                max: self.max_gram,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Builds the `DEFINE ANALYZER` with an `edgengram` filter and the `DEFINE INDEX` that
+    /// searches the field with it
+    pub fn build_definitions(&self) -> Result<Box<str>, SuggestError> {
+        self.validate()?;
+
+        let name = self.name();
+
+        Ok(format!(
+            "DEFINE ANALYZER {name} TOKENIZERS blank,class FILTERS lowercase,ascii,edgengram({min},{max}); \
+             DEFINE INDEX {name} ON {table} FIELDS {field} SEARCH ANALYZER {name} BM25",
+            name = name,
+            min = self.min_gram,
+            max = self.max_gram,
+            table = self.table,
+            field = self.field,
+        )
+        .into_boxed_str())
+    }
+}
+
+impl<'a> QueryOptions<'a> {
+    /// Builds a `SELECT` of the records whose field matches `input`, best first, with the
+    /// filters of `build` on top. Their score is projected as `score`, and ten are returned
+    /// unless `limit` says otherwise. Needs the index of [`Suggest::build_definitions`]
+    pub fn build_suggest(
+        mut self,
+        suggest: &Suggest,
+        input: &str,
+        unsafe_columns: &[&str],
+    ) -> Result<SelectQuery, SuggestError> {
+        suggest.validate()?;
+
+        let mut filters = self.filters.0.into_vec();
+
+        filters.push(
+            Filters::raw(
+                &format!("{} @1@ $input", suggest.field),
+                vec![("input", input)],
+            )
+            .expect("input is a variable name"),
+        );
+
+        self.filters = Filters(filters.into_boxed_slice());
+        self.order_by = Some("score");
+        self.order_dir = Some(OrderDir::Desc);
+        self.limit = self.limit.or(Limit::new(10));
+
+        let mut columns = unsafe_columns.to_vec();
+        columns.push("search::score(1) AS score");
+
+        Ok(self.build(suggest.table, &columns))
+    }
+}
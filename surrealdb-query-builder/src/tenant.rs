@@ -0,0 +1,134 @@
+use std::{fmt::Display, str::FromStr};
+
+use regex::Regex;
+use surrealdb::{Connection, Surreal};
+
+use crate::{
+    filters::{FilterValueKind, Filters},
+    operator::Operator,
+    query_options::QueryOptions,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidTenantName(pub Box<str>);
+
+impl Display for InvalidTenantName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:?} is not a namespace, database or scope field name",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for InvalidTenantName {}
+
+fn validate_name(name: &str) -> Result<Box<str>, InvalidTenantName> {
+    let regex = Regex::new(r"^\w+$").unwrap();
+
+    regex
+        .is_match(name)
+        .then(|| name.into())
+        .ok_or_else(|| InvalidTenantName(name.into()))
+}
+
+/// A checked namespace name, so it can't be mixed up with a database name
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Namespace(Box<str>);
+
+/// A checked database name, so it can't be mixed up with a namespace name
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Database(Box<str>);
+
+impl FromStr for Namespace {
+    type Err = InvalidTenantName;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        validate_name(name).map(Namespace)
+    }
+}
+
+impl FromStr for Database {
+    type Err = InvalidTenantName;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        validate_name(name).map(Database)
+    }
+}
+
+impl Display for Namespace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl Display for Database {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Where a tenant's data lives: its namespace and database, and optionally a field that scopes
+/// shared tables to it
+#[derive(Debug, Clone, PartialEq)]
+pub struct TenantContext {
+    pub namespace: Namespace,
+    pub database: Database,
+    scope: Option<(Box<str>, FilterValueKind)>,
+}
+
+impl TenantContext {
+    pub fn new(namespace: Namespace, database: Database) -> Self {
+        Self {
+            namespace,
+            database,
+            scope: None,
+        }
+    }
+
+    /// Scopes queries to records whose `field` equals `tenant_id`. The field has to be a plain
+    /// (dotted) field name, since a mangled one would scope queries to the wrong field or none
+    pub fn with_scope(
+        mut self,
+        field: &str,
+        tenant_id: impl Into<FilterValueKind>,
+    ) -> Result<Self, InvalidTenantName> {
+        let regex = Regex::new(r"^\w+(\.\w+)*$").unwrap();
+
+        if !regex.is_match(field) {
+            return Err(InvalidTenantName(field.into()));
+        }
+
+        self.scope = Some((field.into(), tenant_id.into()));
+
+        Ok(self)
+    }
+
+    /// The scope field and tenant id set by [`TenantContext::with_scope`]
+    pub fn scope(&self) -> Option<&(Box<str>, FilterValueKind)> {
+        self.scope.as_ref()
+    }
+
+    /// Switches `db` to the tenant's namespace and database
+    pub async fn use_on<C: Connection>(&self, db: &Surreal<C>) -> surrealdb::Result<()> {
+        db.use_ns(self.namespace.0.as_ref())
+            .use_db(self.database.0.as_ref())
+            .await
+    }
+}
+
+impl<'a> QueryOptions<'a> {
+    /// Adds the tenant's scope filter in front of the others, if it has one
+    pub fn scoped(mut self, tenant: &TenantContext) -> Self {
+        if let Some((field, tenant_id)) = &tenant.scope {
+            let mut filters = std::mem::take(&mut self.filters).0.into_vec();
+
+            filters.insert(0, (field.clone(), (Operator::Eq, tenant_id.clone().into())));
+
+            self.filters = Filters(filters.into_boxed_slice());
+        }
+
+        self
+    }
+}
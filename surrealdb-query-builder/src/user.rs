@@ -0,0 +1,122 @@
+use std::fmt::Display;
+
+use regex::Regex;
+use surrealdb::sql::Strand;
+
+/// Where a user is defined, and so what it has access to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UserLevel {
+    Root,
+    Namespace,
+    Database,
+}
+
+impl Display for UserLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UserLevel::Root => write!(f, "ROOT"),
+            UserLevel::Namespace => write!(f, "NAMESPACE"),
+            UserLevel::Database => write!(f, "DATABASE"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UserRole {
+    Owner,
+    Editor,
+    Viewer,
+}
+
+impl Display for UserRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UserRole::Owner => write!(f, "OWNER"),
+            UserRole::Editor => write!(f, "EDITOR"),
+            UserRole::Viewer => write!(f, "VIEWER"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Credential {
+    /// Hashed by the server
+    Password(Box<str>),
+    /// An existing hash, so the password itself never has to be known by the deploy
+    Passhash(Box<str>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DefineUserError {
+    InvalidName(Box<str>),
+    /// At least one role is required, users without one can't do anything
+    NoRoles,
+}
+
+impl Display for DefineUserError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DefineUserError::InvalidName(name) => write!(f, "invalid user name {:?}", name),
+            DefineUserError::NoRoles => write!(f, "a user needs at least one role"),
+        }
+    }
+}
+
+impl std::error::Error for DefineUserError {}
+
+/// Builds `DEFINE USER`. Statements that define users don't take variables, so the credential is
+/// written into the query as an escaped string
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DefineUserOptions<'a> {
+    pub name: &'a str,
+    pub level: UserLevel,
+    pub credential: Credential,
+    pub roles: &'a [UserRole],
+}
+
+impl<'a> DefineUserOptions<'a> {
+    pub fn build(self) -> Result<Box<str>, DefineUserError> {
+        validate_name(self.name)?;
+
+        if self.roles.is_empty() {
+            return Err(DefineUserError::NoRoles);
+        }
+
+        let credential = match self.credential {
+            Credential::Password(password) => {
+                format!("PASSWORD {}", Strand::from(password.as_ref()))
+            }
+            Credential::Passhash(hash) => format!("PASSHASH {}", Strand::from(hash.as_ref())),
+        };
+
+        let roles = self
+            .roles
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        Ok(format!(
+            "DEFINE USER {} ON {} {} ROLES {}",
+            self.name, self.level, credential, roles
+        )
+        .into_boxed_str())
+    }
+}
+
+/// Builds `REMOVE USER`, for rotating service accounts out
+pub fn remove_user(name: &str, level: UserLevel) -> Result<Box<str>, DefineUserError> {
+    validate_name(name)?;
+
+    Ok(format!("REMOVE USER {} ON {}", name, level).into_boxed_str())
+}
+
+fn validate_name(name: &str) -> Result<(), DefineUserError> {
+    let name_regex = Regex::new(r"^\w+$").unwrap();
+
+    if !name_regex.is_match(name) {
+        return Err(DefineUserError::InvalidName(name.into()));
+    }
+
+    Ok(())
+}